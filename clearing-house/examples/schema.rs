@@ -3,10 +3,10 @@ use std::fs::create_dir_all;
 use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
 
 use clearing_house::states::{market, state, user};
-use clearing_house::package::execute::{InstantiateMsg, ExecuteMsg};
+use clearing_house::package::execute::{InstantiateMsg, ExecuteMsg, SudoMsg};
 use clearing_house::package::queries::QueryMsg;
 use clearing_house::states::history::{CurveRecord, DepositRecord, FundingPaymentRecord, FundingRateRecord, LiquidationRecord, TradeRecord};
-use clearing_house::package::response::{UserResponse, UserPositionResponse, AdminResponse, IsExchangePausedResponse, IsFundingPausedResponse, AdminControlsPricesResponse, VaultsResponse, MarginRatioResponse, PartialLiquidationClosePercentageResponse, PartialLiquidationPenaltyPercentageResponse, FullLiquidationPenaltyPercentageResponse, PartialLiquidatorSharePercentageResponse, FullLiquidatorSharePercentageResponse, MaxDepositLimitResponse, FeeStructureResponse, CurveHistoryResponse, DepositHistoryResponse, FundingPaymentHistoryResponse, FundingRateHistoryResponse, LiquidationHistoryResponse, TradeHistoryResponse, MarketInfoResponse, LengthResponse};
+use clearing_house::package::response::{UserResponse, UserPositionResponse, AdminResponse, IsExchangePausedResponse, IsFundingPausedResponse, AdminControlsPricesResponse, VaultsResponse, MarginRatioResponse, PartialLiquidationClosePercentageResponse, PartialLiquidationPenaltyPercentageResponse, FullLiquidationPenaltyPercentageResponse, PartialLiquidatorSharePercentageResponse, FullLiquidatorSharePercentageResponse, MaxDepositLimitResponse, FeeStructureResponse, CurveHistoryResponse, DepositHistoryResponse, FundingPaymentHistoryResponse, FundingRateHistoryResponse, LiquidationHistoryResponse, TradeHistoryResponse, MarketInfoResponse, LengthResponse, RolesResponse, PendingChangeResponse, CircuitBreakerResponse, EffectiveLeverageResponse, OrderResponse, TwapOrderResponse, KeeperRewardsResponse, FundingInfoResponse, PendingFundingResponse, UserFeeTierResponse, StakingContractResponse, ReferralCodeResponse, ReferrerResponse, TradingRewardEpochResponse, UserTradingRewardsResponse};
 
 fn main() {
     let mut out_dir = current_dir().unwrap();
@@ -18,6 +18,7 @@ fn main() {
     export_schema(&schema_for!(InstantiateMsg), &out_dir);
     export_schema(&schema_for!(ExecuteMsg), &out_dir);
     export_schema(&schema_for!(QueryMsg), &out_dir);
+    export_schema(&schema_for!(SudoMsg), &out_dir);
 
     // state schema export
     export_schema(&schema_for!(CurveRecord), &out_dir);
@@ -71,4 +72,19 @@ fn main() {
     export_schema(&schema_for!(LengthResponse), &out_dir);
     export_schema(&schema_for!(TradeHistoryResponse), &out_dir);
     export_schema(&schema_for!(MarketInfoResponse), &out_dir);
+    export_schema(&schema_for!(RolesResponse), &out_dir);
+    export_schema(&schema_for!(PendingChangeResponse), &out_dir);
+    export_schema(&schema_for!(CircuitBreakerResponse), &out_dir);
+    export_schema(&schema_for!(EffectiveLeverageResponse), &out_dir);
+    export_schema(&schema_for!(OrderResponse), &out_dir);
+    export_schema(&schema_for!(TwapOrderResponse), &out_dir);
+    export_schema(&schema_for!(KeeperRewardsResponse), &out_dir);
+    export_schema(&schema_for!(FundingInfoResponse), &out_dir);
+    export_schema(&schema_for!(PendingFundingResponse), &out_dir);
+    export_schema(&schema_for!(UserFeeTierResponse), &out_dir);
+    export_schema(&schema_for!(StakingContractResponse), &out_dir);
+    export_schema(&schema_for!(ReferralCodeResponse), &out_dir);
+    export_schema(&schema_for!(ReferrerResponse), &out_dir);
+    export_schema(&schema_for!(TradingRewardEpochResponse), &out_dir);
+    export_schema(&schema_for!(UserTradingRewardsResponse), &out_dir);
 }
\ No newline at end of file
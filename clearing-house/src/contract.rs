@@ -7,17 +7,17 @@ use cosmwasm_std::{
 use cw2::set_contract_version;
 
 use crate::states::constants::*;
-use crate::states::state::{State, OrderState, FEESTRUCTURE, ORACLEGUARDRAILS, ORDERSTATE, STATE};
+use crate::states::state::{State, OrderState, FEESTRUCTURE, ORACLEGUARDRAILS, ORDERSTATE, ROLES, STATE};
 
-use crate::package::execute::{ExecuteMsg, InstantiateMsg};
+use crate::package::execute::{ExecuteMsg, InstantiateMsg, SudoMsg};
 use crate::package::helper::addr_validate_to_lower;
 use crate::package::queries::QueryMsg;
 
-use crate::package::types::{FeeStructure, OracleGuardRails};
+use crate::package::types::{FeeStructure, OracleGuardRails, Role};
 
 use crate::error::ContractError;
 
-use crate::views::{execute_admin::*, execute_user::*, query::*};
+use crate::views::{execute_admin::*, execute_sudo::*, execute_user::*, query::*};
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:clearing-house";
@@ -95,6 +95,8 @@ pub fn instantiate(
         full_liquidation_liquidator_share_denominator: 2000u64,
         max_deposit: Uint128::zero(),
         markets_length: 0u64,
+        timelock_delay: DEFAULT_TIMELOCK_DELAY,
+        pending_changes_length: 0u64,
     };
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
     STATE.save(deps.storage, &state)?;
@@ -104,6 +106,11 @@ pub fn instantiate(
     ORACLEGUARDRAILS.save(deps.storage, &oracle_gr)?;
     ORDERSTATE.save(deps.storage, &orderstate)?;
 
+    // the instantiator starts out holding every role, it can hand them out and revoke itself later
+    for role in Role::all() {
+        ROLES.save(deps.storage, role.key(), &vec![info.sender.clone()])?;
+    }
+
     Ok(Response::new()
         .add_attribute("method", "instantiate")
         .add_attribute("owner", info.sender.clone()))
@@ -143,15 +150,19 @@ pub fn execute(
             margin_ratio_partial,
             margin_ratio_maintenance,
         ),
-        ExecuteMsg::DepositCollateral { amount, referrer } => {
-            try_deposit_collateral(deps, _env, info, amount, referrer)
+        ExecuteMsg::DepositCollateral { amount, referral_code } => {
+            try_deposit_collateral(deps, _env, info, amount, referral_code)
         }
+        ExecuteMsg::RegisterReferralCode { code } => try_register_referral_code(deps, info, code),
+        ExecuteMsg::ClaimReferralRewards {} => try_claim_referral_rewards(deps, info),
+        ExecuteMsg::ClaimTradingRewards {} => try_claim_trading_rewards(deps, _env, info),
         ExecuteMsg::WithdrawCollateral { amount } => {
             try_withdraw_collateral(deps, _env, info, amount)
         }
         ExecuteMsg::OpenPosition {
             direction,
             quote_asset_amount,
+            base_asset_amount,
             market_index,
             limit_price,
         } => try_open_position(
@@ -160,25 +171,95 @@ pub fn execute(
             info,
             direction,
             quote_asset_amount,
+            base_asset_amount,
             market_index,
             limit_price,
         ),
-        // ExecuteMsg::PlaceOrder { order } => try_place_order(deps, _env, info, order),
-        // ExecuteMsg::CancelOrder {
-        //     market_index,
-        //     order_id,
-        // } => try_cancel_order(deps, _env, info, market_index, order_id),
+        ExecuteMsg::ClosePosition {
+            market_index,
+            base_asset_amount,
+            percentage,
+            limit_price,
+        } => try_close_position(
+            deps,
+            _env,
+            info,
+            market_index,
+            base_asset_amount,
+            percentage,
+            limit_price,
+        ),
+        ExecuteMsg::PlaceOrder { order } => try_place_order(deps, _env, info, order),
+        ExecuteMsg::ModifyOrder {
+            market_index,
+            order_id,
+            price,
+            oracle_price_offset,
+            base_asset_amount,
+        } => try_modify_order(
+            deps,
+            _env,
+            info,
+            market_index,
+            order_id,
+            price,
+            oracle_price_offset,
+            base_asset_amount,
+        ),
+        ExecuteMsg::CancelOrder {
+            market_index,
+            order_id,
+        } => try_cancel_order(deps, _env, info, market_index, order_id),
         // ExecuteMsg::ExpireOrders { user_address } => {
         //     try_expire_orders(deps, _env, info, user_address)
         // }
-        // ExecuteMsg::FillOrder {
-        //     order_id,
-        //     user_address,
-        //     market_index,
-        // } => try_fill_order(deps, _env, info, order_id, user_address, market_index),
-        ExecuteMsg::ClosePosition { market_index } => {
-            try_close_position(deps, _env, info, market_index)
+        ExecuteMsg::FillOrder {
+            order_id,
+            user_address,
+            market_index,
+        } => try_fill_order(deps, _env, info, order_id, user_address, market_index),
+        ExecuteMsg::PlaceTwapOrder {
+            market_index,
+            direction,
+            base_asset_amount,
+            slices,
+            interval,
+            limit_price,
+        } => try_place_twap_order(
+            deps,
+            _env,
+            info,
+            market_index,
+            direction,
+            base_asset_amount,
+            slices,
+            interval,
+            limit_price,
+        ),
+        ExecuteMsg::CancelTwapOrder { twap_order_id } => {
+            try_cancel_twap_order(deps, info, twap_order_id)
         }
+        ExecuteMsg::ExecuteTwapSlice {
+            user_address,
+            twap_order_id,
+        } => try_execute_twap_slice(deps, _env, info, user_address, twap_order_id),
+        ExecuteMsg::SetUserMaxLeverage {
+            market_index,
+            max_leverage,
+        } => try_set_user_max_leverage(deps, info, market_index, max_leverage),
+        ExecuteMsg::Batch { actions } => try_batch(deps, _env, info, actions),
+        ExecuteMsg::SetPositionTriggers {
+            market_index,
+            stop_loss_price,
+            take_profit_price,
+        } => try_set_position_triggers(
+            deps,
+            _env,
+            info,
+            market_index,
+            stop_loss_price,
+            take_profit_price,
+        ),
         ExecuteMsg::Liquidate { user, market_index } => {
             try_liquidate(deps, _env, info, user, market_index)
         }
@@ -186,11 +267,44 @@ pub fn execute(
             base_asset_reserve,
             quote_asset_reserve,
             market_index,
-        } => try_move_amm_price(deps, base_asset_reserve, quote_asset_reserve, market_index),
+        } => try_move_amm_price(
+            deps,
+            info,
+            base_asset_reserve,
+            quote_asset_reserve,
+            market_index,
+        ),
         ExecuteMsg::WithdrawFees {
             market_index,
             amount,
         } => try_withdraw_fees(deps, info, market_index, amount),
+        ExecuteMsg::FundKeeperRewardPool {
+            market_index,
+            amount,
+        } => try_fund_keeper_reward_pool(deps, info, market_index, amount),
+        ExecuteMsg::UpdateKeeperRewards { keeper_rewards } => {
+            try_update_keeper_rewards(deps, info, keeper_rewards)
+        }
+        ExecuteMsg::UpdateTradingRewardToken { reward_token } => {
+            try_update_trading_reward_token(deps, info, reward_token)
+        }
+        ExecuteMsg::ScheduleTradingRewardEpoch {
+            start_ts,
+            end_ts,
+            reward_amount,
+        } => try_schedule_trading_reward_epoch(deps, _env, info, start_ts, end_ts, reward_amount),
+        ExecuteMsg::UpdateTradingRewardEpoch {
+            epoch_id,
+            reward_amount,
+        } => try_update_trading_reward_epoch(deps, _env, info, epoch_id, reward_amount),
+        ExecuteMsg::FundPnlPool {
+            market_index,
+            amount,
+        } => try_fund_pnl_pool(deps, info, market_index, amount),
+        ExecuteMsg::FundStakingRewards {
+            market_index,
+            amount,
+        } => try_fund_staking_rewards(deps, info, market_index, amount),
         ExecuteMsg::WithdrawFromInsuranceVaultToMarket {
             market_index,
             amount,
@@ -198,21 +312,28 @@ pub fn execute(
         ExecuteMsg::RepegAMMCurve {
             new_peg_candidate,
             market_index,
-        } => try_repeg_amm_curve(deps, _env, new_peg_candidate, market_index),
+        } => try_repeg_amm_curve(deps, _env, info, new_peg_candidate, market_index),
         ExecuteMsg::UpdateAMMOracleTwap { market_index } => {
-            try_update_amm_oracle_twap(deps, _env, market_index)
+            try_update_amm_oracle_twap(deps, _env, info, market_index)
         }
         ExecuteMsg::ResetAMMOracleTwap { market_index } => {
             try_reset_amm_oracle_twap(deps, _env, market_index)
         }
         ExecuteMsg::SettleFundingPayment {} => try_settle_funding_payment(deps, _env, info),
+        ExecuteMsg::SettleFundingPaymentFor { users } => {
+            try_settle_funding_payment_for(deps, _env, info, users)
+        }
+        ExecuteMsg::SettlePnl { market_index } => try_settle_pnl(deps, _env, info, market_index),
+        ExecuteMsg::SettlePosition { market_index } => {
+            try_settle_position(deps, _env, info, market_index)
+        }
         ExecuteMsg::UpdateFundingRate { market_index } => {
-            try_update_funding_rate(deps, _env, market_index)
+            try_update_funding_rate(deps, _env, info, market_index)
         }
         ExecuteMsg::UpdateK {
             market_index,
             sqrt_k,
-        } => try_update_k(deps, _env, market_index, sqrt_k),
+        } => try_update_k(deps, _env, info, market_index, sqrt_k),
         ExecuteMsg::UpdateMarginRatio {
             market_index,
             margin_ratio_initial,
@@ -220,26 +341,31 @@ pub fn execute(
             margin_ratio_maintenance,
         } => try_update_margin_ratio(
             deps,
+            _env,
             info,
             market_index,
             margin_ratio_initial,
             margin_ratio_partial,
             margin_ratio_maintenance,
         ),
+        ExecuteMsg::UpdateImfFactor {
+            market_index,
+            imf_factor,
+        } => try_update_imf_factor(deps, _env, info, market_index, imf_factor),
         ExecuteMsg::UpdatePartialLiquidationClosePercentage { value } => {
-            try_update_partial_liquidation_close_percentage(deps, info, value)
+            try_update_partial_liquidation_close_percentage(deps, _env, info, value)
         }
         ExecuteMsg::UpdatePartialLiquidationPenaltyPercentage { value } => {
-            try_update_partial_liquidation_penalty_percentage(deps, info, value)
+            try_update_partial_liquidation_penalty_percentage(deps, _env, info, value)
         }
         ExecuteMsg::UpdateFullLiquidationPenaltyPercentage { value } => {
-            try_update_full_liquidation_penalty_percentage(deps, info, value)
+            try_update_full_liquidation_penalty_percentage(deps, _env, info, value)
         }
         ExecuteMsg::UpdatePartialLiquidationLiquidatorShareDenominator { denominator } => {
-            try_update_partial_liquidation_liquidator_share_denominator(deps, info, denominator)
+            try_update_partial_liquidation_liquidator_share_denominator(deps, _env, info, denominator)
         }
         ExecuteMsg::UpdateFullLiquidationLiquidatorShareDenominator { denominator } => {
-            try_update_full_liquidation_liquidator_share_denominator(deps, info, denominator)
+            try_update_full_liquidation_liquidator_share_denominator(deps, _env, info, denominator)
         }
         ExecuteMsg::UpdateFee {
             fee_: fee,
@@ -255,6 +381,7 @@ pub fn execute(
             referee_discount,
         } => try_update_fee(
             deps,
+            _env,
             info,
             fee,
             first_tier_minimum_balance,
@@ -268,6 +395,13 @@ pub fn execute(
             referrer_reward,
             referee_discount,
         ),
+        ExecuteMsg::UpdateFeeTiers { fee_tiers } => {
+            try_update_fee_tiers(deps, _env, info, fee_tiers)
+        }
+        ExecuteMsg::UpdateMarketFeeTiers {
+            market_index,
+            fee_tiers,
+        } => try_update_market_fee_tiers(deps, _env, info, market_index, fee_tiers),
         ExecuteMsg::UpdateOraceGuardRails {
             use_for_liquidations,
             mark_oracle_divergence,
@@ -276,6 +410,7 @@ pub fn execute(
             too_volatile_ratio,
         } => try_update_oracle_guard_rails(
             deps,
+            _env,
             info,
             use_for_liquidations,
             mark_oracle_divergence,
@@ -283,7 +418,11 @@ pub fn execute(
             confidence_interval_max_size,
             too_volatile_ratio,
         ),
-        ExecuteMsg::UpdateAdmin { admin } => try_update_admin(deps, info, admin),
+        ExecuteMsg::ProposeNewAdmin { new_admin } => {
+            try_propose_new_admin(deps, info, new_admin)
+        }
+        ExecuteMsg::AcceptAdmin {} => try_accept_admin(deps, info),
+        ExecuteMsg::CancelAdminProposal {} => try_cancel_admin_proposal(deps, info),
         ExecuteMsg::UpdateMaxDeposit { max_deposit } => {
             try_update_max_deposit(deps, info, max_deposit)
         }
@@ -312,6 +451,23 @@ pub fn execute(
             market_index,
             minimum_trade_size,
         ),
+        ExecuteMsg::UpdateCircuitBreaker {
+            market_index,
+            circuit_breaker,
+        } => try_update_circuit_breaker(deps, info, market_index, circuit_breaker),
+        ExecuteMsg::UpdateKAdjustmentParams {
+            market_index,
+            k_adjustment,
+        } => try_update_k_adjustment_params(deps, info, market_index, k_adjustment),
+        ExecuteMsg::UpdateFundingParams {
+            market_index,
+            funding_params,
+        } => try_update_funding_params(deps, info, market_index, funding_params),
+        ExecuteMsg::UpdateMarketSpread {
+            market_index,
+            base_spread,
+            max_spread,
+        } => try_update_market_spread(deps, info, market_index, base_spread, max_spread),
         ExecuteMsg::UpdateOrderState {
             min_order_quote_asset_amount,
             reward,
@@ -329,15 +485,43 @@ pub fn execute(
             oracle_source,
         } => try_update_market_oracle(deps, info, market_index, oracle, oracle_source),
         ExecuteMsg::UpdateOracleAddress { oracle } => try_update_oracle_address(deps, info, oracle),
+        ExecuteMsg::UpdateStakingContract { staking_contract } => {
+            try_update_staking_contract(deps, info, staking_contract)
+        }
         ExecuteMsg::OracleFeeder {
             market_index,
             price,
         } => try_feeding_price(deps, info, market_index, price),
+        ExecuteMsg::GrantRole { role, address } => try_grant_role(deps, info, role, address),
+        ExecuteMsg::RevokeRole { role, address } => try_revoke_role(deps, info, role, address),
+        ExecuteMsg::UpdateTimelockDelay { delay } => {
+            try_update_timelock_delay(deps, _env, info, delay)
+        }
+        ExecuteMsg::ExecutePendingChange { change_id } => {
+            try_execute_pending_change(deps, _env, change_id)
+        }
+        ExecuteMsg::CancelPendingChange { change_id } => {
+            try_cancel_pending_change(deps, info, change_id)
+        }
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn sudo(deps: DepsMut, _env: Env, msg: SudoMsg) -> Result<Response, ContractError> {
+    match msg {
+        SudoMsg::UpdateExchangePaused { exchange_paused } => {
+            try_sudo_update_exchange_paused(deps, exchange_paused)
+        }
+        SudoMsg::UpdateFundingPaused { funding_paused } => {
+            try_sudo_update_funding_paused(deps, funding_paused)
+        }
+        SudoMsg::UpdateAdmin { admin } => try_sudo_update_admin(deps, admin),
+        SudoMsg::SettleMarket { market_index } => try_sudo_settle_market(deps, market_index),
     }
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> Result<Binary, ContractError> {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> Result<Binary, ContractError> {
     match msg {
         QueryMsg::GetUser { user_address } => Ok(to_binary(&get_user(deps, user_address)?)?),
         QueryMsg::GetUserMarketPosition {
@@ -384,6 +568,77 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> Result<Binary, ContractErr
         QueryMsg::GetMarketInfo { market_index } => {
             Ok(to_binary(&get_market_info(deps, market_index)?)?)
         }
+        QueryMsg::GetRoles {} => Ok(to_binary(&get_roles(deps)?)?),
+        QueryMsg::GetPendingChanges { start_after, limit } => {
+            Ok(to_binary(&get_pending_changes(deps, start_after, limit)?)?)
+        }
+        QueryMsg::GetKeeperRewards {} => Ok(to_binary(&get_keeper_rewards(deps)?)?),
+        QueryMsg::GetStakingContract {} => Ok(to_binary(&get_staking_contract(deps)?)?),
+        QueryMsg::GetFundingInfo { market_index } => {
+            Ok(to_binary(&get_funding_info(deps, env, market_index)?)?)
+        }
+        QueryMsg::GetPendingFunding { user_address } => {
+            Ok(to_binary(&get_pending_funding(deps, user_address)?)?)
+        }
+        QueryMsg::GetUserFeeTier {
+            user_address,
+            market_index,
+        } => Ok(to_binary(&get_user_fee_tier(
+            deps,
+            env,
+            user_address,
+            market_index,
+        )?)?),
+        QueryMsg::GetCircuitBreaker { market_index } => {
+            Ok(to_binary(&get_circuit_breaker(deps, env, market_index)?)?)
+        }
+        QueryMsg::GetEffectiveLeverage {
+            market_index,
+            base_asset_amount,
+        } => Ok(to_binary(&get_effective_leverage(
+            deps,
+            market_index,
+            base_asset_amount,
+        )?)?),
+        QueryMsg::GetOpenOrders {
+            user_address,
+            market_index,
+        } => Ok(to_binary(&get_open_orders(deps, env, user_address, market_index)?)?),
+        QueryMsg::GetTwapOrders {
+            user_address,
+            start_after,
+            limit,
+        } => Ok(to_binary(&get_twap_orders(
+            deps,
+            user_address,
+            start_after,
+            limit,
+        )?)?),
+        QueryMsg::GetTradingRewardEpochs { start_after, limit } => Ok(to_binary(
+            &get_trading_reward_epochs(deps, env, start_after, limit)?,
+        )?),
+        QueryMsg::GetUserTradingRewards {
+            user_address,
+            start_after,
+            limit,
+        } => Ok(to_binary(&get_user_trading_rewards(
+            deps,
+            env,
+            user_address,
+            start_after,
+            limit,
+        )?)?),
+        QueryMsg::GetReferralCode { code } => Ok(to_binary(&get_referral_code(deps, code)?)?),
+        QueryMsg::GetReferrer {
+            referrer_address,
+            start_after,
+            limit,
+        } => Ok(to_binary(&get_referrer(
+            deps,
+            referrer_address,
+            start_after,
+            limit,
+        )?)?),
     }
 }
 
@@ -1,19 +1,21 @@
+use std::cmp::min;
 use std::ops::Mul;
 use crate::package::number::Number128;
 use integer_sqrt::IntegerSquareRoot;
-use cosmwasm_std::{DepsMut, Uint128};
+use cosmwasm_std::{DepsMut, Fraction, Uint128};
 
 use crate::error::{ContractError};
 
 use crate::package::types::SwapDirection;
 
+use crate::states::history::{CurveRecord, Type};
 use crate::states::market::{Market, MARKETS};
 
-use crate::helpers::amm::{calculate_quote_asset_amount_swapped, calculate_new_oracle_price_twap};
-use crate::states::constants::MARK_PRICE_PRECISION;
+use crate::helpers::amm::calculate_new_oracle_price_twap;
+use crate::states::constants::{MARK_PRICE_PRECISION, UPDATE_K_ALLOWED_PRICE_CHANGE};
 use crate::helpers::amm;
 use crate::helpers::position::_calculate_base_asset_value_and_pnl;
-use crate::helpers::position::asset_to_reserve_amount;
+use crate::helpers::position::{asset_to_reserve_amount, reserve_to_asset_amount};
 
 pub fn update_mark_twap(
     deps: &mut DepsMut,
@@ -80,6 +82,53 @@ pub fn update_oracle_price_twap(
     Ok(oracle_price_twap)
 }
 
+/// Rolls the circuit breaker reference price once its window has elapsed and starts a cool down
+/// if the mark price has moved too far from it. Returns whether the market is in cool down and
+/// whether mark_price_after tripped the breaker.
+pub fn update_circuit_breaker(
+    deps: &mut DepsMut,
+    market_index: u64,
+    now: u64,
+    mark_price_before: Uint128,
+    mark_price_after: Uint128,
+) -> Result<(bool, bool), ContractError> {
+    let mut market = MARKETS.load(deps.storage, market_index.to_string())?;
+    if market.circuit_breaker.max_price_move.is_zero() {
+        return Ok((market.in_cool_down(now), false));
+    }
+
+    let window_end = market
+        .circuit_breaker_reference_ts
+        .checked_add(market.circuit_breaker.window)
+        .ok_or(ContractError::MathError)?;
+    if market.circuit_breaker_reference_price.is_zero() || now >= window_end {
+        market.circuit_breaker_reference_price = if market.amm.last_mark_price_twap.is_zero() {
+            mark_price_before
+        } else {
+            market.amm.last_mark_price_twap
+        };
+        market.circuit_breaker_reference_ts = now;
+    }
+
+    let exceeded = amm::is_circuit_breaker_exceeded(
+        market.circuit_breaker_reference_price,
+        mark_price_after,
+        &market.circuit_breaker,
+    )?;
+    if exceeded {
+        market.cool_down_until_ts = now
+            .checked_add(market.circuit_breaker.cool_down_period)
+            .ok_or(ContractError::MathError)?;
+    }
+
+    let in_cool_down = market.in_cool_down(now);
+    MARKETS.update(deps.storage, market_index.to_string(), |_m| -> Result<Market, ContractError> {
+        Ok(market)
+    })?;
+
+    Ok((in_cool_down, exceeded))
+}
+
 /// To find the cost of adjusting k, compare the the net market value before and after adjusting k
 /// Increasing k costs the protocol money because it reduces slippage and improves the exit price for net market position
 /// Decreasing k costs the protocol money because it increases slippage and hurts the exit price for net market position
@@ -129,6 +178,112 @@ pub fn adjust_k_cost(deps: &mut DepsMut, market_index: u64, new_sqrt_k: Uint128)
     Ok(cost)
 }
 
+/// Grows sqrt_k when the fee pool is healthy and volume is high, shrinks it when the pool is depleted.
+/// Updates that would overspend the market's budget or move the mark price too far are skipped.
+pub fn auto_adjust_k(
+    deps: &mut DepsMut,
+    market_index: u64,
+    now: u64,
+) -> Result<Option<CurveRecord>, ContractError> {
+    let market = MARKETS.load(deps.storage, market_index.to_string())?;
+    let params = market.k_adjustment.clone();
+    if params.k_change.is_zero() {
+        return Ok(None);
+    }
+
+    let next_update_ts = market
+        .last_k_update_ts
+        .checked_add(params.update_interval)
+        .ok_or(ContractError::MathError)?;
+    if now < next_update_ts {
+        return Ok(None);
+    }
+
+    let fee_pool = market.amm.total_fee_minus_distributions;
+    let k_change = market
+        .amm
+        .sqrt_k
+        .checked_mul(Uint128::from(params.k_change.numerator()))?
+        .checked_div(Uint128::from(params.k_change.denominator()))?;
+    if k_change.is_zero() {
+        return Ok(None);
+    }
+
+    let new_sqrt_k = if fee_pool > params.fee_pool_floor
+        && market.volume_since_last_k_update >= params.volume_threshold
+    {
+        market.amm.sqrt_k.checked_add(k_change)?
+    } else if fee_pool < params.fee_pool_floor {
+        market.amm.sqrt_k.checked_sub(k_change)?
+    } else {
+        return Ok(None);
+    };
+
+    let price_before = market.amm.mark_price()?;
+    let adjustment_cost = adjust_k_cost(deps, market_index, new_sqrt_k)?;
+    let mut adjusted_market = MARKETS.load(deps.storage, market_index.to_string())?;
+    let price_after = adjusted_market.amm.mark_price()?;
+
+    let budget = min(
+        fee_pool.saturating_sub(params.fee_pool_floor),
+        params.max_cost_per_update,
+    );
+    let over_budget = adjustment_cost > 0 && adjustment_cost.unsigned_abs() > budget.u128();
+    let price_change_too_large = (price_before.u128() as i128)
+        .checked_sub(price_after.u128() as i128)
+        .ok_or(ContractError::MathError)?
+        .unsigned_abs()
+        .gt(&UPDATE_K_ALLOWED_PRICE_CHANGE.u128());
+
+    if over_budget || price_change_too_large {
+        // put the reserves back, adjust_k_cost already saved the new ones
+        MARKETS.save(deps.storage, market_index.to_string(), &market)?;
+        return Ok(None);
+    }
+
+    if adjustment_cost > 0 {
+        adjusted_market.amm.total_fee_minus_distributions = adjusted_market
+            .amm
+            .total_fee_minus_distributions
+            .checked_sub(Uint128::from(adjustment_cost.unsigned_abs()))?;
+    } else {
+        adjusted_market.amm.total_fee_minus_distributions = adjusted_market
+            .amm
+            .total_fee_minus_distributions
+            .checked_add(Uint128::from(adjustment_cost.unsigned_abs()))?;
+    }
+    adjusted_market.volume_since_last_k_update = Uint128::zero();
+    adjusted_market.last_k_update_ts = now;
+
+    let c = CurveRecord {
+        ts: now,
+        market_index,
+        peg_multiplier_before: market.amm.peg_multiplier,
+        peg_multiplier_after: adjusted_market.amm.peg_multiplier,
+        base_asset_reserve_before: market.amm.base_asset_reserve,
+        base_asset_reserve_after: adjusted_market.amm.base_asset_reserve,
+        quote_asset_reserve_before: market.amm.quote_asset_reserve,
+        quote_asset_reserve_after: adjusted_market.amm.quote_asset_reserve,
+        sqrt_k_before: market.amm.sqrt_k,
+        sqrt_k_after: adjusted_market.amm.sqrt_k,
+        base_asset_amount_long: Uint128::from(adjusted_market.base_asset_amount_long.i128().unsigned_abs()),
+        base_asset_amount_short: Uint128::from(adjusted_market.base_asset_amount_short.i128().unsigned_abs()),
+        base_asset_amount: adjusted_market.base_asset_amount,
+        open_interest: adjusted_market.open_interest,
+        total_fee: adjusted_market.amm.total_fee,
+        total_fee_minus_distributions: adjusted_market.amm.total_fee_minus_distributions,
+        adjustment_cost: Number128::new(adjustment_cost),
+        oracle_price: adjusted_market.amm.get_oracle_price()?.price,
+        record_type: Type::UpdateK,
+    };
+
+    MARKETS.update(deps.storage, market_index.to_string(), |_m| -> Result<Market, ContractError> {
+        Ok(adjusted_market)
+    })?;
+
+    Ok(Some(c))
+}
+
 pub fn swap_quote_asset(
     deps: &mut DepsMut,
     market_index: u64,
@@ -147,16 +302,27 @@ pub fn swap_quote_asset(
         return Err(ContractError::TradeSizeTooSmall);
     }
 
+    let (long_spread, short_spread) = amm::calculate_spread(&market, a.mark_price()?)?;
+    let spread = match direction {
+        SwapDirection::Add => long_spread,
+        SwapDirection::Remove => short_spread,
+    };
+
     let initial_base_asset_reserve = a.base_asset_reserve;
-    let (new_base_asset_reserve, new_quote_asset_reserve) = amm::calculate_swap_output(
-        quote_asset_reserve_amount,
-        a.quote_asset_reserve,
-        direction,
-        a.sqrt_k,
-    )?;
+    let (new_base_asset_reserve, new_quote_asset_reserve, quote_asset_reserve_surplus) =
+        amm::calculate_quote_swap_output_with_spread(
+            quote_asset_reserve_amount,
+            &a,
+            direction,
+            spread,
+        )?;
 
     market.amm.base_asset_reserve = new_base_asset_reserve;
     market.amm.quote_asset_reserve = new_quote_asset_reserve;
+    market.amm.total_fee_minus_distributions = market
+        .amm
+        .total_fee_minus_distributions
+        .checked_add(reserve_to_asset_amount(quote_asset_reserve_surplus, a.peg_multiplier)?)?;
 
     let base_asset_amount = (initial_base_asset_reserve.u128() as i128)
         .checked_sub(new_base_asset_reserve.u128() as i128)
@@ -178,31 +344,24 @@ pub fn swap_base_asset(
     precomputed_mark_price: Option<Uint128>
 ) -> Result<Uint128, ContractError> {
     let mut market = MARKETS.load(deps.storage, market_index.to_string())?;
-    let a = market.amm.clone();
-    
+
     update_mark_twap(deps, market_index, now, precomputed_mark_price)?;
 
-    let initial_quote_asset_reserve = a.quote_asset_reserve;
-    let (new_quote_asset_reserve, new_base_asset_reserve) = amm::calculate_swap_output(
-        base_asset_swap_amount,
-        a.base_asset_reserve,
-        direction,
-        a.sqrt_k,
-    )?;
+    let (new_base_asset_reserve, new_quote_asset_reserve, quote_asset_amount, quote_asset_surplus) =
+        amm::calculate_base_swap_output_with_spread(&market, base_asset_swap_amount, direction)?;
 
     market.amm.base_asset_reserve = new_base_asset_reserve;
     market.amm.quote_asset_reserve = new_quote_asset_reserve;
+    market.amm.total_fee_minus_distributions = market
+        .amm
+        .total_fee_minus_distributions
+        .checked_add(quote_asset_surplus)?;
 
     MARKETS.update(deps.storage, market_index.to_string(), |_m| -> Result<Market, ContractError> {
         Ok(market)
     })?;
 
-    calculate_quote_asset_amount_swapped(
-        initial_quote_asset_reserve,
-        new_quote_asset_reserve,
-        direction,
-        a.peg_multiplier,
-    )
+    Ok(quote_asset_amount)
 
 }
 
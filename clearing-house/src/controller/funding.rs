@@ -1,5 +1,3 @@
-use std::cmp::max;
-
 use crate::package::number::Number128;
 use cosmwasm_std::Addr;
 use cosmwasm_std::DepsMut;
@@ -18,10 +16,11 @@ use crate::states::state::STATE;
 use crate::states::user::{Position, POSITIONS, User, USERS};
 
 use crate::helpers::position::calculate_updated_collateral;
-use crate::states::constants::{
-    AMM_TO_QUOTE_PRECISION_RATIO_I128, FUNDING_PAYMENT_PRECISION, ONE_HOUR,
+use crate::states::constants::AMM_TO_QUOTE_PRECISION_RATIO_I128;
+use crate::helpers::funding::{
+    calculate_funding_payment, calculate_funding_rate, calculate_funding_rate_long_short,
+    calculate_next_funding_update_wait,
 };
-use crate::helpers::funding::{calculate_funding_payment, calculate_funding_rate_long_short};
 use crate::helpers::oracle;
 
 use crate::controller::amm;
@@ -78,6 +77,16 @@ pub fn settle_funding_payment(
                         .ok_or_else(|| (ContractError::MathError))?;
         
                     m.last_cumulative_funding_rate = Number128::new(amm_cumulative_funding_rate);
+                    m.cumulative_funding_paid = Number128::new(
+                        m.cumulative_funding_paid
+                            .i128()
+                            .checked_sub(
+                                market_funding_rate_payment
+                                    .checked_div(AMM_TO_QUOTE_PRECISION_RATIO_I128.u128() as i128)
+                                    .ok_or(ContractError::MathError)?,
+                            )
+                            .ok_or(ContractError::MathError)?,
+                    );
                     m.last_funding_rate_ts = market.amm.last_funding_rate_ts;
         
                     POSITIONS.update(
@@ -107,6 +116,33 @@ pub fn settle_funding_payment(
     Ok(fundingpay)
 }
 
+/// Records the mark/oracle spread a trade left behind. Called once per trade, the previous spread is
+/// weighted by how long it held, so the next funding rate uses a time weighted premium.
+pub fn sample_premium_index(
+    deps: &mut DepsMut,
+    market_index: u64,
+    mark_price: Uint128,
+    oracle_price: i128,
+    now: u64,
+) -> Result<(), ContractError> {
+    MARKETS.update(
+        deps.storage,
+        market_index.to_string(),
+        |m| -> Result<Market, ContractError> {
+            let mut market = m.ok_or(ContractError::MarketIndexNotInitialized)?;
+            market.accrue_premium_index(now)?;
+            market.last_premium = Number128::new(
+                (mark_price.u128() as i128)
+                    .checked_sub(oracle_price)
+                    .ok_or(ContractError::MathError)?,
+            );
+            market.last_premium_ts = now;
+            Ok(market)
+        },
+    )?;
+    Ok(())
+}
+
 pub fn update_funding_rate(
     deps: &mut DepsMut,
     market_index: u64,
@@ -114,7 +150,7 @@ pub fn update_funding_rate(
     funding_paused: bool,
     precomputed_mark_price: Option<Uint128>,
 ) -> Result<Option<FundingRateRecord>, ContractError> {
-    let mut market = MARKETS.load(deps.storage, market_index.to_string())?;
+    let market = MARKETS.load(deps.storage, market_index.to_string())?;
     let guard_rails = ORACLEGUARDRAILS.load(deps.storage)?;
 
     let time_since_last_update = now
@@ -131,61 +167,25 @@ pub fn update_funding_rate(
     let normalised_oracle_price =
         normalise_oracle_price(&market.amm, &oracle_price_data, precomputed_mark_price)?;
 
-    // round next update time to be available on the hour
-    let mut next_update_wait = market.amm.funding_period;
-    if market.amm.funding_period > 1 {
-        let last_update_delay = market
-            .amm
-            .last_funding_rate_ts
-            .rem_euclid(market.amm.funding_period);
-        if last_update_delay != 0 {
-            let max_delay_for_next_period = market
-                .amm
-                .funding_period
-                .checked_div(3)
-                .ok_or_else(|| (ContractError::MathError))?;
-            if last_update_delay > max_delay_for_next_period {
-                // too late for on the hour next period, delay to following period
-                next_update_wait = market
-                    .amm
-                    .funding_period
-                    .checked_mul(2)
-                    .ok_or_else(|| (ContractError::MathError))?
-                    .checked_sub(last_update_delay)
-                    .ok_or_else(|| (ContractError::MathError))?;
-            } else {
-                // allow update on the hour
-                next_update_wait = market
-                    .amm
-                    .funding_period
-                    .checked_sub(last_update_delay)
-                    .ok_or_else(|| (ContractError::MathError))?;
-            }
-        }
-    }
+    let next_update_wait = calculate_next_funding_update_wait(&market.amm)?;
 
-    if !funding_paused && !block_funding_rate_update && time_since_last_update >= next_update_wait {
+    // funding stops accruing once a market is settled
+    if !funding_paused
+        && !market.settled
+        && !block_funding_rate_update
+        && time_since_last_update >= next_update_wait
+    {
         let oracle_price_twap =
             amm::update_oracle_price_twap(deps, market_index, now, normalised_oracle_price)?;
         let mark_price_twap = amm::update_mark_twap(deps, market_index, now, None)?;
 
-        let one_hour_i64 = ONE_HOUR.u128() as i64;
-        let period_adjustment = (24_i64)
-            .checked_mul(one_hour_i64)
-            .ok_or_else(|| (ContractError::MathError))?
-            .checked_div(max(one_hour_i64, market.amm.funding_period as i64))
-            .ok_or_else(|| (ContractError::MathError))?;
-
-        // funding period = 1 hour, window = 1 day
-        // low periodicity => quickly updating/settled funding rates => lower funding rate payment per interval
-        let price_spread = (mark_price_twap.u128()  as i128)
-            .checked_sub(oracle_price_twap).ok_or_else(|| (ContractError::MathError))?;
+        // the twap updates above saved the market, reload it before writing it back
+        let mut market = MARKETS.load(deps.storage, market_index.to_string())?;
+        // close the window with the premium of the last trade, this adds no new sample
+        market.accrue_premium_index(now)?;
 
-        let funding_rate = price_spread
-            .checked_mul(FUNDING_PAYMENT_PRECISION.u128() as i128)
-            .ok_or_else(|| (ContractError::MathError))?
-            .checked_div(period_adjustment as i128)
-            .ok_or_else(|| (ContractError::MathError))?;
+        let (raw_funding_rate, funding_rate) =
+            calculate_funding_rate(&market, mark_price_twap, oracle_price_twap)?;
 
         let (funding_rate_long, funding_rate_short, new_total_fee_minus_distributions) =
             calculate_funding_rate_long_short(&market, funding_rate)?;
@@ -206,6 +206,8 @@ pub fn update_funding_rate(
 
         market.amm.last_funding_rate = Number128::new(funding_rate);
         market.amm.last_funding_rate_ts = now;
+        market.premium_index_sum = Number128::zero();
+        market.premium_index_duration = 0;
 
         MARKETS.update(
             deps.storage,
@@ -218,6 +220,7 @@ pub fn update_funding_rate(
                 ts: now,
                 market_index,
                 funding_rate: Number128::new(funding_rate),
+                raw_funding_rate: Number128::new(raw_funding_rate),
                 cumulative_funding_rate_long: market.amm.cumulative_funding_rate_long,
                 cumulative_funding_rate_short: market.amm.cumulative_funding_rate_short,
                 mark_price_twap,
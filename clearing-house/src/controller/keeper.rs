@@ -0,0 +1,36 @@
+use std::cmp::min;
+
+use cosmwasm_std::{Addr, DepsMut, Uint128};
+
+use crate::error::ContractError;
+use crate::states::state::KEEPER_REWARD_POOL;
+use crate::states::user::USERS;
+
+/// Credits a crank reward from the keeper reward pool to the keeper's collateral.
+/// An empty pool or a keeper without a user account skips the reward instead of failing the crank.
+pub fn pay_keeper_reward(
+    deps: &mut DepsMut,
+    keeper: &Addr,
+    reward: Uint128,
+) -> Result<Uint128, ContractError> {
+    let mut pool = KEEPER_REWARD_POOL
+        .may_load(deps.storage)?
+        .unwrap_or_default();
+    let reward = min(reward, pool.balance);
+    if reward.is_zero() {
+        return Ok(Uint128::zero());
+    }
+
+    let mut keeper_user = match USERS.may_load(deps.storage, keeper)? {
+        Some(user) => user,
+        None => return Ok(Uint128::zero()),
+    };
+    keeper_user.collateral = keeper_user.collateral.checked_add(reward)?;
+    USERS.save(deps.storage, keeper, &keeper_user)?;
+
+    pool.balance = pool.balance.checked_sub(reward)?;
+    pool.total_paid = pool.total_paid.checked_add(reward)?;
+    KEEPER_REWARD_POOL.save(deps.storage, &pool)?;
+
+    Ok(reward)
+}
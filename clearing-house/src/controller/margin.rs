@@ -1,8 +1,10 @@
-use cosmwasm_std::{Addr, DepsMut, Uint128};
+use cosmwasm_std::{Addr, Decimal, DepsMut, Fraction, Uint128};
 
 use crate::error::ContractError;
 use crate::helpers::position::{calculate_updated_collateral, calculate_slippage};
-use crate::states::constants::{MARGIN_PRECISION, MAXIMUM_MARGIN_RATIO, MINIMUM_MARGIN_RATIO};
+use crate::states::constants::{
+    AMM_RESERVE_PRECISION, IMF_SIZE_SQRT_PRECISION, MARGIN_PRECISION, MAXIMUM_MARGIN_RATIO, MINIMUM_MARGIN_RATIO,
+};
 use crate::helpers::position::{
     calculate_base_asset_value_and_pnl, calculate_base_asset_value_and_pnl_with_oracle_price,
 };
@@ -13,6 +15,8 @@ use crate::states::user::{POSITIONS, USERS};
 use crate::helpers::amm::use_oracle_price_for_margin_calculation;
 use crate::helpers::oracle::get_oracle_status;
 
+use integer_sqrt::IntegerSquareRoot;
+use std::cmp::{max, min};
 use std::ops::Div;
 
 pub fn meets_initial_margin_requirement(
@@ -33,13 +37,33 @@ pub fn meets_initial_margin_requirement(
                     continue;
                 }
                 let market = MARKETS.load(deps.storage, n.to_string())?;
+                if market.settled {
+                    // nothing is at risk once the exit price is fixed, only the pnl counts
+                    let (_, settled_pnl) = calculate_base_asset_value_and_pnl_with_oracle_price(
+                        &m,
+                        market.settlement_price.u128() as i128,
+                    )?;
+                    unrealized_pnl = unrealized_pnl
+                        .checked_add(settled_pnl)
+                        .ok_or(ContractError::HelpersError)?;
+                    continue;
+                }
                 let a = &market.amm;
+                let margin_ratio_initial = max(
+                    calculate_size_adjusted_margin_ratio(
+                        m.base_asset_amount.i128(),
+                        market.margin_ratio_initial,
+                        market.margin_ratio_initial,
+                        market.imf_factor,
+                    )?,
+                    calculate_max_leverage_margin_ratio(m.max_leverage)?,
+                );
                 let (position_base_asset_value, position_unrealized_pnl) =
                     calculate_base_asset_value_and_pnl(&m, a)?;
                 initial_margin_requirement = initial_margin_requirement
                     .checked_add(
                         position_base_asset_value
-                            .checked_mul(market.margin_ratio_initial.into())?,
+                            .checked_mul(margin_ratio_initial.into())?,
                     )?;
 
                 unrealized_pnl = unrealized_pnl
@@ -76,14 +100,31 @@ pub fn meets_partial_margin_requirement(
                     continue;
                 }
                 let market = MARKETS.load(deps.storage, n.to_string())?;
+                if market.settled {
+                    // nothing is at risk once the exit price is fixed, only the pnl counts
+                    let (_, settled_pnl) = calculate_base_asset_value_and_pnl_with_oracle_price(
+                        &m,
+                        market.settlement_price.u128() as i128,
+                    )?;
+                    unrealized_pnl = unrealized_pnl
+                        .checked_add(settled_pnl)
+                        .ok_or(ContractError::HelpersError)?;
+                    continue;
+                }
                 let a = &market.amm;
+                let margin_ratio_partial = calculate_size_adjusted_margin_ratio(
+                    m.base_asset_amount.i128(),
+                    market.margin_ratio_partial,
+                    market.margin_ratio_initial,
+                    market.imf_factor,
+                )?;
 
                 let (position_base_asset_value, position_unrealized_pnl) =
                     calculate_base_asset_value_and_pnl(&m, a)?;
                 partial_margin_requirement = partial_margin_requirement
                     .checked_add(
                         position_base_asset_value
-                            .checked_mul(market.margin_ratio_partial.into())?,
+                            .checked_mul(margin_ratio_partial.into())?,
                     )?;
 
                 unrealized_pnl = unrealized_pnl
@@ -123,7 +164,24 @@ pub fn calculate_free_collateral(
                 }
 
                 let market = MARKETS.load(deps.storage, n.to_string())?;
+                if market.settled {
+                    // nothing is at risk once the exit price is fixed, only the pnl counts
+                    let (_, settled_pnl) = calculate_base_asset_value_and_pnl_with_oracle_price(
+                        &m,
+                        market.settlement_price.u128() as i128,
+                    )?;
+                    unrealized_pnl = unrealized_pnl
+                        .checked_add(settled_pnl)
+                        .ok_or(ContractError::HelpersError)?;
+                    continue;
+                }
                 let a = &market.amm;
+                let margin_ratio_initial = calculate_size_adjusted_margin_ratio(
+                    m.base_asset_amount.i128(),
+                    market.margin_ratio_initial,
+                    market.margin_ratio_initial,
+                    market.imf_factor,
+                )?;
                 let (position_base_asset_value, position_unrealized_pnl) =
                     calculate_base_asset_value_and_pnl(&m, a)?;
 
@@ -134,7 +192,7 @@ pub fn calculate_free_collateral(
                     initial_margin_requirement = initial_margin_requirement
                         .checked_add(
                             position_base_asset_value
-                                .checked_mul(market.margin_ratio_initial.into())?,
+                                .checked_mul(margin_ratio_initial.into())?,
                         )?;
                 }
 
@@ -185,7 +243,35 @@ pub fn calculate_liquidation_status(
                 }
 
                 let market = MARKETS.load(deps.storage, n.to_string())?;
+                if market.settled {
+                    // settled positions can't be closed against the amm, they only add their pnl
+                    let (settled_base_asset_value, settled_pnl) =
+                        calculate_base_asset_value_and_pnl_with_oracle_price(
+                            &m,
+                            market.settlement_price.u128() as i128,
+                        )?;
+                    base_asset_value = base_asset_value.checked_add(settled_base_asset_value)?;
+                    unrealized_pnl = unrealized_pnl
+                        .checked_add(settled_pnl)
+                        .ok_or(ContractError::HelpersError)?;
+                    adjusted_unrealized_pnl = adjusted_unrealized_pnl
+                        .checked_add(settled_pnl)
+                        .ok_or(ContractError::HelpersError)?;
+                    continue;
+                }
                 let a = &market.amm;
+                let margin_ratio_partial = calculate_size_adjusted_margin_ratio(
+                    m.base_asset_amount.i128(),
+                    market.margin_ratio_partial,
+                    market.margin_ratio_initial,
+                    market.imf_factor,
+                )?;
+                let margin_ratio_maintenance = calculate_size_adjusted_margin_ratio(
+                    m.base_asset_amount.i128(),
+                    market.margin_ratio_maintenance,
+                    market.margin_ratio_initial,
+                    market.imf_factor,
+                )?;
                 let (amm_position_base_asset_value, amm_position_unrealized_pnl) =
                     calculate_base_asset_value_and_pnl(&m, a)?;
 
@@ -240,13 +326,13 @@ pub fn calculate_liquidation_status(
                             .ok_or_else(|| (ContractError::HelpersError))?;
 
                         market_partial_margin_requirement = (oracle_position_base_asset_value)
-                            .checked_mul(market.margin_ratio_partial.into())?;
+                            .checked_mul(margin_ratio_partial.into())?;
 
                         partial_margin_requirement = partial_margin_requirement
                             .checked_add(market_partial_margin_requirement)?;
 
                         market_maintenance_margin_requirement = oracle_position_base_asset_value
-                            .checked_mul(market.margin_ratio_maintenance.into())?;
+                            .checked_mul(margin_ratio_maintenance.into())?;
 
                         maintenance_margin_requirement = maintenance_margin_requirement
                             .checked_add(market_maintenance_margin_requirement)?;
@@ -256,13 +342,13 @@ pub fn calculate_liquidation_status(
                             .ok_or_else(|| (ContractError::HelpersError))?;
 
                         market_partial_margin_requirement = (amm_position_base_asset_value)
-                            .checked_mul(market.margin_ratio_partial.into())?;
+                            .checked_mul(margin_ratio_partial.into())?;
 
                         partial_margin_requirement = partial_margin_requirement
                             .checked_add(market_partial_margin_requirement)?;
 
                         market_maintenance_margin_requirement = amm_position_base_asset_value
-                            .checked_mul(market.margin_ratio_maintenance.into())?;
+                            .checked_mul(margin_ratio_maintenance.into())?;
 
                         maintenance_margin_requirement = maintenance_margin_requirement
                             .checked_add(market_maintenance_margin_requirement)?;
@@ -273,13 +359,13 @@ pub fn calculate_liquidation_status(
                         .ok_or_else(|| (ContractError::HelpersError))?;
 
                     market_partial_margin_requirement = (amm_position_base_asset_value)
-                        .checked_mul(market.margin_ratio_partial.into())?;
+                        .checked_mul(margin_ratio_partial.into())?;
 
                     partial_margin_requirement = partial_margin_requirement
                         .checked_add(market_partial_margin_requirement)?;
 
                     market_maintenance_margin_requirement = amm_position_base_asset_value
-                        .checked_mul(market.margin_ratio_maintenance.into())?;
+                        .checked_mul(margin_ratio_maintenance.into())?;
 
                     maintenance_margin_requirement = maintenance_margin_requirement
                         .checked_add(market_maintenance_margin_requirement)?;
@@ -387,3 +473,49 @@ pub fn validate_margin(
 
     Ok(true)
 }
+
+/// Adds a premium growing with the square root of the position size to a market margin ratio.
+/// imf_factor is in MARGIN_PRECISION per square root of one unit of base asset. The same premium is
+/// added to the initial, partial and maintenance ratio and capped so the initial ratio never exceeds
+/// MAXIMUM_MARGIN_RATIO, which keeps initial > partial > maintenance for every size.
+pub fn calculate_size_adjusted_margin_ratio(
+    base_asset_amount: i128,
+    margin_ratio: u32,
+    margin_ratio_initial: u32,
+    imf_factor: u32,
+) -> Result<u32, ContractError> {
+    if imf_factor == 0 {
+        return Ok(margin_ratio);
+    }
+
+    let size_sqrt = Uint128::from(base_asset_amount.unsigned_abs())
+        .checked_mul(IMF_SIZE_SQRT_PRECISION)?
+        .checked_mul(IMF_SIZE_SQRT_PRECISION)?
+        .checked_div(AMM_RESERVE_PRECISION)?
+        .u128()
+        .integer_sqrt();
+
+    let size_premium = Uint128::from(size_sqrt)
+        .checked_mul(Uint128::from(imf_factor))?
+        .checked_div(IMF_SIZE_SQRT_PRECISION)?;
+    let size_premium = min(
+        size_premium,
+        MAXIMUM_MARGIN_RATIO.saturating_sub(Uint128::from(margin_ratio_initial)),
+    );
+
+    let margin_ratio = Uint128::from(margin_ratio).checked_add(size_premium)?;
+    Ok(margin_ratio.u128() as u32)
+}
+
+/// Margin ratio implied by a user's leverage cap, zero when the user has not set one
+pub fn calculate_max_leverage_margin_ratio(max_leverage: Option<Decimal>) -> Result<u32, ContractError> {
+    match max_leverage {
+        Some(max_leverage) if !max_leverage.is_zero() => {
+            let margin_ratio = MARGIN_PRECISION
+                .checked_mul(Uint128::from(max_leverage.denominator()))?
+                .checked_div(Uint128::from(max_leverage.numerator()))?;
+            Ok(min(margin_ratio, MAXIMUM_MARGIN_RATIO).u128() as u32)
+        }
+        _ => Ok(0),
+    }
+}
@@ -1,6 +1,9 @@
 pub mod amm;
 pub mod funding;
+pub mod keeper;
 pub mod margin;
-// pub mod order;
+pub mod order;
 pub mod position;
-pub mod repeg;
\ No newline at end of file
+pub mod referral;
+pub mod repeg;
+pub mod trading_rewards;
\ No newline at end of file
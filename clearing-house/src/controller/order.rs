@@ -1,32 +1,32 @@
 use crate::error::ContractError;
-use crate::helpers::position::{calculate_updated_collateral, asset_to_reserve_amount};
-use crate::helpers::fees::{calculate_order_fee_tier, calculate_fee_for_order};
+use crate::helpers::position::{calculate_updated_collateral, asset_to_reserve_amount, direction_to_close_position};
+use crate::helpers::fees::{calculate_order_fee_tier, calculate_fee_for_order, calculate_fee_tier, calculate_rolling_volume, add_to_rolling_volume, get_discount_token_amount, get_fee_tiers};
 use crate::helpers::order::{validate_order, validate_order_can_be_canceled, calculate_base_asset_amount_market_can_execute, limit_price_satisfied};
 use crate::states::market::{MARKETS, Market};
-use crate::states::order::{ORDERS, get_limit_price};
-use crate::states::history::{OrderRecord, OrderAction, ORDER_HISTORY, TRADE_HISTORY, TradeRecord};
-use crate::states::state::{STATE, ORDERSTATE, FEESTRUCTURE, ORACLEGUARDRAILS, LENGTH, Length};
+use crate::states::order::{ORDERS, TWAP_ORDERS, TWAP_ORDERS_INFO, OrderInfo, TwapOrder, get_limit_price, get_position_orders};
+use crate::states::history::TradeRecord;
+use crate::states::state::{STATE, ORDERSTATE, FEESTRUCTURE, ORACLEGUARDRAILS};
 
 use crate::helpers::order::get_valid_oracle_price;
 use std::cmp::min;
 use crate::package::number::Number128;
-use crate::package::types::{Order, OrderType, PositionDirection, SwapDirection, OrderStatus, OrderParams};
+use crate::package::types::{Order, OrderType, OrderTriggerCondition, PositionDirection, SwapDirection, OrderStatus, OrderParams};
 use cosmwasm_std::{DepsMut, Addr, Uint128};
 
-use crate::helpers::amm::{calculate_swap_output, normalise_oracle_price};
+use crate::helpers::amm::{calculate_base_swap_output_with_spread, calculate_swap_output, normalise_oracle_price};
 use crate::states::constants::{
     MARGIN_PRECISION, QUOTE_PRECISION
 };
 use crate::controller::margin::calculate_free_collateral;
+use crate::controller::funding::sample_premium_index;
+use crate::controller::referral::credit_referral_reward;
+use crate::controller::trading_rewards::record_trading_fee;
 use crate::states::user::{USERS, POSITIONS, Position, User};
 use crate::helpers::{amm};
 
-use crate::controller::funding::settle_funding_payment;
-
-use super::amm::update_oracle_price_twap;
-use super::funding::update_funding_rate;
+use super::amm::{update_circuit_breaker, update_oracle_price_twap};
 use super::margin::{meets_partial_margin_requirement, meets_initial_margin_requirement};
-use super::position::{update_position_with_base_asset_amount, update_position_with_quote_asset_amount};
+use super::position::{add_new_position, add_trade_fee, update_position_with_base_asset_amount, update_position_with_quote_asset_amount};
 
 pub fn calculate_base_asset_amount_user_can_execute(
     deps: &mut DepsMut,
@@ -38,7 +38,7 @@ pub fn calculate_base_asset_amount_user_can_execute(
     let position_index = market_index;
     let market = MARKETS.load(deps.storage, market_index.to_string())?;
     
-    let order = ORDERS.load(deps.storage, ((user_addr, market_index.to_string()), order_index.to_string()))?;
+    let order = ORDERS.load(deps.storage, ((user_addr, market_index.to_string()), order_index.into()))?;
 
     let quote_asset_amount = calculate_available_quote_asset_user_can_execute(
         deps,
@@ -88,7 +88,7 @@ pub fn calculate_available_quote_asset_user_can_execute(
     let market_index = position_index;
     let market = MARKETS.load(deps.storage, market_index.to_string())?;
     
-    let order = ORDERS.load(deps.storage, ((user_addr, position_index.to_string()), order_index.to_string()))?;
+    let order = ORDERS.load(deps.storage, ((user_addr, position_index.to_string()), order_index.into()))?;
 
     let max_leverage = MARGIN_PRECISION
         .checked_div(
@@ -141,22 +141,21 @@ pub fn place_order(
     
     let market_index = params.market_index;
     let market = MARKETS.load(deps.storage, market_index.to_string())?;
-   
-    settle_funding_payment(
-        deps,
-        &user_addr.clone(),
-        now,
-    )?;
-    
+    if market.settled {
+        return Err(ContractError::MarketSettled);
+    }
+
+    let discount_token = get_discount_token_amount(deps.storage, &deps.querier, user_addr)?;
     let discount_tier = calculate_order_fee_tier(
         &fee_structure,
-        params.base_asset_amount,
+        discount_token,
     )?;
 
     // Increment open orders for existing position
     market_position.order_length = market_position.order_length.checked_add(1).ok_or_else(|| (ContractError::MathError))?;
+    market_position.next_order_id = market_position.next_order_id.checked_add(1).ok_or(ContractError::MathError)?;
 
-    let new_order_idx = market_position.order_length;
+    let new_order_idx = market_position.next_order_id;
 
     let new_order = Order {
         status: OrderStatus::Open,
@@ -186,7 +185,10 @@ pub fn place_order(
         immediate_or_cancel: false,
     };
 
-    ORDERS.save(deps.storage, ((&user_addr.clone(), position_index.to_string()), new_order_idx.to_string()),&new_order)?;
+    ORDERS.save(deps.storage, ((&user_addr.clone(), position_index.to_string()), new_order_idx.into()),&new_order)?;
+    POSITIONS.update(deps.storage, (user_addr, position_index.to_string()), |_p| -> Result<Position, ContractError> {
+        Ok(market_position)
+    })?;
 
     let valid_oracle_price = get_valid_oracle_price(
         Some(oracle),
@@ -203,27 +205,6 @@ pub fn place_order(
         valid_oracle_price
     )?;
 
-    // Add to the order history account    user.order_length = new_order_idx;
-    let mut len = LENGTH.load(deps.storage)?;
-    let order_history_info_length = len.order_history_length.checked_add(1).ok_or_else(|| (ContractError::MathError))?;
-    len.order_history_length = order_history_info_length;
-    LENGTH.update(deps.storage, |_l| -> Result<Length, ContractError> {
-        Ok(len)
-    })?;
-    ORDER_HISTORY.save(deps.storage, order_history_info_length.to_string(), &OrderRecord {
-        ts: now,
-        order: new_order,
-        user: user_addr.clone(),
-        action: OrderAction::Place,
-        filler: Addr::unchecked(""),
-        trade_record_id: 0,
-        base_asset_amount_filled: Uint128::zero(),
-        quote_asset_amount_filled: Uint128::zero(),
-        filler_reward: Uint128::zero(),
-        fee: Uint128::zero(),
-        quote_asset_amount_surplus: Uint128::zero(),
-        position_index,
-    })?;
 
     Ok(true)
 }
@@ -237,22 +218,70 @@ pub fn cancel_order(
     now: u64
 ) -> Result<bool, ContractError> {
 
-    let mut market_position = POSITIONS.load(deps.storage, (user_addr, position_index.to_string()))?;
     let oracle_guard_rails = ORACLEGUARDRAILS.load(deps.storage)?;
 
-    let order = ORDERS.load(deps.storage, ((user_addr, position_index.to_string()), order_index.to_string()))?;
+    let order = ORDERS.load(deps.storage, ((user_addr, position_index.to_string()), order_index.into()))?;
     let market = MARKETS.load(deps.storage, position_index.to_string())?;
 
-    settle_funding_payment(
-        deps,
-        user_addr, 
+    if order.status != OrderStatus::Open {
+        return Err(ContractError::OrderNotOpen);
+    }
+
+    let valid_oracle_price = get_valid_oracle_price(
+        Some(oracle),
+        &market,
+        &order,
+        &oracle_guard_rails,
         now
     )?;
 
+    validate_order_can_be_canceled(
+        &order,
+        &market,
+        valid_oracle_price,
+    )?;
+
+    remove_order(deps, user_addr, position_index, order_index)?;
+
+    Ok(true)
+}
+
+pub fn modify_order(
+    deps: &mut DepsMut,
+    user_addr: &Addr,
+    position_index: u64,
+    order_index: u64,
+    price: Option<Uint128>,
+    oracle_price_offset: Option<Number128>,
+    base_asset_amount: Option<Uint128>,
+    oracle: &Addr,
+    now: u64,
+) -> Result<bool, ContractError> {
+    let order_state = ORDERSTATE.load(deps.storage)?;
+    let oracle_guard_rails = ORACLEGUARDRAILS.load(deps.storage)?;
+    let market = MARKETS.load(deps.storage, position_index.to_string())?;
+    let mut order = ORDERS.load(deps.storage, ((user_addr, position_index.to_string()), order_index.into()))?;
+
     if order.status != OrderStatus::Open {
         return Err(ContractError::OrderNotOpen);
     }
 
+    // a fixed price and an oracle offset are mutually exclusive, setting one clears the other
+    if let Some(price) = price {
+        order.price = price;
+        order.oracle_price_offset = Number128::zero();
+    }
+    if let Some(oracle_price_offset) = oracle_price_offset {
+        order.oracle_price_offset = oracle_price_offset;
+        order.price = Uint128::zero();
+    }
+    if let Some(base_asset_amount) = base_asset_amount {
+        if base_asset_amount <= order.base_asset_amount_filled {
+            return Err(ContractError::InvalidOrder);
+        }
+        order.base_asset_amount = base_asset_amount;
+    }
+
     let valid_oracle_price = get_valid_oracle_price(
         Some(oracle),
         &market,
@@ -261,52 +290,304 @@ pub fn cancel_order(
         now
     )?;
 
-    validate_order_can_be_canceled(
+    validate_order(
         &order,
         &market,
-        valid_oracle_price,
+        &order_state,
+        valid_oracle_price
     )?;
 
-    // Add to the order history account
-    let mut len = LENGTH.load(deps.storage)?;
-    let order_history_info_length = len.order_history_length.checked_add(1).ok_or_else(|| (ContractError::MathError))?;
-    len.order_history_length = order_history_info_length;
-    LENGTH.update(deps.storage, |_l| -> Result<Length, ContractError> {
-        Ok(len)
-    })?;
-    ORDER_HISTORY.save(deps.storage, order_history_info_length.to_string(), &OrderRecord {
+    ORDERS.save(deps.storage, ((user_addr, position_index.to_string()), order_index.into()), &order)?;
+
+    Ok(true)
+}
+
+pub fn calculate_base_asset_amount_reducible(
+    deps: &DepsMut,
+    user_addr: &Addr,
+    position_index: u64,
+    direction: PositionDirection,
+) -> Result<Uint128, ContractError> {
+    let market_position = POSITIONS.load(deps.storage, (user_addr, position_index.to_string()))?;
+    let base_asset_amount = market_position.base_asset_amount.i128();
+
+    let reduces_position = base_asset_amount > 0 && direction == PositionDirection::Short
+        || base_asset_amount < 0 && direction == PositionDirection::Long;
+
+    if reduces_position {
+        Ok(Uint128::from(base_asset_amount.unsigned_abs()))
+    } else {
+        Ok(Uint128::zero())
+    }
+}
+
+pub fn set_position_triggers(
+    deps: &mut DepsMut,
+    user_addr: &Addr,
+    market_index: u64,
+    stop_loss_price: Option<Uint128>,
+    take_profit_price: Option<Uint128>,
+    now: u64,
+    oracle: &Addr,
+) -> Result<bool, ContractError> {
+    let market_position = POSITIONS.may_load(deps.storage, (user_addr, market_index.to_string()))?
+        .ok_or(ContractError::UserHasNoPositionInMarket)?;
+    let base_asset_amount = market_position.base_asset_amount.i128();
+    if base_asset_amount == 0 {
+        return Err(ContractError::UserHasNoPositionInMarket);
+    }
+
+    let mark_price = MARKETS.load(deps.storage, market_index.to_string())?.amm.mark_price()?;
+    let direction = direction_to_close_position(base_asset_amount);
+
+    // a stop loss fires when the price moves against the position, a take profit when it moves in favour
+    let (stop_loss_condition, take_profit_condition) = match direction {
+        PositionDirection::Short => (OrderTriggerCondition::Below, OrderTriggerCondition::Above),
+        PositionDirection::Long => (OrderTriggerCondition::Above, OrderTriggerCondition::Below),
+    };
+
+    // replace whatever triggers were attached to the position before
+    cancel_trigger_orders(deps, user_addr, market_index)?;
+
+    for (trigger_price, trigger_condition) in [
+        (stop_loss_price, stop_loss_condition),
+        (take_profit_price, take_profit_condition),
+    ] {
+        let trigger_price = match trigger_price {
+            Some(p) => p,
+            None => continue,
+        };
+
+        let already_triggered = match trigger_condition {
+            OrderTriggerCondition::Above => trigger_price <= mark_price,
+            OrderTriggerCondition::Below => trigger_price >= mark_price,
+        };
+        if already_triggered {
+            return Err(ContractError::InvalidPositionTrigger);
+        }
+
+        place_order(
+            deps,
+            user_addr,
+            now,
+            OrderParams {
+                order_type: OrderType::TriggerMarket,
+                direction,
+                quote_asset_amount: Uint128::zero(),
+                base_asset_amount: Uint128::from(base_asset_amount.unsigned_abs()),
+                price: Uint128::zero(),
+                market_index,
+                reduce_only: true,
+                post_only: false,
+                immediate_or_cancel: false,
+                trigger_price,
+                trigger_condition,
+                position_limit: Uint128::zero(),
+                oracle_price_offset: Number128::zero(),
+            },
+            oracle,
+        )?;
+    }
+
+    Ok(true)
+}
+
+pub fn cancel_trigger_orders(
+    deps: &mut DepsMut,
+    user_addr: &Addr,
+    position_index: u64,
+) -> Result<u64, ContractError> {
+    let mut cancelled: u64 = 0;
+    for (order_index, order) in get_position_orders(deps.storage, user_addr, position_index)? {
+        let is_trigger = order.order_type == OrderType::TriggerMarket
+            || order.order_type == OrderType::TriggerLimit;
+        if is_trigger && order.reduce_only {
+            remove_order(deps, user_addr, position_index, order_index)?;
+            cancelled += 1;
+        }
+    }
+
+    Ok(cancelled)
+}
+
+pub fn cancel_trigger_orders_if_closed(
+    deps: &mut DepsMut,
+    user_addr: &Addr,
+    position_index: u64,
+) -> Result<u64, ContractError> {
+    match POSITIONS.may_load(deps.storage, (user_addr, position_index.to_string()))? {
+        Some(p) if p.base_asset_amount.i128() == 0 => cancel_trigger_orders(deps, user_addr, position_index),
+        _ => Ok(0),
+    }
+}
+
+pub fn place_twap_order(
+    deps: &mut DepsMut,
+    user_addr: &Addr,
+    market_index: u64,
+    direction: PositionDirection,
+    base_asset_amount: Uint128,
+    slices: u64,
+    interval: u64,
+    limit_price: Option<Uint128>,
+    now: u64,
+) -> Result<u64, ContractError> {
+    USERS.load(deps.storage, user_addr)?;
+    let market = MARKETS.load(deps.storage, market_index.to_string())?;
+
+    if slices == 0 || interval == 0 {
+        return Err(ContractError::InvalidTwapOrder);
+    }
+
+    // every slice has to be a tradeable size on its own
+    let slice_base_asset_amount = base_asset_amount.checked_div(Uint128::from(slices))?;
+    if slice_base_asset_amount < market.amm.minimum_base_asset_trade_size {
+        return Err(ContractError::InvalidTwapOrder);
+    }
+
+    let mut info = TWAP_ORDERS_INFO.may_load(deps.storage)?.unwrap_or(OrderInfo { len: 0 });
+    info.len = info.len.checked_add(1).ok_or(ContractError::MathError)?;
+    TWAP_ORDERS_INFO.save(deps.storage, &info)?;
+
+    TWAP_ORDERS.save(deps.storage, (user_addr, info.len.into()), &TwapOrder {
         ts: now,
-        user: user_addr.clone(),
-        order: order,
-        action: OrderAction::Cancel,
-        filler: Addr::unchecked(""),
-        trade_record_id: 0,
+        market_index,
+        direction,
+        base_asset_amount,
         base_asset_amount_filled: Uint128::zero(),
         quote_asset_amount_filled: Uint128::zero(),
-        fee: Uint128::zero(),
-        filler_reward: Uint128::zero(),
-        quote_asset_amount_surplus: Uint128::zero(),
-        position_index,
+        slices,
+        slices_filled: 0,
+        slices_skipped: 0,
+        interval,
+        limit_price,
+        next_slice_ts: now,
     })?;
 
-    if order_index != market_position.order_length {
-        let order_to_replace = ORDERS.load(deps.storage, ((user_addr, position_index.to_string()), market_position.order_length.to_string()))?;
-        ORDERS.update(deps.storage, ((user_addr, position_index.to_string()), order_index.to_string()), |_p| -> Result<Order, ContractError> {
-            Ok(order_to_replace)
-        })?;
+    Ok(info.len)
+}
+
+pub fn execute_twap_slice(
+    deps: &mut DepsMut,
+    user_addr: &Addr,
+    filler_addr: &Addr,
+    twap_order_id: u64,
+    now: u64,
+    oracle: &Addr,
+) -> Result<(Uint128, Option<TradeRecord>), ContractError> {
+    let mut twap_order = TWAP_ORDERS.may_load(deps.storage, (user_addr, twap_order_id.into()))?
+        .ok_or(ContractError::OrderDoesNotExist)?;
+
+    if now < twap_order.next_slice_ts {
+        return Err(ContractError::TwapSliceNotDue);
     }
-    
-    ORDERS.remove(deps.storage, ((user_addr, position_index.to_string()), market_position.order_length.to_string()));
 
-    // Decrement open orders for existing position
-    market_position.order_length -= 1;
-    POSITIONS.update(deps.storage, (user_addr, position_index.to_string()), |_p| -> Result<Position, ContractError> {
-        Ok(market_position)
-    })?;
+    let market_index = twap_order.market_index;
+    let slice_base_asset_amount = twap_order.next_slice_base_asset_amount()?;
 
-    Ok(true)
+    // a slice that can't be filled within the limit price is skipped so the schedule keeps moving,
+    // otherwise the order would sit on its next slice until the market came back or it was cancelled
+    if let Some(limit_price) = twap_order.limit_price {
+        let market = MARKETS.load(deps.storage, market_index.to_string())?;
+        let swap_direction = match twap_order.direction {
+            PositionDirection::Long => SwapDirection::Remove,
+            PositionDirection::Short => SwapDirection::Add,
+        };
+        let (_, _, quote_asset_amount, _) =
+            calculate_base_swap_output_with_spread(&market, slice_base_asset_amount, swap_direction)?;
+        if !limit_price_satisfied(
+            limit_price,
+            quote_asset_amount,
+            slice_base_asset_amount,
+            twap_order.direction,
+        )? {
+            twap_order.slices_skipped += 1;
+            advance_twap_order(deps, user_addr, twap_order_id, &mut twap_order, now)?;
+            return Ok((Uint128::zero(), None));
+        }
+    }
+
+    if !POSITIONS.has(deps.storage, (user_addr, market_index.to_string())) {
+        add_new_position(deps, user_addr, market_index)?;
+    }
+
+    // each slice goes through the regular market order path so fees, margin and filler rewards apply
+    place_order(
+        deps,
+        user_addr,
+        now,
+        OrderParams {
+            order_type: OrderType::Market,
+            direction: twap_order.direction,
+            quote_asset_amount: Uint128::zero(),
+            base_asset_amount: slice_base_asset_amount,
+            price: twap_order.limit_price.unwrap_or_default(),
+            market_index,
+            reduce_only: false,
+            post_only: false,
+            immediate_or_cancel: false,
+            trigger_price: Uint128::zero(),
+            trigger_condition: OrderTriggerCondition::Above,
+            position_limit: Uint128::zero(),
+            oracle_price_offset: Number128::zero(),
+        },
+        oracle,
+    )?;
+    let order_index = POSITIONS.load(deps.storage, (user_addr, market_index.to_string()))?.next_order_id;
+
+    let (base_asset_amount, trade_record) = fill_order(
+        deps,
+        user_addr,
+        filler_addr,
+        market_index,
+        order_index,
+        now,
+    )?;
+
+    if base_asset_amount.is_zero() {
+        return Err(ContractError::CouldNotFillOrder);
+    }
+
+    let quote_asset_amount = match &trade_record {
+        Some(t) => t.quote_asset_amount,
+        None => Uint128::zero(),
+    };
+    twap_order.base_asset_amount_filled = twap_order.base_asset_amount_filled.checked_add(base_asset_amount)?;
+    twap_order.quote_asset_amount_filled = twap_order.quote_asset_amount_filled.checked_add(quote_asset_amount)?;
+    twap_order.slices_filled += 1;
+    advance_twap_order(deps, user_addr, twap_order_id, &mut twap_order, now)?;
+
+    Ok((base_asset_amount, trade_record))
+}
+
+// schedules the next slice, or drops the order once every slice was filled or skipped
+fn advance_twap_order(
+    deps: &mut DepsMut,
+    user_addr: &Addr,
+    twap_order_id: u64,
+    twap_order: &mut TwapOrder,
+    now: u64,
+) -> Result<(), ContractError> {
+    twap_order.next_slice_ts = now.checked_add(twap_order.interval).ok_or(ContractError::MathError)?;
+    if twap_order.slices_executed() >= twap_order.slices {
+        TWAP_ORDERS.remove(deps.storage, (user_addr, twap_order_id.into()));
+    } else {
+        TWAP_ORDERS.save(deps.storage, (user_addr, twap_order_id.into()), twap_order)?;
+    }
+    Ok(())
 }
 
+pub fn cancel_twap_order(
+    deps: &mut DepsMut,
+    user_addr: &Addr,
+    twap_order_id: u64,
+) -> Result<bool, ContractError> {
+    if !TWAP_ORDERS.has(deps.storage, (user_addr, twap_order_id.into())) {
+        return Err(ContractError::OrderDoesNotExist);
+    }
+    TWAP_ORDERS.remove(deps.storage, (user_addr, twap_order_id.into()));
+    Ok(true)
+}
 
 pub fn expire_orders(
     deps: &mut DepsMut,
@@ -334,82 +615,27 @@ pub fn expire_orders(
     let mut expired_order_len: u64 = 0;
     if state.markets_length > 0 {
         for i in 1..state.markets_length {
-            let market_position = POSITIONS.load(deps.storage, (user_addr,i.to_string()));
-            match market_position {
-                Ok(p) => {
-                    if p.order_length > 0 {
-                        for j in 1..p.order_length {
-                            let order = ORDERS.load(deps.storage, ((user_addr, i.to_string()), j.to_string()))?;
-                            if order.status == OrderStatus::Open {
-                                expired_order_len += 1;
-                            }
-                        }
-                    };
-                },
-                Err(_) => (),
-            };
+            for (_, order) in get_position_orders(deps.storage, user_addr, i)? {
+                if order.status == OrderStatus::Open {
+                    expired_order_len += 1;
+                }
+            }
         }
     }
     let filler_reward_per_order: u128 = filler_reward / (expired_order_len as u128);
 
     if state.markets_length > 0 {
         for i in 1..state.markets_length {
-            let market_position = POSITIONS.load(deps.storage, (user_addr,i.to_string()));
-            match market_position {
-                Ok(mut p) => {
-                    if p.clone().order_length > 0 {
-                        let mut j = 1;
-                        while j <= p.order_length {
-                            let mut order = ORDERS.load(deps.storage, ((user_addr, i.to_string()), j.to_string()))?;
-                            if order.status == OrderStatus::Init {
-                                j += 1;
-                                continue;
-                            }
-                            order.fee = order
-                            .fee
-                            .checked_add(Uint128::from(filler_reward_per_order))?;
-
-                            // Add to the order history account
-                            let mut len = LENGTH.load(deps.storage)?;
-                            let order_history_info_length = len.order_history_length.checked_add(1).ok_or_else(|| (ContractError::MathError))?;
-                            len.order_history_length = order_history_info_length;
-                            LENGTH.update(deps.storage, |_l| -> Result<Length, ContractError> {
-                                Ok(len)
-                            })?;
-                            ORDER_HISTORY.save(deps.storage, order_history_info_length.to_string(), &OrderRecord {
-                                ts: now,
-                                order: order,
-                                user: user_addr.clone(),
-                                action: OrderAction::Expire,
-                                filler: filler_addr.clone(),
-                                trade_record_id: 0,
-                                base_asset_amount_filled: Uint128::zero(),
-                                quote_asset_amount_filled: Uint128::zero(),
-                                filler_reward: Uint128::from(filler_reward_per_order),
-                                fee: Uint128::from(filler_reward_per_order),
-                                quote_asset_amount_surplus: Uint128::zero(),
-                                position_index : i,
-                            })?;
-
-                            if j != p.clone().order_length {
-                                let order_to_replace = ORDERS.load(deps.storage, ((user_addr, i.to_string()), p.clone().order_length.to_string()))?;
-                                ORDERS.update(deps.storage, ((user_addr, i.to_string()), j.to_string()), |_p| -> Result<Order, ContractError> {
-                                    Ok(order_to_replace)
-                                })?;
-                            }
-                            
-                            ORDERS.remove(deps.storage, ((user_addr, i.to_string()), p.clone().order_length.to_string()));
-
-                            p.order_length -= 1;
-                            // Decrement open orders for existing position
-                            POSITIONS.update(deps.storage, (user_addr, i.to_string()), |_position| -> Result<Position, ContractError> {
-                                Ok(p.clone())
-                            })?;
-                        }
-                    };
-                },
-                Err(_e) => (),
-            };
+            for (order_index, mut order) in get_position_orders(deps.storage, user_addr, i)? {
+                if order.status == OrderStatus::Init {
+                    continue;
+                }
+                order.fee = order
+                .fee
+                .checked_add(Uint128::from(filler_reward_per_order))?;
+
+                remove_order(deps, user_addr, i, order_index)?;
+            }
         }
     }
 
@@ -423,52 +649,46 @@ pub fn fill_order(
     position_index: u64,
     order_index: u64,
     now: u64,
-) -> Result<Uint128, ContractError> {
+) -> Result<(Uint128, Option<TradeRecord>), ContractError> {
     let state = STATE.load(deps.storage)?;
     let order_state = ORDERSTATE.load(deps.storage)?;
-    let mut user = USERS.load(deps.storage, user_addr)?;
-    let mut filler = USERS.load(deps.storage, filler_addr)?;
-    let mut market_position = POSITIONS.load(deps.storage, (user_addr, position_index.to_string()))?;
-    let order = ORDERS.load(deps.storage, ((user_addr, position_index.to_string()), order_index.to_string()))?;
+    let order = ORDERS.load(deps.storage, ((user_addr, position_index.to_string()), order_index.into()))?;
     let market_index = position_index;
     let oracle_guard_rails = ORACLEGUARDRAILS.load(deps.storage)?;
     let fee_structure = FEESTRUCTURE.load(deps.storage)?;
-    let mut market = MARKETS.load(deps.storage, market_index.to_string())?;
-    let mut referrer : Option<User> = None;
-
-    if let Some(s) = user.referrer.clone() {
-        referrer = Some(USERS.load(deps.storage, &s)?)
-    }
-    
-    
-    {
-        settle_funding_payment(
-            deps,
-            user_addr,
-            now,
-        )?;
-    }
-
-    
 
     if order.status != OrderStatus::Open {
         return Err(ContractError::OrderNotOpen);
     }
 
+    if MARKETS.load(deps.storage, market_index.to_string())?.settled {
+        return Err(ContractError::MarketSettled);
+    }
+
     let mark_price_before: Uint128;
     let oracle_mark_spread_pct_before: i128;
     let is_oracle_valid: bool;
-    let oracle_price: i128;
+    let valid_oracle_price: Option<i128>;
+    let in_cool_down_before: bool;
 
     {
+        let market = MARKETS.load(deps.storage, market_index.to_string())?;
         mark_price_before = market.amm.mark_price()?;
+        in_cool_down_before = market.in_cool_down(now);
         let oracle_price_data = &market.amm.get_oracle_price()?;
         oracle_mark_spread_pct_before = amm::calculate_oracle_mark_spread_pct(
             &market.amm,
             oracle_price_data,
             Some(mark_price_before),
         )?;
-        oracle_price = oracle_price_data.price.i128();
+        // oracle offset orders resolve their limit price against a fresh oracle price on every fill attempt
+        valid_oracle_price = get_valid_oracle_price(
+            Some(&state.oracle),
+            &market,
+            &order,
+            &oracle_guard_rails,
+            now,
+        )?;
         let normalised_price =
             normalise_oracle_price(&market.amm, oracle_price_data, Some(mark_price_before))?;
         is_oracle_valid = amm::is_oracle_valid(
@@ -485,11 +705,10 @@ pub fn fill_order(
         }
     }
 
-    let valid_oracle_price = if is_oracle_valid {
-        Some(oracle_price)
-    } else {
-        None
-    };
+    let realized_pnl_before = POSITIONS
+        .load(deps.storage, (user_addr, position_index.to_string()))?
+        .realized_pnl
+        .i128();
 
     let (
         base_asset_amount,
@@ -507,9 +726,10 @@ pub fn fill_order(
     )?;
 
     if base_asset_amount.is_zero() {
-        return Ok(Uint128::zero());
+        return Ok((Uint128::zero(), None));
     }
 
+    let mut market = MARKETS.load(deps.storage, market_index.to_string())?;
     let mark_price_after: Uint128;
     let oracle_price_after: i128;
     let oracle_mark_spread_pct_after: i128;
@@ -524,6 +744,16 @@ pub fn fill_order(
         oracle_price_after = oracle_price_data.price.i128();
     }
 
+    let (in_cool_down, circuit_breaker_tripped) = update_circuit_breaker(
+        deps,
+        market_index,
+        now,
+        mark_price_before,
+        mark_price_after,
+    )?;
+    // keep the reference price and cool down written above when the market is saved below
+    market = MARKETS.load(deps.storage, market_index.to_string())?;
+
     let is_oracle_mark_too_divergent_before = amm::is_oracle_mark_too_divergent(
         oracle_mark_spread_pct_before,
         &oracle_guard_rails,
@@ -567,11 +797,28 @@ pub fn fill_order(
         return Err(ContractError::InsufficientCollateral);
     }
 
+    if potentially_risk_increasing && in_cool_down_before {
+        return Err(ContractError::MarketInCoolDown);
+    }
+
+    if potentially_risk_increasing && circuit_breaker_tripped {
+        return Err(ContractError::CircuitBreakerTripped);
+    }
+
+    let mut user = USERS.load(deps.storage, user_addr)?;
     let discount_tier = order.discount_tier.clone();
-    let (user_fee, fee_to_market, token_discount, filler_reward, referrer_reward, referee_discount) =
+    let trailing_volume = calculate_rolling_volume(
+        &user.daily_volumes,
+        user.last_volume_update_ts,
+        now,
+    )?;
+    let fee_tiers = get_fee_tiers(deps.storage, &market)?;
+    let (_, fee_tier) = calculate_fee_tier(&fee_structure, &fee_tiers, trailing_volume);
+    let (user_fee, fee_to_market, token_discount, filler_reward, referrer_reward, referee_discount, maker_rebate) =
         calculate_fee_for_order(
             quote_asset_amount,
             &fee_structure,
+            &fee_tier,
             &order_state,
             &discount_tier,
             order.ts,
@@ -591,10 +838,20 @@ pub fn fill_order(
             .amm
             .total_fee_minus_distributions
             .checked_add(fee_to_market)?;
+        market.volume_since_last_k_update = market
+            .volume_since_last_k_update
+            .checked_add(quote_asset_amount)?;
     }
 
-    // Subtract the fee from user's collateral
-    user.collateral = Uint128::from(user.collateral.u128().checked_sub(user_fee.u128()).or(Some(0)).unwrap());
+    // Subtract the fee from user's collateral and pay out any maker rebate
+    user.collateral = user.collateral.saturating_sub(user_fee).checked_add(maker_rebate)?;
+    user.daily_volumes = add_to_rolling_volume(
+        &user.daily_volumes,
+        user.last_volume_update_ts,
+        now,
+        quote_asset_amount,
+    )?;
+    user.last_volume_update_ts = now;
 
     // Increment the user's total fee variables
     user.total_fee_paid = user
@@ -607,17 +864,30 @@ pub fn fill_order(
         .total_referee_discount
         .checked_add(referee_discount)?;
 
-    filler.collateral = filler
-        .collateral
-        .checked_add(filler_reward)?;
+    USERS.update(deps.storage, user_addr, |_u|-> Result<User, ContractError> {
+        Ok(user.clone())
+    })?;
+    let market_position = add_trade_fee(deps, user_addr, position_index, user_fee)?;
+    record_trading_fee(deps, user_addr, user_fee, now)?;
+
+    // filler reward is zero when the user fills their own order
+    if filler_addr != user_addr {
+        USERS.update(deps.storage, filler_addr, |f| -> Result<User, ContractError> {
+            let mut filler = f.ok_or(ContractError::UserDoesNotExist)?;
+            filler.collateral = filler.collateral.checked_add(filler_reward)?;
+            Ok(filler)
+        })?;
+    }
 
-    // Update the referrer's collateral with their reward
-    if let Some(mut r) = referrer.clone() {
-        r.total_referral_reward = r
-            .total_referral_reward
-            .checked_add(referrer_reward)?;
+    // Book the referrer's reward as a claimable balance
+    if let Some(referrer) = &user.referrer {
+        credit_referral_reward(deps, referrer, user_addr, referrer_reward)?;
     }
 
+    MARKETS.update(deps.storage, market_index.to_string(), |_m|-> Result<Market, ContractError> {
+        Ok(market.clone())
+    })?;
+
     {
         update_order_after_trade(
             deps,
@@ -631,14 +901,17 @@ pub fn fill_order(
         )?;
     }
 
-    // Insert trade history
-    let mut len = LENGTH.load(deps.storage)?;
-    let trade_history_info_length = len.trade_history_length.checked_add(1).ok_or_else(|| (ContractError::MathError))?;
-    len.trade_history_length = trade_history_info_length;
-    LENGTH.update(deps.storage, |_l| -> Result<Length, ContractError> {
-        Ok(len)
-    })?;
-    TRADE_HISTORY.save(deps.storage, (&user_addr, trade_history_info_length.to_string()), &TradeRecord {
+    // limit orders stay on the book until they are completely filled
+    let order = ORDERS.load(deps.storage, ((user_addr, position_index.to_string()), order_index.into()))?;
+    if order.order_type == OrderType::Market || order.base_asset_amount_filled >= order.base_asset_amount {
+        remove_order(deps, user_addr, position_index, order_index)?;
+    }
+
+    if is_oracle_valid && !is_oracle_mark_too_divergent_after {
+        sample_premium_index(deps, market_index, mark_price_after, oracle_price_after, now)?;
+    }
+
+    let trade_record = TradeRecord {
         ts: now,
         user: user_addr.clone(),
         direction: order.direction,
@@ -653,40 +926,32 @@ pub fn fill_order(
         liquidation: false,
         market_index,
         oracle_price: Number128::new(oracle_price_after),
-    })?;
-    
+        circuit_breaker_cool_down: in_cool_down,
+        realized_pnl: Number128::new(
+            market_position
+                .realized_pnl
+                .i128()
+                .checked_sub(realized_pnl_before)
+                .ok_or(ContractError::MathError)?,
+        ),
+        entry_price: market_position.entry_price,
+        cumulative_funding_paid: market_position.cumulative_funding_paid,
+        cumulative_fees_paid: market_position.cumulative_fees_paid,
+    };
 
-    // Insert Order history
-    let mut len = LENGTH.load(deps.storage)?;
-    let order_history_info_length = len.order_history_length.checked_add(1).ok_or_else(|| (ContractError::MathError))?;
-    len.order_history_length = order_history_info_length;
-    LENGTH.update(deps.storage, |_l| -> Result<Length, ContractError> {
-        Ok(len)
-    })?;
-    ORDER_HISTORY.save(deps.storage, order_history_info_length.to_string(), &OrderRecord {
-        ts: now,
-        user: user_addr.clone(),
-        order: order,
-        action: OrderAction::Fill,
-        filler: filler_addr.clone(),
-        trade_record_id: trade_history_info_length,
-        base_asset_amount_filled: base_asset_amount,
-        quote_asset_amount_filled: quote_asset_amount,
-        fee: user_fee,
-        filler_reward,
-        quote_asset_amount_surplus,
-        position_index,
-    })?;
+    Ok((base_asset_amount, Some(trade_record)))
+}
 
-    // delete order
-    if order_index != market_position.order_length {
-        let order_to_replace = ORDERS.load(deps.storage, ((user_addr, position_index.to_string()), market_position.order_length.to_string()))?;
-        ORDERS.update(deps.storage, ((user_addr, position_index.to_string()), order_index.to_string()), |_p| -> Result<Order, ContractError> {
-            Ok(order_to_replace)
-        })?;
-    }
-    
-    ORDERS.remove(deps.storage, ((user_addr, position_index.to_string()), market_position.order_length.to_string()));
+pub fn remove_order(
+    deps: &mut DepsMut,
+    user_addr: &Addr,
+    position_index: u64,
+    order_index: u64,
+) -> Result<bool, ContractError> {
+    let mut market_position = POSITIONS.load(deps.storage, (user_addr, position_index.to_string()))?;
+
+    // the freed id is not handed out again, the other orders keep theirs
+    ORDERS.remove(deps.storage, ((user_addr, position_index.to_string()), order_index.into()));
 
     // Decrement open orders for existing position
     market_position.order_length -= 1;
@@ -694,40 +959,7 @@ pub fn fill_order(
         Ok(market_position)
     })?;
 
-
-    // save user, filler, referrer, market
-    USERS.update(deps.storage, user_addr, |_u|-> Result<User, ContractError> {
-        Ok(user.clone())
-    })?;
-
-    USERS.update(deps.storage, filler_addr, |_u|-> Result<User, ContractError> {
-        Ok(filler)
-    })?;
-
-    if let Some(s) = user.referrer {
-        if let Some(r) = referrer {
-            USERS.update(deps.storage, &s, |_u|-> Result<User, ContractError> {
-                Ok(r)
-            })?;    
-        }
-    }
-
-    MARKETS.update(deps.storage, market_index.to_string(), |_m|-> Result<Market, ContractError> {
-        Ok(market.clone())
-    })?;
-
-    // Try to update the funding rate at the end of every trade
-    {
-        update_funding_rate(
-            deps,
-            market_index,
-            now,
-            state.funding_paused,
-            Some(mark_price_before),
-        )?;
-    }
-
-    Ok(base_asset_amount)
+    Ok(true)
 }
 
 pub fn execute_order(
@@ -739,7 +971,7 @@ pub fn execute_order(
     now: u64,
     value_oracle_price: Option<i128>,
 ) -> Result<(Uint128, Uint128, bool, Uint128), ContractError> {
-    let order = ORDERS.load(deps.storage, ((user_addr, market_index.to_string()), order_index.to_string()))?;
+    let order = ORDERS.load(deps.storage, ((user_addr, market_index.to_string()), order_index.into()))?;
     
     match order.order_type {
         OrderType::Market => execute_market_order(
@@ -770,7 +1002,7 @@ pub fn execute_market_order(
     mark_price_before: Uint128,
     now: u64,
 ) -> Result<(Uint128, Uint128, bool, Uint128), ContractError> {
-    let order = ORDERS.load(deps.storage, ((user_addr, market_index.to_string()), order_index.to_string()))?;
+    let order = ORDERS.load(deps.storage, ((user_addr, market_index.to_string()), order_index.into()))?;
     let market = MARKETS.load(deps.storage, market_index.to_string())?;
 
     let position_index = market_index;
@@ -849,7 +1081,7 @@ pub fn execute_non_market_order(
         return Ok((Uint128::zero(), Uint128::zero(), false, Uint128::zero()));
     }
 
-    let order = ORDERS.load(deps.storage, ((user_addr, market_index.to_string()), order_index.to_string()))?;
+    let order = ORDERS.load(deps.storage, ((user_addr, market_index.to_string()), order_index.into()))?;
     let market = MARKETS.load(deps.storage, market_index.to_string())?;
 
     // Determine the base asset amount the market can fill
@@ -870,6 +1102,14 @@ pub fn execute_non_market_order(
         base_asset_amount_user_can_execute,
     );
 
+    // reduce only orders can't fill more than what is left of the position
+    if order.reduce_only {
+        base_asset_amount = min(
+            base_asset_amount,
+            calculate_base_asset_amount_reducible(deps, user_addr, market_index, order.direction)?,
+        );
+    }
+
     if base_asset_amount < market.amm.minimum_base_asset_trade_size {
         // msg!("base asset amount too small {}", base_asset_amount);
         return Ok((Uint128::zero(), Uint128::zero(), false, Uint128::zero()));
@@ -878,13 +1118,14 @@ pub fn execute_non_market_order(
     let minimum_base_asset_trade_size = market.amm.minimum_base_asset_trade_size;
     let base_asset_amount_left_to_fill = order
         .base_asset_amount
-        .checked_sub(
+        .saturating_sub(
             order
                 .base_asset_amount_filled
                 .checked_add(base_asset_amount)?,
-        )?;
+        );
 
-    if base_asset_amount_left_to_fill.gt(&Uint128::zero())
+    if !order.reduce_only
+        && base_asset_amount_left_to_fill.gt(&Uint128::zero())
         && base_asset_amount_left_to_fill < minimum_base_asset_trade_size
     {
         base_asset_amount = base_asset_amount
@@ -939,7 +1180,7 @@ pub fn update_order_after_trade(
     quote_asset_amount: Uint128,
     fee: Uint128,
 ) -> Result<bool, ContractError>{
-    let mut order = ORDERS.load(deps.storage, ((user_addr, position_index.to_string()), order_index.to_string()))?;
+    let mut order = ORDERS.load(deps.storage, ((user_addr, position_index.to_string()), order_index.into()))?;
     order.base_asset_amount_filled = order
         .base_asset_amount_filled
         .checked_add(base_asset_amount)?;
@@ -948,7 +1189,7 @@ pub fn update_order_after_trade(
         .quote_asset_amount_filled
         .checked_add(quote_asset_amount)?;
 
-    if order.order_type != OrderType::Market {
+    if order.order_type != OrderType::Market && !order.reduce_only {
         // redundant test to make sure no min trade size remaining
         let base_asset_amount_to_fill = order
             .base_asset_amount
@@ -963,7 +1204,7 @@ pub fn update_order_after_trade(
 
     order.fee = order.fee.checked_add(fee)?;
 
-    ORDERS.update(deps.storage, ((user_addr, position_index.to_string()), order_index.to_string()), |_o| -> Result<Order, ContractError> {
+    ORDERS.update(deps.storage, ((user_addr, position_index.to_string()), order_index.into()), |_o| -> Result<Order, ContractError> {
         Ok(order)
     })?;
 
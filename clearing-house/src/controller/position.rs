@@ -1,3 +1,5 @@
+use std::cmp::min;
+
 use crate::package::number::Number128;
 use cosmwasm_std::{Addr, DepsMut, Uint128};
 
@@ -7,13 +9,18 @@ use crate::error::ContractError;
 
 use crate::helpers::amm::should_round_trade;
 use crate::helpers::order::calculate_quote_asset_amount_for_maker_order;
-use crate::helpers::position::calculate_base_asset_value_and_pnl;
+use crate::helpers::position::{
+    calculate_base_asset_value_and_pnl, calculate_base_asset_value_and_pnl_with_oracle_price,
+};
 use crate::states::market::{Market, MARKETS};
+use crate::states::history::SettlePnlRecord;
+use crate::states::state::ORACLEGUARDRAILS;
 use crate::states::user::{Position, User, POSITIONS, USERS};
 
-use crate::helpers::position::{calculate_pnl, calculate_updated_collateral};
+use crate::helpers::position::{calculate_entry_price, calculate_pnl, calculate_updated_collateral};
 
 use crate::controller::amm;
+use crate::helpers::oracle::get_oracle_status;
 
 pub fn increase(
     deps: &mut DepsMut,
@@ -67,6 +74,10 @@ pub fn increase(
             .checked_add(base_asset_acquired)
             .ok_or_else(|| (ContractError::MathError))?,
     );
+    market_position.entry_price = calculate_entry_price(
+        market_position.quote_asset_amount,
+        market_position.base_asset_amount.i128(),
+    )?;
     market.base_asset_amount = Number128::new(
         market
             .base_asset_amount
@@ -200,6 +211,13 @@ pub fn reduce(
     };
 
     user.collateral = calculate_updated_collateral(user.collateral, pnl)?;
+    market_position.realized_pnl = Number128::new(
+        market_position
+            .realized_pnl
+            .i128()
+            .checked_add(pnl)
+            .ok_or(ContractError::MathError)?,
+    );
 
     MARKETS.update(
         deps.storage,
@@ -271,6 +289,14 @@ pub fn close(
     )?;
 
     user.collateral = calculate_updated_collateral(user.collateral, pnl)?;
+    market_position.realized_pnl = Number128::new(
+        market_position
+            .realized_pnl
+            .i128()
+            .checked_add(pnl)
+            .ok_or(ContractError::MathError)?,
+    );
+    market_position.entry_price = Uint128::zero();
     market_position.last_cumulative_funding_rate = Number128::zero();
     market_position.last_funding_rate_ts = 0;
 
@@ -332,6 +358,78 @@ pub fn close(
     ))
 }
 
+/// Closes the position of a settled market at its settlement price, no amm swap takes place.
+/// Returns the quote asset value, the closed base asset amount and the realized pnl.
+pub fn close_at_settlement_price(
+    deps: &mut DepsMut,
+    user_addr: &Addr,
+    market_index: u64,
+) -> Result<(Uint128, i128, i128), ContractError> {
+    let mut user = USERS.load(deps.storage, user_addr)?;
+    let mut market = MARKETS.load(deps.storage, market_index.to_string())?;
+    if !market.settled {
+        return Err(ContractError::MarketNotSettled);
+    }
+    let mut market_position = POSITIONS
+        .may_load(deps.storage, (user_addr, market_index.to_string()))?
+        .ok_or(ContractError::UserHasNoPositionInMarket)?;
+    if market_position.base_asset_amount.i128() == 0 {
+        return Err(ContractError::UserHasNoPositionInMarket);
+    }
+
+    let (quote_asset_amount, pnl) = calculate_base_asset_value_and_pnl_with_oracle_price(
+        &market_position,
+        market.settlement_price.u128() as i128,
+    )?;
+
+    user.collateral = calculate_updated_collateral(user.collateral, pnl)?;
+    market_position.realized_pnl = Number128::new(
+        market_position
+            .realized_pnl
+            .i128()
+            .checked_add(pnl)
+            .ok_or(ContractError::MathError)?,
+    );
+    market_position.entry_price = Uint128::zero();
+    market_position.last_cumulative_funding_rate = Number128::zero();
+    market_position.last_funding_rate_ts = 0;
+    market_position.quote_asset_amount = Uint128::zero();
+
+    let base_asset_amount = market_position.base_asset_amount.i128();
+    market.open_interest = market.open_interest.checked_sub(Uint128::from(1_u128))?;
+    market.base_asset_amount = Number128::new(
+        market
+            .base_asset_amount
+            .i128()
+            .checked_sub(base_asset_amount)
+            .ok_or(ContractError::MathError)?,
+    );
+    if base_asset_amount > 0 {
+        market.base_asset_amount_long = Number128::new(
+            market
+                .base_asset_amount_long
+                .i128()
+                .checked_sub(base_asset_amount)
+                .ok_or(ContractError::MathError)?,
+        );
+    } else {
+        market.base_asset_amount_short = Number128::new(
+            market
+                .base_asset_amount_short
+                .i128()
+                .checked_sub(base_asset_amount)
+                .ok_or(ContractError::MathError)?,
+        );
+    }
+    market_position.base_asset_amount = Number128::zero();
+
+    MARKETS.save(deps.storage, market_index.to_string(), &market)?;
+    POSITIONS.save(deps.storage, (user_addr, market_index.to_string()), &market_position)?;
+    USERS.save(deps.storage, user_addr, &user)?;
+
+    Ok((quote_asset_amount, base_asset_amount, pnl))
+}
+
 pub fn add_new_position(
     deps: &mut DepsMut,
     user_addr: &Addr,
@@ -346,6 +444,12 @@ pub fn add_new_position(
         last_cumulative_repeg_rebate: Uint128::zero(),
         last_funding_rate_ts: 0,
         order_length: 0,
+        next_order_id: 0,
+        max_leverage: None,
+        entry_price: Uint128::zero(),
+        realized_pnl: Number128::zero(),
+        cumulative_funding_paid: Number128::zero(),
+        cumulative_fees_paid: Uint128::zero(),
     };
 
     POSITIONS.update(
@@ -357,6 +461,115 @@ pub fn add_new_position(
     Ok(market_index)
 }
 
+/// Moves the position's unrealized pnl into the user's collateral and resets the cost basis so the
+/// remaining unrealized pnl is what could not be settled. Profits are capped by the market's pnl pool
+/// and the collateral vault balance, losses by the user's collateral and flow into the pnl pool.
+/// Returns the settled pnl.
+pub fn settle_pnl(
+    deps: &mut DepsMut,
+    user_addr: &Addr,
+    market_index: u64,
+    vault_balance: Uint128,
+    now: u64,
+) -> Result<SettlePnlRecord, ContractError> {
+    let mut user = USERS.load(deps.storage, user_addr)?;
+    let mut market = MARKETS.load(deps.storage, market_index.to_string())?;
+    let mut market_position = POSITIONS
+        .may_load(deps.storage, (user_addr, market_index.to_string()))?
+        .ok_or(ContractError::UserHasNoPositionInMarket)?;
+    if market_position.base_asset_amount.i128() == 0 {
+        return Err(ContractError::UserHasNoPositionInMarket);
+    }
+    if market.settled {
+        return Err(ContractError::MarketSettled);
+    }
+
+    // only settle against a sane oracle, and at whichever of mark or oracle is worse for the user
+    let oracle_guard_rails = ORACLEGUARDRAILS.load(deps.storage)?;
+    let mark_price = market.amm.mark_price()?;
+    let oracle_status = get_oracle_status(&market.amm, &oracle_guard_rails, Some(mark_price))?;
+    if !oracle_status.is_valid {
+        return Err(ContractError::InvalidOracle);
+    }
+    if oracle_status.mark_too_divergent {
+        return Err(ContractError::OracleMarkSpreadLimit);
+    }
+
+    let (_, amm_unrealized_pnl) = calculate_base_asset_value_and_pnl(&market_position, &market.amm)?;
+    let (_, oracle_unrealized_pnl) = calculate_base_asset_value_and_pnl_with_oracle_price(
+        &market_position,
+        oracle_status.price_data.price.i128(),
+    )?;
+    let unrealized_pnl = min(amm_unrealized_pnl, oracle_unrealized_pnl);
+
+    let pnl_to_settle = if unrealized_pnl > 0 {
+        let max_payout = min(market.pnl_pool, vault_balance);
+        if max_payout.is_zero() {
+            return Err(ContractError::PnlPoolEmpty);
+        }
+        let pnl_to_settle = min(Uint128::from(unrealized_pnl.unsigned_abs()), max_payout);
+        market.pnl_pool = market.pnl_pool.checked_sub(pnl_to_settle)?;
+        pnl_to_settle.u128() as i128
+    } else {
+        let loss_to_settle = min(Uint128::from(unrealized_pnl.unsigned_abs()), user.collateral);
+        market.pnl_pool = market.pnl_pool.checked_add(loss_to_settle)?;
+        -(loss_to_settle.u128() as i128)
+    };
+
+    user.collateral = calculate_updated_collateral(user.collateral, pnl_to_settle)?;
+
+    // longs profit when the value rises above the cost basis, shorts when it falls below it
+    let cost_basis_change = if market_position.base_asset_amount.i128() > 0 {
+        pnl_to_settle
+    } else {
+        -pnl_to_settle
+    };
+    market_position.quote_asset_amount = Uint128::from(
+        (market_position.quote_asset_amount.u128() as i128)
+            .checked_add(cost_basis_change)
+            .ok_or(ContractError::MathError)?
+            .unsigned_abs(),
+    );
+    market_position.entry_price = calculate_entry_price(
+        market_position.quote_asset_amount,
+        market_position.base_asset_amount.i128(),
+    )?;
+    market_position.realized_pnl = Number128::new(
+        market_position
+            .realized_pnl
+            .i128()
+            .checked_add(pnl_to_settle)
+            .ok_or(ContractError::MathError)?,
+    );
+
+    MARKETS.save(deps.storage, market_index.to_string(), &market)?;
+    POSITIONS.save(deps.storage, (user_addr, market_index.to_string()), &market_position)?;
+    USERS.save(deps.storage, user_addr, &user)?;
+
+    Ok(SettlePnlRecord {
+        ts: now,
+        user: user_addr.clone(),
+        market_index,
+        pnl: Number128::new(pnl_to_settle),
+        mark_price,
+        oracle_price: oracle_status.price_data.price,
+        pnl_pool_after: market.pnl_pool,
+    })
+}
+
+/// Adds a trade fee to the position's fee accounting, returns the position for the trade record
+pub fn add_trade_fee(
+    deps: &mut DepsMut,
+    user_addr: &Addr,
+    position_index: u64,
+    fee: Uint128,
+) -> Result<Position, ContractError> {
+    let mut market_position = POSITIONS.load(deps.storage, (user_addr, position_index.to_string()))?;
+    market_position.cumulative_fees_paid = market_position.cumulative_fees_paid.checked_add(fee)?;
+    POSITIONS.save(deps.storage, (user_addr, position_index.to_string()), &market_position)?;
+    Ok(market_position)
+}
+
 pub fn increase_with_base_asset_amount(
     deps: &mut DepsMut,
     direction: PositionDirection,
@@ -428,6 +641,10 @@ pub fn increase_with_base_asset_amount(
             .checked_add(base_asset_amount)
             .ok_or_else(|| (ContractError::MathError))?,
     );
+    market_position.entry_price = calculate_entry_price(
+        market_position.quote_asset_amount,
+        market_position.base_asset_amount.i128(),
+    )?;
     market.base_asset_amount = Number128::new(
         market
             .base_asset_amount
@@ -584,6 +801,13 @@ pub fn reduce_with_base_asset_amount(
     };
 
     user.collateral = calculate_updated_collateral(user.collateral, pnl)?;
+    market_position.realized_pnl = Number128::new(
+        market_position
+            .realized_pnl
+            .i128()
+            .checked_add(pnl)
+            .ok_or(ContractError::MathError)?,
+    );
 
     MARKETS.update(
         deps.storage,
@@ -745,6 +969,12 @@ pub fn update_position_with_quote_asset_amount(
                 last_cumulative_repeg_rebate: Uint128::zero(),
                 last_funding_rate_ts: 0,
                 order_length: 0,
+                next_order_id: 0,
+                max_leverage: None,
+                entry_price: Uint128::zero(),
+                realized_pnl: Number128::zero(),
+                cumulative_funding_paid: Number128::zero(),
+                cumulative_fees_paid: Uint128::zero(),
             };
             POSITIONS.save(
                 deps.storage,
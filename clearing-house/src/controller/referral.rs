@@ -0,0 +1,66 @@
+use cosmwasm_std::{Addr, DepsMut, Uint128};
+
+use crate::error::ContractError;
+use crate::states::referral::{REFEREES, REFERRAL_CODES, REFERRERS};
+use crate::states::user::{User, USERS};
+
+/// Resolves a referral code and links the user to its referrer.
+/// A user keeps their first referrer, passing the same code again is a no-op.
+pub fn set_referrer(
+    deps: &mut DepsMut,
+    user_address: &Addr,
+    user: &mut User,
+    referral_code: String,
+) -> Result<(), ContractError> {
+    let referrer = REFERRAL_CODES
+        .may_load(deps.storage, referral_code)?
+        .ok_or(ContractError::ReferralCodeNotFound)?;
+    if &referrer == user_address {
+        return Err(ContractError::UserCantReferThemselves);
+    }
+    match &user.referrer {
+        Some(existing) if existing == &referrer => return Ok(()),
+        Some(_) => return Err(ContractError::ReferrerAlreadySet),
+        None => {}
+    }
+
+    REFERRERS.update(deps.storage, &referrer, |r| -> Result<_, ContractError> {
+        let mut r = r.unwrap_or_default();
+        r.referee_count = r.referee_count.checked_add(1).ok_or(ContractError::MathError)?;
+        Ok(r)
+    })?;
+    REFEREES.save(deps.storage, (&referrer, user_address), &Uint128::zero())?;
+    user.referrer = Some(referrer);
+    Ok(())
+}
+
+/// Books the referrer's share of a referee's fee as a claimable balance.
+/// The reward is never part of the market's fees, so it is still held by the collateral vault.
+pub fn credit_referral_reward(
+    deps: &mut DepsMut,
+    referrer: &Addr,
+    referee: &Addr,
+    reward: Uint128,
+) -> Result<(), ContractError> {
+    if reward.is_zero() {
+        return Ok(());
+    }
+
+    let mut referrer_info = REFERRERS.may_load(deps.storage, referrer)?.unwrap_or_default();
+    referrer_info.total_reward = referrer_info.total_reward.checked_add(reward)?;
+    referrer_info.unclaimed_reward = referrer_info.unclaimed_reward.checked_add(reward)?;
+    REFERRERS.save(deps.storage, referrer, &referrer_info)?;
+
+    let earned = REFEREES
+        .may_load(deps.storage, (referrer, referee))?
+        .unwrap_or_default();
+    REFEREES.save(deps.storage, (referrer, referee), &earned.checked_add(reward)?)?;
+
+    // referrers don't need a trading account to earn rewards
+    if let Some(mut referrer_user) = USERS.may_load(deps.storage, referrer)? {
+        referrer_user.total_referral_reward =
+            referrer_user.total_referral_reward.checked_add(reward)?;
+        USERS.save(deps.storage, referrer, &referrer_user)?;
+    }
+    Ok(())
+}
@@ -2,6 +2,8 @@ use cosmwasm_std::{DepsMut, Uint128};
 
 use crate::error::ContractError;
 
+use crate::package::number::Number128;
+use crate::states::history::{CurveRecord, Type};
 use crate::states::market::{MARKETS, Market};
 use crate::states::state::ORACLEGUARDRAILS;
 
@@ -136,3 +138,115 @@ pub fn adjust_peg_cost(market: &mut Market, new_peg: Uint128) -> Result<i128, Co
 
     Ok(cost)
 }
+
+/// Moves the peg towards the oracle twap without admin input. The move is shrunk so that its cost
+/// never eats into the share of fees allocated to the clearing house.
+pub fn formulaic_repeg(
+    deps: &mut DepsMut,
+    market_index: u64,
+    now: u64,
+) -> Result<Option<CurveRecord>, ContractError> {
+    let mut market = MARKETS.load(deps.storage, market_index.to_string())?;
+    let oracle_guard_rails = ORACLEGUARDRAILS.load(deps.storage)?;
+
+    let oracle_price_data = market.amm.get_oracle_price()?;
+    if !amm::is_oracle_valid(&market.amm, &oracle_price_data, &oracle_guard_rails)? {
+        return Ok(None);
+    }
+
+    let oracle_price_twap = market.amm.last_oracle_price_twap.i128();
+    if oracle_price_twap <= 0 {
+        return Ok(None);
+    }
+
+    let peg_multiplier_before = market.amm.peg_multiplier;
+    let mark_price = market.amm.mark_price()?;
+    let optimal_peg = peg_multiplier_before
+        .checked_mul(Uint128::from(oracle_price_twap.unsigned_abs()))?
+        .checked_div(mark_price)?;
+
+    if optimal_peg.is_zero() || optimal_peg == peg_multiplier_before {
+        return Ok(None);
+    }
+
+    let budget = calculate_repeg_budget(&market)?;
+    let mut new_peg = optimal_peg;
+    let mut adjustment_cost = adjust_peg_cost(&mut market.clone(), new_peg)?;
+
+    if adjustment_cost > 0 && adjustment_cost.unsigned_abs() > budget.u128() {
+        // the cost is linear in the peg change, so scale the change down to fit the budget
+        let optimal_peg_change = if optimal_peg > peg_multiplier_before {
+            optimal_peg.checked_sub(peg_multiplier_before)?
+        } else {
+            peg_multiplier_before.checked_sub(optimal_peg)?
+        };
+        let peg_change = optimal_peg_change
+            .checked_mul(budget)?
+            .checked_div(Uint128::from(adjustment_cost.unsigned_abs()))?;
+        if peg_change.is_zero() {
+            return Ok(None);
+        }
+
+        new_peg = if optimal_peg > peg_multiplier_before {
+            peg_multiplier_before.checked_add(peg_change)?
+        } else {
+            peg_multiplier_before.checked_sub(peg_change)?
+        };
+        adjustment_cost = adjust_peg_cost(&mut market.clone(), new_peg)?;
+        if adjustment_cost > 0 && adjustment_cost.unsigned_abs() > budget.u128() {
+            return Ok(None);
+        }
+    }
+
+    adjust_peg_cost(&mut market, new_peg)?;
+    if adjustment_cost > 0 {
+        market.amm.total_fee_minus_distributions = market
+            .amm
+            .total_fee_minus_distributions
+            .checked_sub(Uint128::from(adjustment_cost.unsigned_abs()))?;
+    } else {
+        market.amm.total_fee_minus_distributions = market
+            .amm
+            .total_fee_minus_distributions
+            .checked_add(Uint128::from(adjustment_cost.unsigned_abs()))?;
+    }
+
+    let c = CurveRecord {
+        ts: now,
+        market_index,
+        peg_multiplier_before,
+        peg_multiplier_after: market.amm.peg_multiplier,
+        base_asset_reserve_before: market.amm.base_asset_reserve,
+        base_asset_reserve_after: market.amm.base_asset_reserve,
+        quote_asset_reserve_before: market.amm.quote_asset_reserve,
+        quote_asset_reserve_after: market.amm.quote_asset_reserve,
+        sqrt_k_before: market.amm.sqrt_k,
+        sqrt_k_after: market.amm.sqrt_k,
+        base_asset_amount_long: Uint128::from(market.base_asset_amount_long.i128().unsigned_abs()),
+        base_asset_amount_short: Uint128::from(market.base_asset_amount_short.i128().unsigned_abs()),
+        base_asset_amount: market.base_asset_amount,
+        open_interest: market.open_interest,
+        total_fee: market.amm.total_fee,
+        total_fee_minus_distributions: market.amm.total_fee_minus_distributions,
+        adjustment_cost: Number128::new(adjustment_cost),
+        oracle_price: oracle_price_data.price,
+        record_type: Type::Repeg,
+    };
+
+    MARKETS.update(deps.storage, market_index.to_string(), |_m| -> Result<Market, ContractError> {
+        Ok(market)
+    })?;
+
+    Ok(Some(c))
+}
+
+/// Fees that can be spent on repegging without dipping below the clearing house's share
+pub fn calculate_repeg_budget(market: &Market) -> Result<Uint128, ContractError> {
+    let reserved_fee = market
+        .amm
+        .total_fee
+        .checked_mul(SHARE_OF_FEES_ALLOCATED_TO_CLEARING_HOUSE_NUMERATOR)?
+        .checked_div(SHARE_OF_FEES_ALLOCATED_TO_CLEARING_HOUSE_DENOMINATOR)?;
+
+    Ok(market.amm.total_fee_minus_distributions.saturating_sub(reserved_fee))
+}
@@ -0,0 +1,69 @@
+use cosmwasm_std::{Addr, DepsMut, Storage, Uint128};
+use cw_storage_plus::U64Key;
+
+use crate::error::ContractError;
+use crate::states::trading_rewards::{
+    TradingRewardEpoch, TRADING_REWARDS, TRADING_REWARD_EPOCHS, TRADING_REWARD_SCORES,
+};
+
+/// Epochs are scheduled back to back, so the active one is the latest epoch that has started.
+pub fn get_active_epoch(
+    storage: &dyn Storage,
+    now: u64,
+) -> Result<Option<(u64, TradingRewardEpoch)>, ContractError> {
+    let epochs_length = TRADING_REWARDS
+        .may_load(storage)?
+        .unwrap_or_default()
+        .epochs_length;
+    for epoch_id in (1..=epochs_length).rev() {
+        let epoch = TRADING_REWARD_EPOCHS.load(storage, epoch_id.into())?;
+        if epoch.start_ts <= now {
+            if epoch.is_active(now) {
+                return Ok(Some((epoch_id, epoch)));
+            }
+            return Ok(None);
+        }
+    }
+    Ok(None)
+}
+
+/// Adds an epoch's reward to the running total of its token
+pub fn add_reward(
+    rewards: &mut Vec<(Addr, Uint128)>,
+    reward_token: &Addr,
+    reward: Uint128,
+) -> Result<(), ContractError> {
+    match rewards.iter_mut().find(|(token, _)| token == reward_token) {
+        Some((_, amount)) => *amount = amount.checked_add(reward)?,
+        None => rewards.push((reward_token.clone(), reward)),
+    }
+    Ok(())
+}
+
+/// Adds the fee a user paid on a trade to their score in the active epoch.
+/// Weighting volume by fees means tiered and discounted traders earn in proportion to what they pay.
+pub fn record_trading_fee(
+    deps: &mut DepsMut,
+    user_address: &Addr,
+    fee: Uint128,
+    now: u64,
+) -> Result<(), ContractError> {
+    if fee.is_zero() {
+        return Ok(());
+    }
+    let (epoch_id, mut epoch) = match get_active_epoch(deps.storage, now)? {
+        Some(active) => active,
+        None => return Ok(()),
+    };
+
+    epoch.total_score = epoch.total_score.checked_add(fee)?;
+    TRADING_REWARD_EPOCHS.save(deps.storage, epoch_id.into(), &epoch)?;
+
+    let key = (user_address, U64Key::from(epoch_id));
+    let mut score = TRADING_REWARD_SCORES
+        .may_load(deps.storage, key.clone())?
+        .unwrap_or_default();
+    score.score = score.score.checked_add(fee)?;
+    TRADING_REWARD_SCORES.save(deps.storage, key, &score)?;
+    Ok(())
+}
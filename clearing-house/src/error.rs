@@ -33,6 +33,8 @@ pub enum ContractError {
     UserAccountAndUserPositionsAccountMismatch,
     #[error("User Has No Position In Market")]
     UserHasNoPositionInMarket,
+    #[error("Pnl pool cannot pay out any profit")]
+    PnlPoolEmpty,
     #[error("Invalid Initial Peg")]
     InvalidInitialPeg,
     #[error("AMM repeg already configured with amt given")]
@@ -135,6 +137,72 @@ pub enum ContractError {
     CantExpireOrders,
     #[error("Helpers Error")]
     HelpersError,
+    #[error("Address already has this role")]
+    RoleAlreadyGranted,
+    #[error("Address does not have this role")]
+    RoleNotGranted,
+    #[error("Pending change does not exist")]
+    PendingChangeDoesNotExist,
+    #[error("Pending change is still timelocked")]
+    PendingChangeTimelocked,
+    #[error("No admin transfer pending")]
+    NoPendingAdmin,
+    #[error("Market is settled, positions can only be closed at the settlement price")]
+    MarketSettled,
+    #[error("Pause was set by governance and can only be lifted by governance")]
+    PausedByGovernance,
+    #[error("Market is not settled")]
+    MarketNotSettled,
+    #[error("Market can't be settled without an oracle twap")]
+    InvalidSettlementPrice,
+    #[error("Market is in circuit breaker cool down, only risk reducing trades are allowed")]
+    MarketInCoolDown,
+    #[error("Trade moves the mark price past the circuit breaker limit")]
+    CircuitBreakerTripped,
+    #[error("Invalid circuit breaker parameters")]
+    InvalidCircuitBreaker,
+    #[error("Invalid k adjustment parameters")]
+    InvalidKAdjustmentParams,
+    #[error("Invalid funding parameters")]
+    InvalidFundingParams,
+    #[error("Invalid spread")]
+    InvalidSpread,
+    #[error("Max leverage must be at least 1")]
+    InvalidMaxLeverage,
+    #[error("Specify either a quote or a base asset amount")]
+    AmbiguousTradeSize,
+    #[error("Invalid close amount")]
+    InvalidCloseAmount,
+    #[error("Stop loss or take profit price would trigger immediately")]
+    InvalidPositionTrigger,
+    #[error("Twap order needs at least one slice, a non zero interval and slices above the minimum trade size")]
+    InvalidTwapOrder,
+    #[error("Next twap slice is not due yet")]
+    TwapSliceNotDue,
+    #[error("Fee tiers must have ascending volumes, fees of at most 100% and either a maker fee or a maker rebate")]
+    InvalidFeeTiers,
+    #[error("Staking contract is not set")]
+    StakingContractNotSet,
+    #[error("Referral codes must be 3 to 32 lowercase letters, digits, '-' or '_'")]
+    InvalidReferralCode,
+    #[error("Referral code is already taken")]
+    ReferralCodeTaken,
+    #[error("Referral code not found")]
+    ReferralCodeNotFound,
+    #[error("Referrer already registered a referral code")]
+    ReferralCodeAlreadyRegistered,
+    #[error("User already has a different referrer")]
+    ReferrerAlreadySet,
+    #[error("No referral rewards to claim")]
+    NoReferralRewards,
+    #[error("Trading reward epochs must start in the future, after the previous epoch and end after they start")]
+    InvalidTradingRewardEpoch,
+    #[error("Trading reward epoch has ended")]
+    TradingRewardEpochEnded,
+    #[error("Trading reward token is not set")]
+    TradingRewardTokenNotSet,
+    #[error("No trading rewards to claim")]
+    NoTradingRewards,
 }
 
 
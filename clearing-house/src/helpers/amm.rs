@@ -3,7 +3,7 @@ use integer_sqrt::IntegerSquareRoot;
 
 use crate::error::ContractError;
 
-use crate::package::types::{OracleGuardRails, SwapDirection, PositionDirection, OraclePriceData};
+use crate::package::types::{CircuitBreaker, OracleGuardRails, SwapDirection, PositionDirection, OraclePriceData};
 use cosmwasm_std::{Fraction, Uint128};
 
 use crate::states::market::{Market, Amm};
@@ -175,6 +175,205 @@ pub fn calculate_swap_output(
     return Ok((new_output_amount, new_input_amount));
 }
 
+/// Spreads charged to longs and shorts in PRICE_SPREAD_PRECISION. Each side starts at half the base
+/// spread, is widened to the oracle/mark divergence when it trades towards the oracle and is scaled
+/// up by the share of the base reserve the net inventory on its side makes up.
+pub fn calculate_spread(market: &Market, mark_price: Uint128) -> Result<(u128, u128), ContractError> {
+    let a = &market.amm;
+    if a.max_spread.is_zero() {
+        return Ok((0, 0));
+    }
+
+    let half_base_spread = a.base_spread.u128() / 2;
+    let mut long_spread = half_base_spread;
+    let mut short_spread = half_base_spread;
+
+    let oracle_price_data = a.get_oracle_price()?;
+    if oracle_price_data.price.i128() > 0 {
+        let oracle_mark_spread_pct =
+            calculate_oracle_mark_spread_pct(a, &oracle_price_data, Some(mark_price))?;
+        if oracle_mark_spread_pct < 0 {
+            long_spread = max(long_spread, oracle_mark_spread_pct.unsigned_abs());
+        } else {
+            short_spread = max(short_spread, oracle_mark_spread_pct.unsigned_abs());
+        }
+    }
+
+    let base_asset_amount = market.base_asset_amount.i128();
+    if base_asset_amount != 0 {
+        let inventory_pct = min(
+            Uint128::from(base_asset_amount.unsigned_abs())
+                .checked_mul(PRICE_SPREAD_PRECISION_U128)?
+                .checked_div(a.base_asset_reserve)?,
+            PRICE_SPREAD_PRECISION_U128,
+        );
+        let inventory_scale = PRICE_SPREAD_PRECISION_U128.checked_add(inventory_pct)?;
+        if base_asset_amount > 0 {
+            long_spread = Uint128::from(long_spread)
+                .checked_mul(inventory_scale)?
+                .checked_div(PRICE_SPREAD_PRECISION_U128)?
+                .u128();
+        } else {
+            short_spread = Uint128::from(short_spread)
+                .checked_mul(inventory_scale)?
+                .checked_div(PRICE_SPREAD_PRECISION_U128)?
+                .u128();
+        }
+    }
+
+    Ok((min(long_spread, a.max_spread.u128()), min(short_spread, a.max_spread.u128())))
+}
+
+/// Reserves quoting the ask (long) or bid (short) side. Shifting the quote reserve by half the spread
+/// at constant k moves the price by roughly the full spread.
+pub fn calculate_spread_reserves(
+    a: &Amm,
+    spread: u128,
+    direction: PositionDirection,
+) -> Result<(Uint128, Uint128), ContractError> {
+    if spread == 0 {
+        return Ok((a.base_asset_reserve, a.quote_asset_reserve));
+    }
+
+    let quote_asset_reserve_delta = a
+        .quote_asset_reserve
+        .checked_mul(Uint128::from(spread))?
+        .checked_div(PRICE_SPREAD_PRECISION_U128.checked_mul(Uint128::from(2_u64))?)?;
+
+    let quote_asset_reserve = match direction {
+        PositionDirection::Long => a.quote_asset_reserve.checked_add(quote_asset_reserve_delta)?,
+        PositionDirection::Short => a.quote_asset_reserve.checked_sub(quote_asset_reserve_delta)?,
+    };
+    let base_asset_reserve = a
+        .sqrt_k
+        .checked_mul(a.sqrt_k)?
+        .checked_div(quote_asset_reserve)?;
+
+    Ok((base_asset_reserve, quote_asset_reserve))
+}
+
+pub fn calculate_bid_ask_price(market: &Market) -> Result<(Uint128, Uint128), ContractError> {
+    let a = &market.amm;
+    let (long_spread, short_spread) = calculate_spread(market, a.mark_price()?)?;
+
+    let (bid_base_asset_reserve, bid_quote_asset_reserve) =
+        calculate_spread_reserves(a, short_spread, PositionDirection::Short)?;
+    let (ask_base_asset_reserve, ask_quote_asset_reserve) =
+        calculate_spread_reserves(a, long_spread, PositionDirection::Long)?;
+
+    Ok((
+        calculate_price(bid_quote_asset_reserve, bid_base_asset_reserve, a.peg_multiplier)?,
+        calculate_price(ask_quote_asset_reserve, ask_base_asset_reserve, a.peg_multiplier)?,
+    ))
+}
+
+/// Fills a quote swap against the spread reserves and moves the real reserves by the base asset that
+/// came out. Returns the new base and quote reserves and the quote reserve kept as spread surplus.
+pub fn calculate_quote_swap_output_with_spread(
+    quote_asset_reserve_amount: Uint128,
+    a: &Amm,
+    direction: SwapDirection,
+    spread: u128,
+) -> Result<(Uint128, Uint128, Uint128), ContractError> {
+    if spread == 0 {
+        let (new_base_asset_reserve, new_quote_asset_reserve) = calculate_swap_output(
+            quote_asset_reserve_amount,
+            a.quote_asset_reserve,
+            direction,
+            a.sqrt_k,
+        )?;
+        return Ok((new_base_asset_reserve, new_quote_asset_reserve, Uint128::zero()));
+    }
+
+    let position_direction = match direction {
+        SwapDirection::Add => PositionDirection::Long,
+        SwapDirection::Remove => PositionDirection::Short,
+    };
+    let (spread_base_asset_reserve, spread_quote_asset_reserve) =
+        calculate_spread_reserves(a, spread, position_direction)?;
+    let (new_spread_base_asset_reserve, _) = calculate_swap_output(
+        quote_asset_reserve_amount,
+        spread_quote_asset_reserve,
+        direction,
+        a.sqrt_k,
+    )?;
+
+    let invariant = a.sqrt_k.checked_mul(a.sqrt_k)?;
+    match direction {
+        SwapDirection::Add => {
+            let base_asset_swapped =
+                spread_base_asset_reserve.checked_sub(new_spread_base_asset_reserve)?;
+            let new_base_asset_reserve = a.base_asset_reserve.checked_sub(base_asset_swapped)?;
+            let new_quote_asset_reserve = invariant.checked_div(new_base_asset_reserve)?;
+            let surplus = quote_asset_reserve_amount
+                .saturating_sub(new_quote_asset_reserve.saturating_sub(a.quote_asset_reserve));
+            Ok((new_base_asset_reserve, new_quote_asset_reserve, surplus))
+        }
+        SwapDirection::Remove => {
+            let base_asset_swapped =
+                new_spread_base_asset_reserve.checked_sub(spread_base_asset_reserve)?;
+            let new_base_asset_reserve = a.base_asset_reserve.checked_add(base_asset_swapped)?;
+            let new_quote_asset_reserve = invariant.checked_div(new_base_asset_reserve)?;
+            let surplus = a
+                .quote_asset_reserve
+                .saturating_sub(new_quote_asset_reserve)
+                .saturating_sub(quote_asset_reserve_amount);
+            Ok((new_base_asset_reserve, new_quote_asset_reserve, surplus))
+        }
+    }
+}
+
+/// Fills a base swap against the spread reserves and moves the real reserves by the same base asset.
+/// Returns the new base and quote reserves, the quote asset amount the user trades at and the quote
+/// asset the amm keeps as spread surplus.
+pub fn calculate_base_swap_output_with_spread(
+    market: &Market,
+    base_asset_swap_amount: Uint128,
+    direction: SwapDirection,
+) -> Result<(Uint128, Uint128, Uint128, Uint128), ContractError> {
+    let a = &market.amm;
+    let (new_quote_asset_reserve, new_base_asset_reserve) = calculate_swap_output(
+        base_asset_swap_amount,
+        a.base_asset_reserve,
+        direction,
+        a.sqrt_k,
+    )?;
+
+    // the user is filled against the spread reserves, the amm keeps the difference
+    let (long_spread, short_spread) = calculate_spread(market, a.mark_price()?)?;
+    let (spread, position_direction) = match direction {
+        SwapDirection::Add => (short_spread, PositionDirection::Short),
+        SwapDirection::Remove => (long_spread, PositionDirection::Long),
+    };
+    let (spread_base_asset_reserve, spread_quote_asset_reserve) =
+        calculate_spread_reserves(a, spread, position_direction)?;
+    let (new_spread_quote_asset_reserve, _) = calculate_swap_output(
+        base_asset_swap_amount,
+        spread_base_asset_reserve,
+        direction,
+        a.sqrt_k,
+    )?;
+
+    let amm_quote_asset_amount = calculate_quote_asset_amount_swapped(
+        a.quote_asset_reserve,
+        new_quote_asset_reserve,
+        direction,
+        a.peg_multiplier,
+    )?;
+    let quote_asset_amount = calculate_quote_asset_amount_swapped(
+        spread_quote_asset_reserve,
+        new_spread_quote_asset_reserve,
+        direction,
+        a.peg_multiplier,
+    )?;
+    let quote_asset_surplus = match direction {
+        SwapDirection::Add => amm_quote_asset_amount.saturating_sub(quote_asset_amount),
+        SwapDirection::Remove => quote_asset_amount.saturating_sub(amm_quote_asset_amount),
+    };
+
+    Ok((new_base_asset_reserve, new_quote_asset_reserve, quote_asset_amount, quote_asset_surplus))
+}
+
 pub fn calculate_quote_asset_amount_swapped(
     quote_asset_reserve_before: Uint128,
     quote_asset_reserve_after: Uint128,
@@ -306,6 +505,28 @@ pub fn is_oracle_mark_too_divergent(
     Ok(Uint128::from(price_spread_pct.unsigned_abs()).gt(&Uint128::from(max_divergence)))
 }
 
+pub fn is_circuit_breaker_exceeded(
+    reference_price: Uint128,
+    mark_price: Uint128,
+    circuit_breaker: &CircuitBreaker,
+) -> Result<bool, ContractError> {
+    if circuit_breaker.max_price_move.is_zero() || reference_price.is_zero() {
+        return Ok(false);
+    }
+
+    let price_move = if mark_price > reference_price {
+        mark_price.checked_sub(reference_price)?
+    } else {
+        reference_price.checked_sub(mark_price)?
+    };
+
+    let max_price_move = reference_price
+        .checked_mul(Uint128::from(circuit_breaker.max_price_move.numerator()))?
+        .checked_div(Uint128::from(circuit_breaker.max_price_move.denominator()))?;
+
+    Ok(price_move.gt(&max_price_move))
+}
+
 pub fn calculate_mark_twap_spread_pct(a: &Amm, mark_price: Uint128) -> Result<i128, ContractError> {
     let mark_price = mark_price.u128() as i128;
     let mark_twap = a.last_mark_price_twap.u128() as i128;
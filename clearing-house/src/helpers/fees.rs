@@ -1,24 +1,114 @@
 use std::cmp::{max, min};
 
-use cosmwasm_std::{Addr, Uint128, Fraction, Decimal};
+use cosmwasm_std::{Addr, Uint128, Fraction, Decimal, QuerierWrapper, StdResult, Storage};
 
-use crate::states::state::OrderState;
+use crate::states::market::Market;
+use crate::package::helper::query_staked_balance;
+use crate::states::state::{OrderState, FEE_TIERS, STAKING_CONTRACT};
 use crate::{error::ContractError};
 
 
 use integer_sqrt::IntegerSquareRoot;
 
-use crate::package::types::{FeeStructure, OrderDiscountTier};
+use crate::package::types::{FeeStructure, FeeTier, OrderDiscountTier};
+use crate::states::constants::{ONE_DAY, VOLUME_WINDOW_DAYS};
+
+/// Volume is kept in one bucket per day, indexed by the day modulo the window. Moves the buckets
+/// forward to `now`, emptying those of the days in between since they now stand for days without trades.
+pub fn roll_daily_volumes(daily_volumes: &[Uint128], last_update_ts: u64, now: u64) -> Vec<Uint128> {
+    let mut daily_volumes = daily_volumes.to_vec();
+    daily_volumes.resize(VOLUME_WINDOW_DAYS as usize, Uint128::zero());
+
+    let today = now / ONE_DAY;
+    let elapsed_days = min(today.saturating_sub(last_update_ts / ONE_DAY), VOLUME_WINDOW_DAYS);
+    for day in (today + 1 - elapsed_days)..=today {
+        daily_volumes[(day % VOLUME_WINDOW_DAYS) as usize] = Uint128::zero();
+    }
+    daily_volumes
+}
+
+/// Volume of the last thirty days, today included
+pub fn calculate_rolling_volume(
+    daily_volumes: &[Uint128],
+    last_update_ts: u64,
+    now: u64,
+) -> Result<Uint128, ContractError> {
+    let mut volume = Uint128::zero();
+    for daily_volume in roll_daily_volumes(daily_volumes, last_update_ts, now) {
+        volume = volume.checked_add(daily_volume)?;
+    }
+    Ok(volume)
+}
+
+/// Adds a trade's notional to today's bucket
+pub fn add_to_rolling_volume(
+    daily_volumes: &[Uint128],
+    last_update_ts: u64,
+    now: u64,
+    quote_asset_amount: Uint128,
+) -> Result<Vec<Uint128>, ContractError> {
+    let mut daily_volumes = roll_daily_volumes(daily_volumes, last_update_ts, now);
+    let today = ((now / ONE_DAY) % VOLUME_WINDOW_DAYS) as usize;
+    daily_volumes[today] = daily_volumes[today].checked_add(quote_asset_amount)?;
+    Ok(daily_volumes)
+}
+
+/// Only tokens locked in the staking contract count towards the discount tiers, so a wallet can't
+/// borrow tokens for the length of a trade
+pub fn get_discount_token_amount(
+    storage: &dyn Storage,
+    querier: &QuerierWrapper,
+    user: &Addr,
+) -> StdResult<Uint128> {
+    match STAKING_CONTRACT.may_load(storage)? {
+        Some(staking_contract) => query_staked_balance(querier, &staking_contract, user),
+        None => Ok(Uint128::zero()),
+    }
+}
+
+/// Markets with their own fee tiers ignore the global schedule
+pub fn get_fee_tiers(storage: &dyn Storage, market: &Market) -> StdResult<Vec<FeeTier>> {
+    match &market.fee_tiers {
+        Some(fee_tiers) => Ok(fee_tiers.clone()),
+        None => Ok(FEE_TIERS.may_load(storage)?.unwrap_or_default()),
+    }
+}
+
+/// Picks the highest tier the volume qualifies for. Without a matching tier takers pay the flat
+/// fee of the fee structure and makers pay nothing.
+/// Returns the tier's index, if any, and its rates.
+pub fn calculate_fee_tier(
+    fee_structure: &FeeStructure,
+    fee_tiers: &[FeeTier],
+    trailing_volume: Uint128,
+) -> (Option<u64>, FeeTier) {
+    match fee_tiers
+        .iter()
+        .enumerate()
+        .rev()
+        .find(|(_, tier)| trailing_volume >= tier.min_volume)
+    {
+        Some((index, tier)) => (Some(index as u64), tier.clone()),
+        None => (
+            None,
+            FeeTier {
+                taker_fee: fee_structure.fee,
+                ..FeeTier::default()
+            },
+        ),
+    }
+}
 
 pub fn calculate_fee_for_trade(
     quote_asset_amount: Uint128,
     fee_structure: &FeeStructure,
+    fee_tier: &FeeTier,
     discount_token_amt: Uint128,
     referrer: &Option<Addr>,
 ) -> Result<(Uint128, Uint128, Uint128, Uint128, Uint128), ContractError> {
     let fee = quote_asset_amount
-        .checked_mul(Uint128::from(fee_structure.fee.numerator()))?
-        .checked_div(Uint128::from(fee_structure.fee.denominator()))?;
+        .checked_mul(Uint128::from(fee_tier.taker_fee.numerator()))?
+        .checked_div(Uint128::from(fee_tier.taker_fee.denominator()))?;
 
     let token_discount = calculate_token_discount(fee, fee_structure, discount_token_amt)?;
 
@@ -159,9 +249,13 @@ pub fn calculate_order_fee_tier(
     Ok(OrderDiscountTier::None)
 }
 
+/// (user_fee, fee_to_market, token_discount, filler_reward, referrer_reward, referee_discount, maker_rebate)
+pub type OrderFees = (Uint128, Uint128, Uint128, Uint128, Uint128, Uint128, Uint128);
+
 pub fn calculate_fee_for_order(
     quote_asset_amount: Uint128,
     fee_structure: &FeeStructure,
+    fee_tier: &FeeTier,
     filler_reward_structure: &OrderState,
     order_fee_tier: &OrderDiscountTier,
     order_ts: u64,
@@ -169,7 +263,7 @@ pub fn calculate_fee_for_order(
     referrer: &Option<Addr>,
     filler_is_user: bool,
     quote_asset_amount_surplus: Uint128,
-) -> Result<(Uint128, Uint128, Uint128, Uint128, Uint128, Uint128), ContractError> {
+) -> Result<OrderFees, ContractError> {
     // if there was a quote_asset_amount_surplus, the order was a maker order and fee_to_market comes from surplus
     if !quote_asset_amount_surplus.is_zero() {
         let fee = quote_asset_amount_surplus;
@@ -178,13 +272,34 @@ pub fn calculate_fee_for_order(
         } else {
             calculate_filler_reward(fee, order_ts, now, filler_reward_structure)?
         };
-        let fee_to_market = fee.checked_sub(filler_reward)?;
+        let maker_fee = quote_asset_amount
+            .checked_mul(Uint128::from(fee_tier.maker_fee.numerator()))?
+            .checked_div(Uint128::from(fee_tier.maker_fee.denominator()))?;
+        // rebates are only paid out of the surplus so the market never pays makers from its own fees
+        let maker_rebate = min(
+            quote_asset_amount
+                .checked_mul(Uint128::from(fee_tier.maker_rebate.numerator()))?
+                .checked_div(Uint128::from(fee_tier.maker_rebate.denominator()))?,
+            fee.checked_sub(filler_reward)?,
+        );
+        let fee_to_market = fee
+            .checked_sub(filler_reward)?
+            .checked_sub(maker_rebate)?
+            .checked_add(maker_fee)?;
 
-        Ok((Uint128::zero(), fee_to_market, Uint128::zero(), filler_reward, Uint128::zero(), Uint128::zero()))
+        Ok((
+            maker_fee,
+            fee_to_market,
+            Uint128::zero(),
+            filler_reward,
+            Uint128::zero(),
+            Uint128::zero(),
+            maker_rebate,
+        ))
     } else {
         let fee = quote_asset_amount
-            .checked_mul(Uint128::from(fee_structure.fee.numerator()))?
-            .checked_div(Uint128::from(fee_structure.fee.denominator()))?;
+            .checked_mul(Uint128::from(fee_tier.taker_fee.numerator()))?
+            .checked_div(Uint128::from(fee_tier.taker_fee.denominator()))?;
 
         let token_discount =
             calculate_token_discount_for_limit_order(fee, fee_structure, order_fee_tier)?;
@@ -213,6 +328,7 @@ pub fn calculate_fee_for_order(
             filler_reward,
             referrer_reward,
             referee_discount,
+            Uint128::zero(),
         ))
     }
 }
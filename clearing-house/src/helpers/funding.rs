@@ -1,14 +1,14 @@
 use std::cmp::max;
 
-use cosmwasm_std::{Uint128};
+use cosmwasm_std::{Decimal, Fraction, Uint128};
 
 use crate::error::ContractError;
 
-use crate::states::market::Market;
+use crate::states::market::{Amm, Market};
 use crate::states::user::Position;
 
 use crate::states::constants::{
-    AMM_TO_QUOTE_PRECISION_RATIO, FUNDING_PAYMENT_PRECISION, MARK_PRICE_PRECISION,
+    AMM_TO_QUOTE_PRECISION_RATIO, FUNDING_PAYMENT_PRECISION, MARK_PRICE_PRECISION, ONE_HOUR,
     QUOTE_TO_BASE_AMT_FUNDING_PRECISION, SHARE_OF_FEES_ALLOCATED_TO_CLEARING_HOUSE_DENOMINATOR,SHARE_OF_FEES_ALLOCATED_TO_CLEARING_HOUSE_NUMERATOR
 };
 
@@ -72,6 +72,106 @@ pub fn calculate_funding_rate_long_short(
     return Ok((funding_rate_long, funding_rate_short, new_total_fee_minus_distributions));
 }
 
+/// Rounds the next funding update to be available on the hour.
+/// Returns the seconds to wait after the last funding update.
+pub fn calculate_next_funding_update_wait(a: &Amm) -> Result<u64, ContractError> {
+    if a.funding_period <= 1 {
+        return Ok(a.funding_period);
+    }
+
+    let last_update_delay = a.last_funding_rate_ts.rem_euclid(a.funding_period);
+    if last_update_delay == 0 {
+        return Ok(a.funding_period);
+    }
+
+    let max_delay_for_next_period = a.funding_period / 3;
+    let next_update_wait = if last_update_delay > max_delay_for_next_period {
+        // too late for on the hour next period, delay to following period
+        a.funding_period
+            .checked_mul(2)
+            .ok_or(ContractError::MathError)?
+            .checked_sub(last_update_delay)
+            .ok_or(ContractError::MathError)?
+    } else {
+        // allow update on the hour
+        a.funding_period
+            .checked_sub(last_update_delay)
+            .ok_or(ContractError::MathError)?
+    };
+    Ok(next_update_wait)
+}
+
+/// Funding period = 1 hour, window = 1 day.
+/// Low periodicity => quickly updating/settled funding rates => lower funding rate payment per interval.
+/// The premium index averages the spread sampled at every trade, the twap spread is the fallback.
+/// Returns the raw and the clamped funding rate.
+pub fn calculate_funding_rate(
+    market: &Market,
+    mark_price_twap: Uint128,
+    oracle_price_twap: i128,
+) -> Result<(i128, i128), ContractError> {
+    let one_hour_i64 = ONE_HOUR.u128() as i64;
+    let period_adjustment = (24_i64)
+        .checked_mul(one_hour_i64)
+        .ok_or(ContractError::MathError)?
+        .checked_div(max(one_hour_i64, market.amm.funding_period as i64))
+        .ok_or(ContractError::MathError)?;
+
+    let price_spread = match market.average_premium_index() {
+        Some(premium_index) => premium_index,
+        None => (mark_price_twap.u128() as i128)
+            .checked_sub(oracle_price_twap)
+            .ok_or(ContractError::MathError)?,
+    };
+
+    let premium_funding_rate = price_spread
+        .checked_mul(FUNDING_PAYMENT_PRECISION.u128() as i128)
+        .ok_or(ContractError::MathError)?
+        .checked_div(period_adjustment as i128)
+        .ok_or(ContractError::MathError)?;
+
+    calculate_clamped_funding_rate(market, premium_funding_rate, oracle_price_twap)
+}
+
+/// Adds the market's interest component to the premium funding rate and clamps the sum to the market's
+/// max funding rate. Both parameters are fractions of the oracle twap per funding period.
+/// Returns the raw and the clamped funding rate.
+fn calculate_clamped_funding_rate(
+    market: &Market,
+    premium_funding_rate: i128,
+    oracle_price_twap: i128,
+) -> Result<(i128, i128), ContractError> {
+    let params = &market.funding_params;
+    let oracle_price_twap = Uint128::from(oracle_price_twap.unsigned_abs());
+
+    let interest_funding_rate =
+        calculate_funding_rate_from_fraction(oracle_price_twap, params.interest_rate)?;
+    let raw_funding_rate = premium_funding_rate
+        .checked_add(interest_funding_rate)
+        .ok_or(ContractError::MathError)?;
+
+    if params.max_funding_rate.is_zero() {
+        return Ok((raw_funding_rate, raw_funding_rate));
+    }
+
+    let max_funding_rate =
+        calculate_funding_rate_from_fraction(oracle_price_twap, params.max_funding_rate)?;
+    let clamped_funding_rate = raw_funding_rate.clamp(-max_funding_rate, max_funding_rate);
+
+    Ok((raw_funding_rate, clamped_funding_rate))
+}
+
+fn calculate_funding_rate_from_fraction(
+    oracle_price_twap: Uint128,
+    fraction: Decimal,
+) -> Result<i128, ContractError> {
+    let funding_rate = oracle_price_twap
+        .checked_mul(Uint128::from(fraction.numerator()))?
+        .checked_div(Uint128::from(fraction.denominator()))?
+        .checked_mul(FUNDING_PAYMENT_PRECISION)?;
+    Ok(funding_rate.u128() as i128)
+}
+
 fn calculate_capped_funding_rate(
     market: &Market,
     uncapped_funding_pnl: i128, // if negative, users would net recieve from clearinghouse
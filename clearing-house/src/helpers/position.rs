@@ -177,6 +177,19 @@ pub fn swap_direction_to_close_position(base_asset_amount: i128) -> SwapDirectio
     }
 }
 
+/// Average entry price in MARK_PRICE_PRECISION, zero for an empty position
+pub fn calculate_entry_price(
+    quote_asset_amount: Uint128,
+    base_asset_amount: i128,
+) -> Result<Uint128, ContractError> {
+    if base_asset_amount == 0 {
+        return Ok(Uint128::zero());
+    }
+    Ok(quote_asset_amount
+        .checked_mul(MARK_PRICE_TIMES_AMM_TO_QUOTE_PRECISION_RATIO)?
+        .checked_div(Uint128::from(base_asset_amount.unsigned_abs()))?)
+}
+
 pub fn calculate_pnl(
     exit_value: Uint128,
     entry_value: Uint128,
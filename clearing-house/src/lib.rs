@@ -6,7 +6,7 @@ pub mod helpers;
 pub mod views;
 pub mod package;
 
-// #[cfg(test)]
-// mod tests;
+#[cfg(test)]
+mod tests;
 
 pub use crate::error::ContractError;
@@ -2,7 +2,8 @@ use cosmwasm_std::{Decimal, Uint128};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use crate::package::types::{OracleSource, PositionDirection};
+use crate::package::types::{CircuitBreaker, FeeTier, FundingParams, KAdjustmentParams, KeeperRewards, OracleSource, OrderParams, PositionDirection, Role};
+use crate::package::number::Number128;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct InstantiateMsg {
@@ -31,8 +32,13 @@ pub enum ExecuteMsg {
     //deposit collateral, updates user struct
     DepositCollateral {
         amount: u64,
-        referrer: Option<String>
+        referral_code: Option<String>
     },
+    RegisterReferralCode {
+        code: String,
+    },
+    ClaimReferralRewards {},
+    ClaimTradingRewards {},
     //user function withdraw collateral, updates user struct
     WithdrawCollateral {
         amount: u64,
@@ -40,29 +46,74 @@ pub enum ExecuteMsg {
     OpenPosition {
         direction: PositionDirection,
         quote_asset_amount: Uint128,
+        // trade an exact base amount instead, quote_asset_amount must then be zero
+        base_asset_amount: Option<Uint128>,
         market_index: u64,
         limit_price: Option<Uint128>,
     },
+    // closes the whole position unless a base amount or a percentage of it is given
     ClosePosition {
         market_index: u64,
+        base_asset_amount: Option<Uint128>,
+        percentage: Option<Decimal>,
+        limit_price: Option<Uint128>,
+    },
+    SetUserMaxLeverage {
+        market_index: u64,
+        max_leverage: Option<Decimal>,
+    },
+    // applies the actions in order and checks initial margin once at the end
+    Batch {
+        actions: Vec<BatchAction>,
+    },
+    // replaces the reduce only stop loss / take profit orders attached to a position
+    SetPositionTriggers {
+        market_index: u64,
+        stop_loss_price: Option<Uint128>,
+        take_profit_price: Option<Uint128>,
     },
 
     // order related messages
-    // PlaceOrder {
-    //     order: OrderParams,
-    // },
-    // CancelOrder {
-    //     market_index: u64,
-    //     order_id: u64,
-    // },
+    PlaceOrder {
+        order: OrderParams,
+    },
+    // price and oracle_price_offset are exclusive, setting one clears the other on the order
+    ModifyOrder {
+        market_index: u64,
+        order_id: u64,
+        price: Option<Uint128>,
+        oracle_price_offset: Option<Number128>,
+        base_asset_amount: Option<Uint128>,
+    },
+    CancelOrder {
+        market_index: u64,
+        order_id: u64,
+    },
     // ExpireOrders {
     //     user_address: String,
     // },
-    // FillOrder {
-    //     order_id: u64,
-    //     user_address: String,
-    //     market_index: u64,
-    // },
+    FillOrder {
+        order_id: u64,
+        user_address: String,
+        market_index: u64,
+    },
+    // splits base_asset_amount into equal slices executed by keepers every interval seconds
+    // a slice that can't be filled within limit_price is skipped rather than retried
+    PlaceTwapOrder {
+        market_index: u64,
+        direction: PositionDirection,
+        base_asset_amount: Uint128,
+        slices: u64,
+        interval: u64,
+        limit_price: Option<Uint128>,
+    },
+    CancelTwapOrder {
+        twap_order_id: u64,
+    },
+    ExecuteTwapSlice {
+        user_address: String,
+        twap_order_id: u64,
+    },
     Liquidate {
         user: String,
         market_index: u64,
@@ -77,6 +128,33 @@ pub enum ExecuteMsg {
         market_index: u64,
         amount: u64,
     },
+    FundKeeperRewardPool {
+        market_index: u64,
+        amount: Uint128,
+    },
+    UpdateKeeperRewards {
+        keeper_rewards: KeeperRewards,
+    },
+    UpdateTradingRewardToken {
+        reward_token: String,
+    },
+    ScheduleTradingRewardEpoch {
+        start_ts: u64,
+        end_ts: u64,
+        reward_amount: Uint128,
+    },
+    UpdateTradingRewardEpoch {
+        epoch_id: u64,
+        reward_amount: Uint128,
+    },
+    FundPnlPool {
+        market_index: u64,
+        amount: Uint128,
+    },
+    FundStakingRewards {
+        market_index: u64,
+        amount: Uint128,
+    },
 
     // withdraw from insurance vault sends token but no logic
 
@@ -100,6 +178,16 @@ pub enum ExecuteMsg {
     },
     //user calls it we get the user identification from msg address sender
     SettleFundingPayment {},
+    // permissionless, lets keepers bring other users' collateral up to date
+    SettleFundingPaymentFor {
+        users: Vec<String>,
+    },
+    SettlePnl {
+        market_index: u64,
+    },
+    SettlePosition {
+        market_index: u64,
+    },
     UpdateFundingRate {
         market_index: u64,
     },
@@ -113,6 +201,10 @@ pub enum ExecuteMsg {
         margin_ratio_partial: u32,
         margin_ratio_maintenance: u32,
     },
+    UpdateImfFactor {
+        market_index: u64,
+        imf_factor: u32,
+    },
     UpdatePartialLiquidationClosePercentage {
         value: Decimal,
     },
@@ -141,6 +233,13 @@ pub enum ExecuteMsg {
         referrer_reward: Decimal,
         referee_discount: Decimal,
     },
+    UpdateFeeTiers {
+        fee_tiers: Vec<FeeTier>,
+    },
+    UpdateMarketFeeTiers {
+        market_index: u64,
+        fee_tiers: Option<Vec<FeeTier>>,
+    },
     UpdateOraceGuardRails {
         use_for_liquidations: bool,
         mark_oracle_divergence: Decimal,
@@ -161,6 +260,9 @@ pub enum ExecuteMsg {
     UpdateOracleAddress {
         oracle: String,
     },
+    UpdateStakingContract {
+        staking_contract: String,
+    },
     UpdateMarketMinimumQuoteAssetTradeSize {
         market_index: u64,
         minimum_trade_size: Uint128,
@@ -170,10 +272,30 @@ pub enum ExecuteMsg {
         market_index: u64,
         minimum_trade_size: Uint128,
     },
+    UpdateCircuitBreaker {
+        market_index: u64,
+        circuit_breaker: CircuitBreaker,
+    },
+    UpdateKAdjustmentParams {
+        market_index: u64,
+        k_adjustment: KAdjustmentParams,
+    },
+    UpdateFundingParams {
+        market_index: u64,
+        funding_params: FundingParams,
+    },
+    UpdateMarketSpread {
+        market_index: u64,
+        base_spread: Uint128,
+        max_spread: Uint128,
+    },
     // will move to admin controller
-    UpdateAdmin {
-        admin: String,
+    ProposeNewAdmin {
+        new_admin: String,
     },
+    // called by the proposed admin to complete the transfer
+    AcceptAdmin {},
+    CancelAdminProposal {},
     UpdateMaxDeposit {
         max_deposit: Uint128,
     },
@@ -188,4 +310,65 @@ pub enum ExecuteMsg {
         market_index: u64,
         price: i128,
     },
+    //admin function
+    GrantRole {
+        role: Role,
+        address: String,
+    },
+    //admin function
+    RevokeRole {
+        role: Role,
+        address: String,
+    },
+    //admin function, queued behind the current timelock delay
+    UpdateTimelockDelay {
+        delay: u64,
+    },
+    // anyone can apply a queued change once its eta has passed
+    ExecutePendingChange {
+        change_id: u64,
+    },
+    //admin function
+    CancelPendingChange {
+        change_id: u64,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchAction {
+    OpenPosition {
+        direction: PositionDirection,
+        quote_asset_amount: Uint128,
+        base_asset_amount: Option<Uint128>,
+        market_index: u64,
+        limit_price: Option<Uint128>,
+    },
+    ClosePosition {
+        market_index: u64,
+        base_asset_amount: Option<Uint128>,
+        percentage: Option<Decimal>,
+        limit_price: Option<Uint128>,
+    },
+    WithdrawCollateral {
+        amount: u64,
+    },
+}
+
+// messages only the chain's governance module can send, they bypass State.admin
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SudoMsg {
+    UpdateExchangePaused {
+        exchange_paused: bool,
+    },
+    UpdateFundingPaused {
+        funding_paused: bool,
+    },
+    UpdateAdmin {
+        admin: String,
+    },
+    SettleMarket {
+        market_index: u64,
+    },
 }
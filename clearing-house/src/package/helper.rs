@@ -1,9 +1,10 @@
 use cosmwasm_std::{
-    Addr, Api, BalanceResponse, BankQuery, MessageInfo, QuerierWrapper, QueryRequest, StdError,
-    StdResult, Uint128,
+    to_binary, Addr, Api, BalanceResponse, BankQuery, MessageInfo, QuerierWrapper, QueryRequest,
+    StdError, StdResult, Uint128, WasmQuery,
 };
 use schemars::JsonSchema;
 use serde::{Serialize, Deserialize};
+use std::convert::TryInto;
 
 pub fn addr_validate_to_lower(api: &dyn Api, addr: &str) -> StdResult<Addr> {
     if addr.to_lowercase() != addr {
@@ -15,6 +16,11 @@ pub fn addr_validate_to_lower(api: &dyn Api, addr: &str) -> StdResult<Addr> {
     api.addr_validate(addr)
 }
 
+// integer keys are stored big endian, which keeps ranges in numeric order
+pub fn parse_u64_key(k: &[u8]) -> Option<u64> {
+    Some(u64::from_be_bytes(k.try_into().ok()?))
+}
+
 pub fn assert_sent_uusd_balance(message_info: &MessageInfo, input_amount: u128) -> StdResult<()> {
     let amount = Uint128::from(input_amount);
     match message_info.funds.iter().find(|x| x.denom == "uusd") {
@@ -52,8 +58,52 @@ pub fn query_balance(querier: &QuerierWrapper, account_addr: Addr) -> StdResult<
 pub enum VaultInterface {
     Withdraw{
         to_address: Addr,
-        amount: u128
+        amount: Uint128
     },
     Deposit {}
 
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Cw20Interface {
+    Transfer {
+        recipient: String,
+        amount: Uint128,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum StakingInterface {
+    DistributeRewards {
+        amount: Uint128,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum StakingQuery {
+    GetStakedBalance {
+        address: String,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct StakedBalanceResponse {
+    pub balance: Uint128,
+}
+
+pub fn query_staked_balance(
+    querier: &QuerierWrapper,
+    staking_contract: &Addr,
+    account_addr: &Addr,
+) -> StdResult<Uint128> {
+    let res: StakedBalanceResponse = querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: staking_contract.to_string(),
+        msg: to_binary(&StakingQuery::GetStakedBalance {
+            address: account_addr.to_string(),
+        })?,
+    }))?;
+    Ok(res.balance)
 }
\ No newline at end of file
@@ -1,14 +1,16 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use crate::states::history::{CurveRecord, DepositRecord, FundingPaymentRecord, FundingRateRecord, LiquidationRecord, TradeRecord};
+use crate::states::history::{CurveRecord, DepositRecord, FundingPaymentRecord, FundingRateRecord, LiquidationRecord, SettlePnlRecord, TradeRecord};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum HistoryExecuteMsg {
-    UpdateAdmin {
+    ProposeNewAdmin {
         new_admin: String,
     },
+    AcceptAdmin {},
+    CancelAdminProposal {},
     UpdateClearingHouse {
         new_house: String,
     },
@@ -30,7 +32,13 @@ pub enum HistoryExecuteMsg {
     RecordTrade {
         t: TradeRecord
     },
+    RecordSettlePnl {
+        s: SettlePnlRecord
+    },
     RecordFundingPaymentsMultiple {
         vecf: Vec<FundingPaymentRecord>
     },
+    RecordBatch {
+        records: Vec<HistoryExecuteMsg>
+    },
 }
@@ -1,3 +1,4 @@
+use cosmwasm_std::Uint128;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
@@ -36,4 +37,55 @@ pub enum QueryMsg {
     GetMarketInfo {
         market_index: u64,
     },
+    GetRoles {},
+    GetPendingChanges {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    GetKeeperRewards {},
+    GetStakingContract {},
+    GetFundingInfo {
+        market_index: u64,
+    },
+    GetPendingFunding {
+        user_address: String,
+    },
+    GetUserFeeTier {
+        user_address: String,
+        market_index: u64,
+    },
+    GetCircuitBreaker {
+        market_index: u64,
+    },
+    GetEffectiveLeverage {
+        market_index: u64,
+        base_asset_amount: Uint128,
+    },
+    GetOpenOrders {
+        user_address: String,
+        market_index: u64,
+    },
+    GetTwapOrders {
+        user_address: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    GetTradingRewardEpochs {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    // accrued and claimable only cover the epochs of the returned page
+    GetUserTradingRewards {
+        user_address: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    GetReferralCode {
+        code: String,
+    },
+    GetReferrer {
+        referrer_address: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
 }
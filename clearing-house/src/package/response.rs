@@ -2,7 +2,7 @@ use cosmwasm_std::{Uint128, Decimal};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use crate::package::{types::{OracleSource, PositionDirection}, number::Number128};
+use crate::package::{types::{CircuitBreaker, FeeTier, FundingParams, KAdjustmentParams, KeeperRewards, OracleSource, Order, ParameterChange, PositionDirection, Role}, number::Number128};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct UserResponse {
@@ -22,7 +22,11 @@ pub struct UserPositionResponse {
     pub last_cumulative_funding_rate: Number128,
     pub last_cumulative_repeg_rebate: Uint128,
     pub last_funding_rate_ts: u64,
-    
+    pub max_leverage: Option<Decimal>,
+    pub entry_price: Uint128,
+    pub realized_pnl: Number128,
+    pub cumulative_funding_paid: Number128,
+    pub cumulative_fees_paid: Uint128,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -44,16 +48,19 @@ pub struct PositionResponse {
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct AdminResponse {
     pub admin: String,
+    pub pending_admin: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct IsExchangePausedResponse {
     pub exchange_paused: bool,
+    pub paused_by_governance: bool,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct IsFundingPausedResponse {
     pub funding_paused: bool,
+    pub paused_by_governance: bool,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -150,6 +157,8 @@ pub struct MarketLengthResponse {
 pub struct MarketInfoResponse {
     pub market_name: String,
     pub initialized: bool,
+    pub settled: bool,
+    pub settlement_price: Uint128,
     pub base_asset_amount_long: Number128,
     pub base_asset_amount_short: Number128,
     pub base_asset_amount: Number128, // net market bias
@@ -177,7 +186,193 @@ pub struct MarketInfoResponse {
     pub last_oracle_price_twap_ts: u64,
     pub last_oracle_price: Number128,
     pub minimum_base_asset_trade_size: Uint128,
-    pub minimum_quote_asset_trade_size: Uint128
+    pub minimum_quote_asset_trade_size: Uint128,
+    pub k_adjustment: KAdjustmentParams,
+    pub volume_since_last_k_update: Uint128,
+    pub last_k_update_ts: u64,
+    pub funding_params: FundingParams,
+    pub premium_index_sum: Number128,
+    pub premium_index_duration: u64,
+    pub last_premium: Number128,
+    pub last_premium_ts: u64,
+    pub pnl_pool: Uint128,
+    pub fee_tiers: Option<Vec<FeeTier>>,
+    pub base_spread: Uint128,
+    pub max_spread: Uint128,
+    pub bid_price: Uint128,
+    pub ask_price: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RoleMembersResponse {
+    pub role: Role,
+    pub members: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RolesResponse {
+    pub roles: Vec<RoleMembersResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingChangeResponse {
+    pub change_id: u64,
+    pub change: ParameterChange,
+    pub proposer: String,
+    pub queued_ts: u64,
+    pub eta: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct EffectiveLeverageResponse {
+    pub market_index: u64,
+    pub base_asset_amount: Uint128,
+    pub imf_factor: u32,
+    pub margin_ratio_initial: u32,
+    pub margin_ratio_maintenance: u32,
+    pub max_leverage: Decimal,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct KeeperRewardsResponse {
+    pub keeper_rewards: KeeperRewards,
+    pub pool_balance: Uint128,
+    pub total_paid: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct StakingContractResponse {
+    pub staking_contract: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CircuitBreakerResponse {
+    pub market_index: u64,
+    pub circuit_breaker: CircuitBreaker,
+    pub reference_price: Uint128,
+    pub reference_ts: u64,
+    pub cool_down_until_ts: u64,
+    pub in_cool_down: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct OrderResponse {
+    pub order_id: u64,
+    pub order: Order,
+    // limit price after resolving any oracle offset, none for unpriced orders or while the oracle is invalid
+    pub effective_price: Option<Uint128>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TradingRewardEpochResponse {
+    pub epoch_id: u64,
+    pub reward_token: String,
+    pub start_ts: u64,
+    pub end_ts: u64,
+    pub reward_amount: Uint128,
+    pub total_score: Uint128,
+    pub total_claimed: Uint128,
+    pub active: bool,
+    pub ended: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct UserTradingRewardEpochResponse {
+    pub epoch_id: u64,
+    pub reward_token: String,
+    pub score: Uint128,
+    pub reward: Uint128,
+    pub claimed: bool,
+    pub ended: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TradingRewardAmountResponse {
+    pub reward_token: String,
+    pub amount: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct UserTradingRewardsResponse {
+    pub reward_token: Option<String>, // token of epochs scheduled from now on
+    pub accrued: Vec<TradingRewardAmountResponse>, // estimated share of the active epoch at its current total score
+    pub claimable: Vec<TradingRewardAmountResponse>, // ended epochs that have not been claimed yet, per token
+    pub epochs: Vec<UserTradingRewardEpochResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ReferralCodeResponse {
+    pub code: String,
+    pub referrer: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RefereeResponse {
+    pub referee: String,
+    pub reward: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ReferrerResponse {
+    pub code: String,
+    pub referee_count: u64,
+    pub total_reward: Uint128,
+    pub unclaimed_reward: Uint128,
+    pub referees: Vec<RefereeResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TwapOrderResponse {
+    pub twap_order_id: u64,
+    pub market_index: u64,
+    pub direction: PositionDirection,
+    pub base_asset_amount: Uint128,
+    pub base_asset_amount_filled: Uint128,
+    pub quote_asset_amount_filled: Uint128,
+    pub slices: u64,
+    pub slices_filled: u64,
+    pub slices_skipped: u64,
+    pub interval: u64,
+    pub limit_price: Option<Uint128>,
+    pub next_slice_ts: u64,
+    pub next_slice_base_asset_amount: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FundingInfoResponse {
+    pub market_index: u64,
+    pub last_funding_rate: Number128,
+    pub last_funding_rate_ts: u64,
+    pub mark_price_twap: Uint128,
+    pub oracle_price_twap: Number128,
+    // what the next update would charge at the current twaps and premium index
+    pub estimated_funding_rate: Number128,
+    pub estimated_raw_funding_rate: Number128,
+    pub next_funding_rate_ts: u64,
+    pub seconds_until_next_update: u64,
+    // fraction of the oracle twap per year, MARK_PRICE_PRECISION is 100%
+    pub annualized_funding_rate: Number128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingFundingPaymentResponse {
+    pub market_index: u64,
+    pub base_asset_amount: Number128,
+    // in collateral precision, positive is owed to the user
+    pub pending_funding_payment: Number128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingFundingResponse {
+    pub positions: Vec<PendingFundingPaymentResponse>,
+    pub total_pending_funding_payment: Number128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct UserFeeTierResponse {
+    pub trailing_volume_30d: Uint128,
+    pub tier: Option<u64>,
+    pub fee_tier: FeeTier,
 }
 
 // #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -139,6 +139,16 @@ pub enum OrderDiscountTier {
 }
 
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct FeeTier {
+    // rolling 30 day notional volume in quote precision needed to reach the tier
+    pub min_volume: Uint128,
+    pub taker_fee: Decimal,
+    pub maker_fee: Decimal,
+    // paid to makers out of the fill surplus, only one of maker_fee and maker_rebate may be set
+    pub maker_rebate: Decimal,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct FeeStructure {
     pub fee: Decimal,
@@ -171,6 +181,48 @@ pub struct OracleGuardRails {
     pub too_volatile_ratio: Number128,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct CircuitBreaker {
+    // max move of the mark price away from the reference price, zero disables the breaker
+    pub max_price_move: Decimal,
+    // seconds the reference price is held before it is rolled to the latest mark twap
+    pub window: u64,
+    // seconds risk increasing trades are rejected once the breaker trips
+    pub cool_down_period: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct KAdjustmentParams {
+    // change applied to sqrt_k per update, zero disables automatic k adjustment
+    pub k_change: Decimal,
+    // seconds between automatic updates
+    pub update_interval: u64,
+    // fee pool below this is considered depleted and k is shrunk
+    pub fee_pool_floor: Uint128,
+    // max fees spent on a single k increase
+    pub max_cost_per_update: Uint128,
+    // quote volume traded since the last update required before k grows
+    pub volume_threshold: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct KeeperRewards {
+    // paid from the keeper reward pool per crank that changed state, zero disables the reward
+    pub funding_rate_update: Uint128,
+    pub oracle_twap_update: Uint128,
+    pub funding_settlement: Uint128,
+    // seconds that must pass between two rewarded oracle twap updates of a market
+    pub oracle_twap_update_interval: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct FundingParams {
+    // max funding rate per period as a fraction of the oracle twap, zero leaves funding uncapped
+    pub max_funding_rate: Decimal,
+    // interest rate per period as a fraction of the oracle twap, paid by longs to shorts
+    pub interest_rate: Decimal,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct OrderParams {
     pub order_type: OrderType,
@@ -186,4 +238,83 @@ pub struct OrderParams {
     pub trigger_condition: OrderTriggerCondition,
     pub position_limit: Uint128,
     pub oracle_price_offset: Number128,
-}
\ No newline at end of file
+}
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    MarketAdmin,
+    RiskAdmin,
+    Pauser,
+    PriceFeeder,
+    Treasury,
+}
+
+impl Role {
+    pub fn all() -> Vec<Role> {
+        vec![
+            Role::MarketAdmin,
+            Role::RiskAdmin,
+            Role::Pauser,
+            Role::PriceFeeder,
+            Role::Treasury,
+        ]
+    }
+
+    // storage key of the role's address set
+    pub fn key(&self) -> String {
+        match self {
+            Role::MarketAdmin => "market_admin".to_string(),
+            Role::RiskAdmin => "risk_admin".to_string(),
+            Role::Pauser => "pauser".to_string(),
+            Role::PriceFeeder => "price_feeder".to_string(),
+            Role::Treasury => "treasury".to_string(),
+        }
+    }
+}
+
+// parameter updates which only take effect once the timelock delay has passed
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ParameterChange {
+    MarginRatio {
+        market_index: u64,
+        margin_ratio_initial: u32,
+        margin_ratio_partial: u32,
+        margin_ratio_maintenance: u32,
+    },
+    ImfFactor {
+        market_index: u64,
+        imf_factor: u32,
+    },
+    PartialLiquidationClosePercentage {
+        value: Decimal,
+    },
+    PartialLiquidationPenaltyPercentage {
+        value: Decimal,
+    },
+    FullLiquidationPenaltyPercentage {
+        value: Decimal,
+    },
+    PartialLiquidationLiquidatorShareDenominator {
+        denominator: u64,
+    },
+    FullLiquidationLiquidatorShareDenominator {
+        denominator: u64,
+    },
+    Fee {
+        fee_structure: FeeStructure,
+    },
+    FeeTiers {
+        fee_tiers: Vec<FeeTier>,
+    },
+    MarketFeeTiers {
+        market_index: u64,
+        fee_tiers: Option<Vec<FeeTier>>,
+    },
+    OracleGuardRails {
+        oracle_guard_rails: OracleGuardRails,
+    },
+    TimelockDelay {
+        delay: u64,
+    },
+}
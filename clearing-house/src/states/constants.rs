@@ -27,6 +27,10 @@ pub const UPDATE_K_ALLOWED_PRICE_CHANGE: Uint128 = Uint128::new(1_000_000_000);
 
 // TIME PERIODS
 pub const ONE_HOUR: Uint128 =  Uint128::new(3600);
+pub const ONE_YEAR: u64 = 31_536_000;
+pub const ONE_DAY: u64 = 86_400;
+pub const VOLUME_WINDOW_DAYS: u64 = 30; // daily volume buckets summed up for the fee tiers
+pub const DEFAULT_TIMELOCK_DELAY: u64 = 86_400; // seconds a queued parameter change waits before it can be executed
 
 // FEES
 pub const SHARE_OF_FEES_ALLOCATED_TO_CLEARING_HOUSE_NUMERATOR: Uint128 = Uint128::new(5);
@@ -67,6 +71,7 @@ pub const MAX_LIQUIDATION_SLIPPAGE_U128: Uint128 = Uint128::new(100); // expo =
 pub const MAX_MARK_TWAP_DIVERGENCE: Uint128 = Uint128::new(5_000); // expo = -3
 pub const MAXIMUM_MARGIN_RATIO: Uint128 = MARGIN_PRECISION;
 pub const MINIMUM_MARGIN_RATIO: Uint128 =  Uint128::new(200);// MARGIN_PRECISION / Uint128::new(50);
+pub const IMF_SIZE_SQRT_PRECISION: Uint128 = Uint128::new(1_000); // expo = -3
 
 // iterator limits
 pub const MAX_LIMIT: u32 = 20;
@@ -36,7 +36,8 @@ pub struct CurveRecord {
     pub total_fee: Uint128,
     pub total_fee_minus_distributions: Uint128,
     pub adjustment_cost: Number128,
-    pub oracle_price: Number128
+    pub oracle_price: Number128,
+    pub record_type: Type,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -67,6 +68,8 @@ pub struct FundingRateRecord {
     pub ts: u64,
     pub market_index: u64,
     pub funding_rate: Number128,
+    // premium plus interest before the max funding rate clamp
+    pub raw_funding_rate: Number128,
     pub cumulative_funding_rate_long: Number128,
     pub cumulative_funding_rate_short: Number128,
     pub oracle_price_twap: Number128,
@@ -137,4 +140,21 @@ pub struct TradeRecord {
     pub liquidation: bool,
     pub market_index: u64,
     pub oracle_price: Number128,
-}
\ No newline at end of file
+    pub circuit_breaker_cool_down: bool,
+    // pnl realized by this trade and the position's totals after it
+    pub realized_pnl: Number128,
+    pub entry_price: Uint128,
+    pub cumulative_funding_paid: Number128,
+    pub cumulative_fees_paid: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SettlePnlRecord {
+    pub ts: u64,
+    pub user: Addr,
+    pub market_index: u64,
+    pub pnl: Number128,
+    pub mark_price: Uint128,
+    pub oracle_price: Number128,
+    pub pnl_pool_after: Uint128,
+}
@@ -6,7 +6,7 @@ use cosmwasm_std::{Addr, Uint128};
 
 use cw_storage_plus::Map;
 
-use crate::package::types::{OracleSource, OracleStatus, OraclePriceData};
+use crate::package::types::{CircuitBreaker, FeeTier, FundingParams, KAdjustmentParams, OracleSource, OracleStatus, OraclePriceData};
 
 use crate::error::ContractError;
 
@@ -16,6 +16,9 @@ use crate::helpers::amm;
 pub struct Market {
     pub market_name: String,
     pub initialized: bool,
+    // set by governance, a settled market takes no more trades and positions exit at settlement_price
+    pub settled: bool,
+    pub settlement_price: Uint128,
     pub base_asset_amount_long: Number128,
     pub base_asset_amount_short: Number128,
     pub base_asset_amount: Number128, // net market bias
@@ -24,6 +27,60 @@ pub struct Market {
     pub margin_ratio_initial: u32,
     pub margin_ratio_partial: u32,
     pub margin_ratio_maintenance: u32,
+    pub imf_factor: u32, // margin ratio added per square root of base asset, zero keeps margin flat
+    pub circuit_breaker: CircuitBreaker,
+    pub circuit_breaker_reference_price: Uint128,
+    pub circuit_breaker_reference_ts: u64,
+    pub cool_down_until_ts: u64,
+    pub k_adjustment: KAdjustmentParams,
+    pub volume_since_last_k_update: Uint128,
+    pub last_k_update_ts: u64,
+    pub funding_params: FundingParams,
+    // mark minus oracle price after each trade, weighted by the seconds it held since the last funding update
+    pub premium_index_sum: Number128,
+    pub premium_index_duration: u64,
+    pub last_premium: Number128,
+    pub last_premium_ts: u64,
+    // settled losses and treasury funding available to pay out settled profits
+    pub pnl_pool: Uint128,
+    // replaces the global volume fee tiers for this market
+    pub fee_tiers: Option<Vec<FeeTier>>,
+}
+
+impl Market {
+    pub fn in_cool_down(&self, now: u64) -> bool {
+        now < self.cool_down_until_ts
+    }
+
+    pub fn average_premium_index(&self) -> Option<i128> {
+        if self.premium_index_duration == 0 {
+            return None;
+        }
+        self.premium_index_sum
+            .i128()
+            .checked_div(self.premium_index_duration as i128)
+    }
+
+    // weights the premium left by the last trade with the time it held until now
+    pub fn accrue_premium_index(&mut self, now: u64) -> Result<(), ContractError> {
+        if self.last_premium_ts == 0 || now <= self.last_premium_ts {
+            return Ok(());
+        }
+        let elapsed = now - self.last_premium_ts;
+        self.premium_index_sum = Number128::new(
+            self.last_premium
+                .i128()
+                .checked_mul(elapsed as i128)
+                .and_then(|weighted| weighted.checked_add(self.premium_index_sum.i128()))
+                .ok_or(ContractError::MathError)?,
+        );
+        self.premium_index_duration = self
+            .premium_index_duration
+            .checked_add(elapsed)
+            .ok_or(ContractError::MathError)?;
+        self.last_premium_ts = now;
+        Ok(())
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -51,6 +108,8 @@ pub struct Amm {
     pub last_oracle_price: Number128,
     pub last_oracle_price_twap: Number128,
     pub minimum_base_asset_trade_size: Uint128,
+    pub base_spread: Uint128, // in PRICE_SPREAD_PRECISION, split evenly between bid and ask
+    pub max_spread: Uint128,  // cap on either side once the dynamic spread is added, zero disables spreads
 }
 
 pub const MARKETS: Map<String, Market> = Map::new("markets");
@@ -1,5 +1,7 @@
 pub mod market;
-// pub mod order;
+pub mod order;
+pub mod referral;
+pub mod trading_rewards;
 pub mod state;
 pub mod user;
 pub mod history;
@@ -1,19 +1,83 @@
-use crate::package::types::{Order};
+use crate::package::types::{Order, PositionDirection};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use crate::error::ContractError;
 
-use cosmwasm_std::{Addr, Uint128};
-use cw_storage_plus::{Item, Map};
+use cosmwasm_std::{Addr, Order as StorageOrder, StdError, StdResult, Storage, Uint128};
+use std::convert::TryInto;
+use cw_storage_plus::{Item, Map, U64Key};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct OrderInfo {
     pub len: u64,
 }
 
-pub const ORDERS: Map<((&Addr, String), String), Order> = Map::new("orders");
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TwapOrder {
+    pub ts: u64,
+    pub market_index: u64,
+    pub direction: PositionDirection,
+    pub base_asset_amount: Uint128,
+    pub base_asset_amount_filled: Uint128,
+    pub quote_asset_amount_filled: Uint128,
+    pub slices: u64,
+    pub slices_filled: u64,
+    // slices whose fill would have broken the limit price, their base asset is not made up for
+    pub slices_skipped: u64,
+    // seconds between two slices
+    pub interval: u64,
+    pub limit_price: Option<Uint128>,
+    pub next_slice_ts: u64,
+}
+
+impl TwapOrder {
+    pub fn slices_executed(&self) -> u64 {
+        self.slices_filled + self.slices_skipped
+    }
+
+    // the last slice picks up whatever rounding left behind
+    pub fn next_slice_base_asset_amount(&self) -> Result<Uint128, ContractError> {
+        let slice_base_asset_amount = self.base_asset_amount.checked_div(Uint128::from(self.slices))?;
+        if self.slices_executed() + 1 >= self.slices {
+            let skipped_base_asset_amount =
+                slice_base_asset_amount.checked_mul(Uint128::from(self.slices_skipped))?;
+            return Ok(self
+                .base_asset_amount
+                .checked_sub(self.base_asset_amount_filled)?
+                .saturating_sub(skipped_base_asset_amount));
+        }
+        Ok(slice_base_asset_amount)
+    }
+}
+
+// keyed by position and order id, ids only grow so an order keeps its id until it is removed
+pub const ORDERS: Map<((&Addr, String), U64Key), Order> = Map::new("orders");
 pub const ORDERS_INFO: Item<OrderInfo> = Item::new("order_info");
+// keyed by twap order id, big endian so ranges come back in placement order
+pub const TWAP_ORDERS: Map<(&Addr, U64Key), TwapOrder> = Map::new("twap_orders");
+pub const TWAP_ORDERS_INFO: Item<OrderInfo> = Item::new("twap_order_info");
+
+/// Open orders of a position with their ids, oldest first
+pub fn get_position_orders(
+    storage: &dyn Storage,
+    user_addr: &Addr,
+    position_index: u64,
+) -> StdResult<Vec<(u64, Order)>> {
+    ORDERS
+        .prefix((user_addr, position_index.to_string()))
+        .range(storage, None, None, StorageOrder::Ascending)
+        .map(|item| {
+            let (k, order) = item?;
+            let order_id = u64::from_be_bytes(
+                k.as_slice()
+                    .try_into()
+                    .map_err(|_| StdError::generic_err("invalid order id"))?,
+            );
+            Ok((order_id, order))
+        })
+        .collect()
+}
 
 pub fn has_oracle_price_offset(oo: &Order) -> bool {
     oo.oracle_price_offset.i128() != 0
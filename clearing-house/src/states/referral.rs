@@ -0,0 +1,18 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{Addr, Uint128};
+use cw_storage_plus::Map;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct Referrer {
+    pub code: String, // empty for referrers set by address before codes existed
+    pub referee_count: u64,
+    pub total_reward: Uint128,
+    pub unclaimed_reward: Uint128,
+}
+
+pub const REFERRAL_CODES: Map<String, Addr> = Map::new("referral_codes");
+pub const REFERRERS: Map<&Addr, Referrer> = Map::new("referrers");
+// (referrer, referee) -> rewards the referrer earned from the referee's fees
+pub const REFEREES: Map<(&Addr, &Addr), Uint128> = Map::new("referees");
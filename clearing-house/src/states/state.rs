@@ -2,9 +2,9 @@ use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use cosmwasm_std::{Addr, Decimal, Uint128};
-use cw_storage_plus::Item;
+use cw_storage_plus::{Item, Map, U64Key};
 
-use crate::package::types::{FeeStructure, OracleGuardRails};
+use crate::package::types::{FeeStructure, FeeTier, KeeperRewards, OracleGuardRails, ParameterChange};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct State {
@@ -29,6 +29,9 @@ pub struct State {
 
     pub max_deposit: Uint128,
     pub markets_length: u64,
+
+    pub timelock_delay: u64,
+    pub pending_changes_length: u64,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -38,7 +41,37 @@ pub struct OrderState {
     pub time_based_reward_lower_bound: Uint128,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingChange {
+    pub change: ParameterChange,
+    pub proposer: Addr,
+    pub queued_ts: u64,
+    pub eta: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct KeeperRewardPool {
+    pub balance: Uint128,
+    pub total_paid: Uint128,
+}
+
+// pauses set through sudo, the pauser role can not lift them
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct GovernancePause {
+    pub exchange_paused: bool,
+    pub funding_paused: bool,
+}
+
 pub const STATE: Item<State> = Item::new("state");
 pub const FEESTRUCTURE: Item<FeeStructure> = Item::new("fee_structure");
 pub const ORACLEGUARDRAILS: Item<OracleGuardRails> = Item::new("oracle_guard_rails");
 pub const ORDERSTATE: Item<OrderState> = Item::new("order_state");
+pub const ROLES: Map<String, Vec<Addr>> = Map::new("roles");
+pub const GOVERNANCE_PAUSE: Item<GovernancePause> = Item::new("governance_pause");
+pub const PENDING_ADMIN: Item<Addr> = Item::new("pending_admin");
+// keyed by change id, big endian so ranges come back in queue order
+pub const PENDING_CHANGES: Map<U64Key, PendingChange> = Map::new("pending_changes");
+pub const KEEPER_REWARDS: Item<KeeperRewards> = Item::new("keeper_rewards");
+pub const KEEPER_REWARD_POOL: Item<KeeperRewardPool> = Item::new("keeper_reward_pool");
+pub const FEE_TIERS: Item<Vec<FeeTier>> = Item::new("fee_tiers");
+pub const STAKING_CONTRACT: Item<Addr> = Item::new("staking_contract");
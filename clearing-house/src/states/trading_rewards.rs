@@ -0,0 +1,46 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{Addr, Uint128};
+use cw_storage_plus::{Item, Map, U64Key};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct TradingRewards {
+    pub reward_token: Option<Addr>, // cw20 token held by the clearing house, used by epochs scheduled next
+    pub epochs_length: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TradingRewardEpoch {
+    pub reward_token: Addr, // the reward token when the epoch was scheduled, its claims pay in it
+    pub start_ts: u64,
+    pub end_ts: u64,
+    pub reward_amount: Uint128,
+    pub total_score: Uint128,
+    pub total_claimed: Uint128,
+}
+
+impl TradingRewardEpoch {
+    pub fn is_active(&self, now: u64) -> bool {
+        self.start_ts <= now && now < self.end_ts
+    }
+
+    pub fn reward_for_score(&self, score: Uint128) -> Uint128 {
+        if self.total_score.is_zero() {
+            return Uint128::zero();
+        }
+        self.reward_amount.multiply_ratio(score, self.total_score)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct TradingRewardScore {
+    pub score: Uint128, // fees paid during the epoch
+    pub claimed: bool,
+}
+
+pub const TRADING_REWARDS: Item<TradingRewards> = Item::new("trading_rewards");
+pub const TRADING_REWARD_EPOCHS: Map<U64Key, TradingRewardEpoch> = Map::new("trading_reward_epochs");
+// (user, epoch id) -> the user's score in that epoch
+pub const TRADING_REWARD_SCORES: Map<(&Addr, U64Key), TradingRewardScore> =
+    Map::new("trading_reward_scores");
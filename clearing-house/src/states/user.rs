@@ -2,7 +2,7 @@ use crate::package::number::Number128;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::{Addr, Uint128};
+use cosmwasm_std::{Addr, Decimal, Uint128};
 use cw_storage_plus::Map;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -14,6 +14,8 @@ pub struct User {
     pub total_referral_reward: Uint128,
     pub total_referee_discount: Uint128,
     pub referrer: Option<Addr>,
+    pub daily_volumes: Vec<Uint128>, // notional traded per day over the fee tier window
+    pub last_volume_update_ts: u64,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -24,7 +26,13 @@ pub struct Position {
     pub last_cumulative_funding_rate: Number128,
     pub last_cumulative_repeg_rebate: Uint128,
     pub last_funding_rate_ts: u64,
-    pub order_length: u64,
+    pub order_length: u64, // number of open orders
+    pub next_order_id: u64, // last order id handed out, ids are never reused
+    pub max_leverage: Option<Decimal>, // cap chosen by the user on top of the market's initial margin
+    pub entry_price: Uint128,          // average entry price of the open position, zero when closed
+    pub realized_pnl: Number128,
+    pub cumulative_funding_paid: Number128, // negative when the position received funding
+    pub cumulative_fees_paid: Uint128,
 }
 
 pub const USERS: Map<&Addr, User> = Map::new("users");
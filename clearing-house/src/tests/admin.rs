@@ -0,0 +1,145 @@
+use crate::error::ContractError;
+use crate::package::execute::ExecuteMsg;
+use crate::package::queries::QueryMsg;
+use crate::package::response::{AdminResponse, IsExchangePausedResponse, RolesResponse};
+use crate::package::types::Role;
+use crate::tests::setup::*;
+
+const PAUSER: &str = "pauser";
+const NEW_ADMIN: &str = "new_admin";
+
+fn members(deps: &TestDeps, role: Role) -> Vec<String> {
+    let roles: RolesResponse = query_msg(deps, QueryMsg::GetRoles {});
+    roles
+        .roles
+        .into_iter()
+        .find(|r| r.role == role)
+        .unwrap()
+        .members
+}
+
+fn pause(deps: &mut TestDeps, sender: &str, exchange_paused: bool) -> Result<(), ContractError> {
+    execute_msg(
+        deps,
+        sender,
+        START_TS,
+        ExecuteMsg::UpdateExchangePaused { exchange_paused },
+    )
+    .map(|_| ())
+}
+
+fn grant(
+    deps: &mut TestDeps,
+    sender: &str,
+    role: Role,
+    address: &str,
+) -> Result<(), ContractError> {
+    execute_msg(
+        deps,
+        sender,
+        START_TS,
+        ExecuteMsg::GrantRole {
+            role,
+            address: address.to_string(),
+        },
+    )
+    .map(|_| ())
+}
+
+#[test]
+fn test_instantiator_holds_every_role() {
+    let deps = setup(0);
+    for role in Role::all() {
+        assert_eq!(members(&deps, role), vec![ADMIN.to_string()]);
+    }
+}
+
+#[test]
+fn test_handlers_are_gated_by_role() {
+    let mut deps = setup(0);
+    let err = pause(&mut deps, PAUSER, true).unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+
+    grant(&mut deps, ADMIN, Role::Pauser, PAUSER).unwrap();
+    assert_eq!(
+        members(&deps, Role::Pauser),
+        vec![ADMIN.to_string(), PAUSER.to_string()]
+    );
+    pause(&mut deps, PAUSER, true).unwrap();
+    let paused: IsExchangePausedResponse = query_msg(&deps, QueryMsg::IsExchangePaused {});
+    assert!(paused.exchange_paused);
+
+    // the pauser role does not reach other handlers
+    let err = execute_msg(
+        &mut deps,
+        PAUSER,
+        START_TS,
+        ExecuteMsg::OracleFeeder {
+            market_index: MARKET_INDEX,
+            price: 2_000_000,
+        },
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+
+    execute_msg(
+        &mut deps,
+        ADMIN,
+        START_TS,
+        ExecuteMsg::RevokeRole {
+            role: Role::Pauser,
+            address: PAUSER.to_string(),
+        },
+    )
+    .unwrap();
+    let err = pause(&mut deps, PAUSER, false).unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+}
+
+#[test]
+fn test_only_admin_grants_roles() {
+    let mut deps = setup(0);
+    grant(&mut deps, ADMIN, Role::Pauser, PAUSER).unwrap();
+
+    let err = grant(&mut deps, PAUSER, Role::Pauser, "mallory").unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+    let err = grant(&mut deps, ADMIN, Role::Pauser, PAUSER).unwrap_err();
+    assert!(matches!(err, ContractError::RoleAlreadyGranted));
+}
+
+#[test]
+fn test_admin_handover_moves_roles() {
+    let mut deps = setup(0);
+    grant(&mut deps, ADMIN, Role::Pauser, PAUSER).unwrap();
+    execute_msg(
+        &mut deps,
+        ADMIN,
+        START_TS,
+        ExecuteMsg::ProposeNewAdmin {
+            new_admin: NEW_ADMIN.to_string(),
+        },
+    )
+    .unwrap();
+
+    // nothing changes hands until the proposed admin accepts
+    let err = execute_msg(&mut deps, PAUSER, START_TS, ExecuteMsg::AcceptAdmin {}).unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+    assert_eq!(members(&deps, Role::Treasury), vec![ADMIN.to_string()]);
+
+    execute_msg(&mut deps, NEW_ADMIN, START_TS, ExecuteMsg::AcceptAdmin {}).unwrap();
+    let admin: AdminResponse = query_msg(&deps, QueryMsg::GetAdmin {});
+    assert_eq!(admin.admin, NEW_ADMIN);
+    assert_eq!(admin.pending_admin, None);
+    for role in Role::all() {
+        assert!(!members(&deps, role.clone()).contains(&ADMIN.to_string()));
+    }
+    assert_eq!(members(&deps, Role::Treasury), vec![NEW_ADMIN.to_string()]);
+    assert_eq!(
+        members(&deps, Role::Pauser),
+        vec![PAUSER.to_string(), NEW_ADMIN.to_string()]
+    );
+
+    let err = pause(&mut deps, ADMIN, true).unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+    pause(&mut deps, NEW_ADMIN, true).unwrap();
+}
@@ -0,0 +1,317 @@
+use crate::controller::amm::auto_adjust_k;
+use crate::controller::repeg::{calculate_repeg_budget, formulaic_repeg};
+use crate::helpers::amm::calculate_spread;
+use crate::package::execute::ExecuteMsg;
+use crate::package::history::HistoryExecuteMsg;
+use crate::package::queries::QueryMsg;
+use crate::package::response::MarketInfoResponse;
+use crate::package::types::{KAdjustmentParams, PositionDirection};
+use crate::states::constants::{
+    MARK_PRICE_PRECISION, PEG_PRECISION, QUOTE_PRECISION,
+    SHARE_OF_FEES_ALLOCATED_TO_CLEARING_HOUSE_DENOMINATOR,
+    SHARE_OF_FEES_ALLOCATED_TO_CLEARING_HOUSE_NUMERATOR,
+};
+use crate::states::history::Type;
+use crate::states::market::MARKETS;
+use crate::states::user::POSITIONS;
+use crate::tests::setup::*;
+
+use cosmwasm_std::{Addr, Decimal, Uint128};
+
+const ALICE: &str = "alice";
+const TRADE_TS: u64 = START_TS + FUNDING_PERIOD;
+
+#[test]
+fn test_funding_update_repegs_a_balanced_market_to_the_oracle() {
+    let mut deps = setup(0);
+    feed_price(&mut deps, (MARK_PRICE_PRECISION.u128() * 11 / 10) as i128);
+
+    let res = execute_msg(
+        &mut deps,
+        "keeper",
+        TRADE_TS,
+        ExecuteMsg::UpdateFundingRate {
+            market_index: MARKET_INDEX,
+        },
+    )
+    .unwrap();
+
+    // without open interest the repeg is free, so it goes all the way
+    let market = MARKETS
+        .load(&deps.storage, MARKET_INDEX.to_string())
+        .unwrap();
+    assert_eq!(
+        market.amm.peg_multiplier,
+        PEG_PRECISION * Uint128::from(11u128) / Uint128::from(10u128)
+    );
+    assert_eq!(
+        mark_price(&deps),
+        MARK_PRICE_PRECISION * Uint128::from(11u128) / Uint128::from(10u128)
+    );
+    let curve = history(&res)
+        .into_iter()
+        .find_map(|h| match h {
+            HistoryExecuteMsg::RecordCurve { c } => Some(c),
+            _ => None,
+        })
+        .unwrap();
+    assert_eq!(curve.peg_multiplier_before, PEG_PRECISION);
+    assert_eq!(curve.peg_multiplier_after, market.amm.peg_multiplier);
+    assert_eq!(curve.adjustment_cost.i128(), 0);
+}
+
+#[test]
+fn test_repeg_cost_stays_within_the_fee_budget() {
+    let mut deps = setup(1_000_000 * QUOTE_PRECISION.u128());
+    deposit(
+        &mut deps,
+        ALICE,
+        10_000 * QUOTE_PRECISION.u128() as u64,
+        None,
+    );
+    open_position(
+        &mut deps,
+        ALICE,
+        TRADE_TS,
+        PositionDirection::Long,
+        5_000 * QUOTE_PRECISION.u128(),
+    );
+
+    // as if the insurance vault had topped the market up
+    let mut market = MARKETS
+        .load(&deps.storage, MARKET_INDEX.to_string())
+        .unwrap();
+    market.amm.total_fee_minus_distributions += Uint128::from(100 * QUOTE_PRECISION.u128());
+    MARKETS
+        .save(&mut deps.storage, MARKET_INDEX.to_string(), &market)
+        .unwrap();
+    let budget = calculate_repeg_budget(&market).unwrap();
+
+    // raising the peg under a net long costs the amm more than the budget, so it only goes part way
+    feed_price(&mut deps, (MARK_PRICE_PRECISION.u128() * 11 / 10) as i128);
+    let curve = formulaic_repeg(&mut deps.as_mut(), MARKET_INDEX, TRADE_TS)
+        .unwrap()
+        .unwrap();
+    assert!(curve.peg_multiplier_after > PEG_PRECISION);
+    assert!(
+        curve.peg_multiplier_after < PEG_PRECISION * Uint128::from(11u128) / Uint128::from(10u128)
+    );
+    assert!(curve.adjustment_cost.i128() > 0);
+    assert!(curve.adjustment_cost.i128() as u128 <= budget.u128());
+
+    // the clearing house's share of fees is left untouched
+    let market = MARKETS
+        .load(&deps.storage, MARKET_INDEX.to_string())
+        .unwrap();
+    let reserved_fee = market.amm.total_fee.multiply_ratio(
+        SHARE_OF_FEES_ALLOCATED_TO_CLEARING_HOUSE_NUMERATOR,
+        SHARE_OF_FEES_ALLOCATED_TO_CLEARING_HOUSE_DENOMINATOR,
+    );
+    assert!(market.amm.total_fee_minus_distributions >= reserved_fee);
+}
+
+fn set_k_adjustment(deps: &mut TestDeps, fee_pool_floor: u128, volume_threshold: u128) {
+    execute_msg(
+        deps,
+        ADMIN,
+        START_TS,
+        ExecuteMsg::UpdateKAdjustmentParams {
+            market_index: MARKET_INDEX,
+            k_adjustment: KAdjustmentParams {
+                k_change: Decimal::percent(1),
+                update_interval: FUNDING_PERIOD,
+                fee_pool_floor: Uint128::from(fee_pool_floor * QUOTE_PRECISION.u128()),
+                max_cost_per_update: Uint128::from(10 * QUOTE_PRECISION.u128()),
+                volume_threshold: Uint128::from(volume_threshold * QUOTE_PRECISION.u128()),
+            },
+        },
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_funding_update_shrinks_k_while_the_fee_pool_is_depleted() {
+    let mut deps = setup(0);
+    set_k_adjustment(&mut deps, 10, 1_000);
+    let sqrt_k_before = MARKETS
+        .load(&deps.storage, MARKET_INDEX.to_string())
+        .unwrap()
+        .amm
+        .sqrt_k;
+
+    let res = execute_msg(
+        &mut deps,
+        "keeper",
+        TRADE_TS,
+        ExecuteMsg::UpdateFundingRate {
+            market_index: MARKET_INDEX,
+        },
+    )
+    .unwrap();
+
+    let market = MARKETS
+        .load(&deps.storage, MARKET_INDEX.to_string())
+        .unwrap();
+    assert_eq!(
+        market.amm.sqrt_k,
+        sqrt_k_before - sqrt_k_before * Decimal::percent(1)
+    );
+    assert_eq!(market.last_k_update_ts, TRADE_TS);
+    assert_eq!(mark_price(&deps), MARK_PRICE_PRECISION);
+    let curve = history(&res)
+        .into_iter()
+        .find_map(|h| match h {
+            HistoryExecuteMsg::RecordCurve { c } => Some(c),
+            _ => None,
+        })
+        .unwrap();
+    assert_eq!(curve.record_type, Type::UpdateK);
+    assert_eq!(curve.sqrt_k_before, sqrt_k_before);
+    assert_eq!(curve.sqrt_k_after, market.amm.sqrt_k);
+}
+
+#[test]
+fn test_k_grows_once_volume_passes_the_threshold() {
+    let mut deps = setup(0);
+    set_k_adjustment(&mut deps, 10, 1_000);
+    let mut market = MARKETS
+        .load(&deps.storage, MARKET_INDEX.to_string())
+        .unwrap();
+    let sqrt_k_before = market.amm.sqrt_k;
+    market.amm.total_fee_minus_distributions = Uint128::from(100 * QUOTE_PRECISION.u128());
+    MARKETS
+        .save(&mut deps.storage, MARKET_INDEX.to_string(), &market)
+        .unwrap();
+
+    // a healthy pool alone does not grow k
+    assert!(auto_adjust_k(&mut deps.as_mut(), MARKET_INDEX, TRADE_TS)
+        .unwrap()
+        .is_none());
+
+    let mut market = MARKETS
+        .load(&deps.storage, MARKET_INDEX.to_string())
+        .unwrap();
+    market.volume_since_last_k_update = Uint128::from(1_000 * QUOTE_PRECISION.u128());
+    MARKETS
+        .save(&mut deps.storage, MARKET_INDEX.to_string(), &market)
+        .unwrap();
+    let curve = auto_adjust_k(&mut deps.as_mut(), MARKET_INDEX, TRADE_TS)
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        curve.sqrt_k_after,
+        sqrt_k_before + sqrt_k_before * Decimal::percent(1)
+    );
+    let market = MARKETS
+        .load(&deps.storage, MARKET_INDEX.to_string())
+        .unwrap();
+    assert!(market.volume_since_last_k_update.is_zero());
+
+    // and not again before the update interval has passed
+    let mut market = market;
+    market.volume_since_last_k_update = Uint128::from(1_000 * QUOTE_PRECISION.u128());
+    MARKETS
+        .save(&mut deps.storage, MARKET_INDEX.to_string(), &market)
+        .unwrap();
+    assert!(auto_adjust_k(
+        &mut deps.as_mut(),
+        MARKET_INDEX,
+        TRADE_TS + FUNDING_PERIOD - 1
+    )
+    .unwrap()
+    .is_none());
+}
+
+fn set_spread(deps: &mut TestDeps, base_spread: u128, max_spread: u128) {
+    execute_msg(
+        deps,
+        ADMIN,
+        START_TS,
+        ExecuteMsg::UpdateMarketSpread {
+            market_index: MARKET_INDEX,
+            base_spread: Uint128::from(base_spread),
+            max_spread: Uint128::from(max_spread),
+        },
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_market_info_quotes_around_the_mark_price() {
+    let mut deps = setup(0);
+    set_spread(&mut deps, 20, 100);
+    let info: MarketInfoResponse = query_msg(
+        &deps,
+        QueryMsg::GetMarketInfo {
+            market_index: MARKET_INDEX,
+        },
+    );
+
+    // each side carries half of the 0.2% base spread
+    let mark = MARK_PRICE_PRECISION.u128();
+    assert!(info.bid_price.u128() < mark && mark < info.ask_price.u128());
+    assert!((info.ask_price.u128() - mark).abs_diff(mark / 1000) <= mark / 100_000);
+    assert!((mark - info.bid_price.u128()).abs_diff(mark / 1000) <= mark / 100_000);
+}
+
+#[test]
+fn test_spread_widens_the_side_trading_towards_the_oracle() {
+    let mut deps = setup(0);
+    set_spread(&mut deps, 20, 5000);
+
+    // the mark 20% below the oracle, longs push the mark towards it
+    feed_price(&mut deps, (MARK_PRICE_PRECISION.u128() * 5 / 4) as i128);
+    let market = MARKETS
+        .load(&deps.storage, MARKET_INDEX.to_string())
+        .unwrap();
+    let (long_spread, short_spread) =
+        calculate_spread(&market, market.amm.mark_price().unwrap()).unwrap();
+    assert_eq!(long_spread, 2000);
+    assert_eq!(short_spread, 10);
+
+    // and never past the cap
+    set_spread(&mut deps, 20, 500);
+    let market = MARKETS
+        .load(&deps.storage, MARKET_INDEX.to_string())
+        .unwrap();
+    let (long_spread, _) = calculate_spread(&market, market.amm.mark_price().unwrap()).unwrap();
+    assert_eq!(long_spread, 500);
+}
+
+#[test]
+fn test_long_pays_the_spread() {
+    let base_asset_amount = |base_spread: u128| {
+        let mut deps = setup(1_000_000 * QUOTE_PRECISION.u128());
+        set_spread(&mut deps, base_spread, 100);
+        deposit(
+            &mut deps,
+            ALICE,
+            10_000 * QUOTE_PRECISION.u128() as u64,
+            None,
+        );
+        open_position(
+            &mut deps,
+            ALICE,
+            TRADE_TS,
+            PositionDirection::Long,
+            1_000 * QUOTE_PRECISION.u128(),
+        );
+        POSITIONS
+            .load(
+                &deps.storage,
+                (&Addr::unchecked(ALICE), MARKET_INDEX.to_string()),
+            )
+            .unwrap()
+            .base_asset_amount
+            .i128()
+            .unsigned_abs()
+    };
+
+    let without_spread = base_asset_amount(0);
+    let with_spread = base_asset_amount(20);
+    assert!(with_spread < without_spread);
+    // roughly the 0.1% half spread less base for the same quote
+    assert!(
+        (without_spread - with_spread).abs_diff(without_spread / 1000) <= without_spread / 10_000
+    );
+}
@@ -0,0 +1,115 @@
+use crate::error::ContractError;
+use crate::package::execute::{BatchAction, ExecuteMsg};
+use crate::package::history::HistoryExecuteMsg;
+use crate::package::types::PositionDirection;
+use crate::states::constants::QUOTE_PRECISION;
+use crate::states::user::POSITIONS;
+use crate::tests::setup::*;
+
+use cosmwasm_std::{Addr, Decimal, Response, Uint128};
+
+const ALICE: &str = "alice";
+const TRADE_TS: u64 = START_TS + FUNDING_PERIOD;
+
+fn setup_alice() -> TestDeps {
+    let mut deps = setup(1_000_000 * QUOTE_PRECISION.u128());
+    deposit(
+        &mut deps,
+        ALICE,
+        1_000 * QUOTE_PRECISION.u128() as u64,
+        None,
+    );
+    deps
+}
+
+fn open(direction: PositionDirection, quote_asset_amount: u128) -> BatchAction {
+    BatchAction::OpenPosition {
+        direction,
+        quote_asset_amount: Uint128::from(quote_asset_amount),
+        base_asset_amount: None,
+        market_index: MARKET_INDEX,
+        limit_price: None,
+    }
+}
+
+fn batch(deps: &mut TestDeps, actions: Vec<BatchAction>) -> Result<Response, ContractError> {
+    execute_msg(deps, ALICE, TRADE_TS, ExecuteMsg::Batch { actions })
+}
+
+#[test]
+fn test_batch_records_its_trades_in_one_message() {
+    let mut deps = setup_alice();
+    let res = batch(
+        &mut deps,
+        vec![
+            open(PositionDirection::Long, 2_000 * QUOTE_PRECISION.u128()),
+            open(PositionDirection::Short, 500 * QUOTE_PRECISION.u128()),
+        ],
+    )
+    .unwrap();
+
+    let position = POSITIONS
+        .load(
+            &deps.storage,
+            (&Addr::unchecked(ALICE), MARKET_INDEX.to_string()),
+        )
+        .unwrap();
+    assert!(position.base_asset_amount.i128() > 0);
+
+    let batches: Vec<_> = history(&res)
+        .into_iter()
+        .filter_map(|h| match h {
+            HistoryExecuteMsg::RecordBatch { records } => Some(records),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(batches.len(), 1);
+    let trades = batches[0]
+        .iter()
+        .filter(|r| matches!(r, HistoryExecuteMsg::RecordTrade { .. }))
+        .count();
+    assert_eq!(trades, 2);
+}
+
+#[test]
+fn test_batch_rejects_an_unsafe_final_state() {
+    let mut deps = setup_alice();
+
+    // 6x is above the 5x initial margin limit
+    let err = batch(
+        &mut deps,
+        vec![open(
+            PositionDirection::Long,
+            6_000 * QUOTE_PRECISION.u128(),
+        )],
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::InsufficientCollateral));
+}
+
+#[test]
+fn test_batch_checks_margin_on_the_final_state() {
+    let mut deps = setup_alice();
+
+    // passing through 6x is fine as long as the batch ends within the limit
+    batch(
+        &mut deps,
+        vec![
+            open(PositionDirection::Long, 6_000 * QUOTE_PRECISION.u128()),
+            BatchAction::ClosePosition {
+                market_index: MARKET_INDEX,
+                base_asset_amount: None,
+                percentage: Some(Decimal::percent(50)),
+                limit_price: None,
+            },
+        ],
+    )
+    .unwrap();
+    let position = POSITIONS
+        .load(
+            &deps.storage,
+            (&Addr::unchecked(ALICE), MARKET_INDEX.to_string()),
+        )
+        .unwrap();
+    assert!(position.base_asset_amount.i128() > 0);
+}
@@ -0,0 +1,100 @@
+use crate::controller::amm::update_circuit_breaker;
+use crate::error::ContractError;
+use crate::package::execute::ExecuteMsg;
+use crate::package::queries::QueryMsg;
+use crate::package::response::CircuitBreakerResponse;
+use crate::package::types::{CircuitBreaker, PositionDirection};
+use crate::states::constants::{MARK_PRICE_PRECISION, QUOTE_PRECISION};
+use crate::tests::setup::*;
+
+use cosmwasm_std::{Decimal, Uint128};
+
+const ALICE: &str = "alice";
+const COOL_DOWN_PERIOD: u64 = 600;
+const TRADE_TS: u64 = START_TS + FUNDING_PERIOD;
+
+/// The mark price jumps 5% past a 2% breaker
+fn setup_jump() -> TestDeps {
+    let mut deps = setup(1_000_000 * QUOTE_PRECISION.u128());
+    execute_msg(
+        &mut deps,
+        ADMIN,
+        START_TS,
+        ExecuteMsg::UpdateCircuitBreaker {
+            market_index: MARKET_INDEX,
+            circuit_breaker: CircuitBreaker {
+                max_price_move: Decimal::percent(2),
+                window: FUNDING_PERIOD,
+                cool_down_period: COOL_DOWN_PERIOD,
+            },
+        },
+    )
+    .unwrap();
+    deposit(
+        &mut deps,
+        ALICE,
+        10_000 * QUOTE_PRECISION.u128() as u64,
+        None,
+    );
+    move_mark_price(&mut deps, 500);
+    deps
+}
+
+fn open_long(deps: &mut TestDeps, ts: u64) -> Result<(), ContractError> {
+    execute_msg(
+        deps,
+        ALICE,
+        ts,
+        ExecuteMsg::OpenPosition {
+            direction: PositionDirection::Long,
+            quote_asset_amount: Uint128::from(100 * QUOTE_PRECISION.u128()),
+            base_asset_amount: None,
+            market_index: MARKET_INDEX,
+            limit_price: None,
+        },
+    )
+    .map(|_| ())
+}
+
+fn circuit_breaker(deps: &TestDeps) -> CircuitBreakerResponse {
+    query_msg(
+        deps,
+        QueryMsg::GetCircuitBreaker {
+            market_index: MARKET_INDEX,
+        },
+    )
+}
+
+#[test]
+fn test_risk_increasing_trade_past_the_limit_is_rejected() {
+    let mut deps = setup_jump();
+    let err = open_long(&mut deps, TRADE_TS).unwrap_err();
+    assert!(matches!(err, ContractError::CircuitBreakerTripped));
+}
+
+#[test]
+fn test_tripped_breaker_starts_the_cool_down() {
+    let mut deps = setup_jump();
+    assert_eq!(circuit_breaker(&deps).cool_down_until_ts, 0);
+
+    // what a risk reducing trade landing on the jumped price reports
+    let mark_price_after = mark_price(&deps);
+    let (in_cool_down, tripped) = update_circuit_breaker(
+        &mut deps.as_mut(),
+        MARKET_INDEX,
+        TRADE_TS,
+        MARK_PRICE_PRECISION,
+        mark_price_after,
+    )
+    .unwrap();
+    assert!(in_cool_down);
+    assert!(tripped);
+
+    let breaker = circuit_breaker(&deps);
+    assert_eq!(breaker.reference_price, MARK_PRICE_PRECISION);
+    assert_eq!(breaker.reference_ts, TRADE_TS);
+    assert_eq!(breaker.cool_down_until_ts, TRADE_TS + COOL_DOWN_PERIOD);
+
+    let err = open_long(&mut deps, TRADE_TS).unwrap_err();
+    assert!(matches!(err, ContractError::MarketInCoolDown));
+}
@@ -0,0 +1,403 @@
+use crate::error::ContractError;
+use crate::package::execute::ExecuteMsg;
+use crate::package::helper::{Cw20Interface, VaultInterface};
+use crate::package::queries::QueryMsg;
+use crate::package::response::UserTradingRewardsResponse;
+use crate::package::types::PositionDirection;
+use crate::states::constants::QUOTE_PRECISION;
+use crate::states::referral::REFERRERS;
+use crate::states::trading_rewards::{TRADING_REWARD_EPOCHS, TRADING_REWARD_SCORES};
+use crate::tests::setup::*;
+
+use cosmwasm_std::testing::MOCK_CONTRACT_ADDR;
+use cosmwasm_std::{Addr, Uint128};
+use cw_storage_plus::U64Key;
+
+const ALICE: &str = "alice";
+const BOB: &str = "bob";
+const REFERRER: &str = "referrer";
+const REWARD_TOKEN: &str = "reward_token";
+const NEXT_REWARD_TOKEN: &str = "next_reward_token";
+
+const EPOCH_START_TS: u64 = START_TS + 100;
+const EPOCH_END_TS: u64 = START_TS + 200;
+
+fn setup_traders() -> TestDeps {
+    let mut deps = setup(1_000_000 * QUOTE_PRECISION.u128());
+    deposit(
+        &mut deps,
+        ALICE,
+        10_000 * QUOTE_PRECISION.u128() as u64,
+        None,
+    );
+    deposit(&mut deps, BOB, 10_000 * QUOTE_PRECISION.u128() as u64, None);
+    deps
+}
+
+fn schedule_epoch(
+    deps: &mut TestDeps,
+    reward_token: &str,
+    start_ts: u64,
+    end_ts: u64,
+    reward_amount: Uint128,
+) {
+    execute_msg(
+        deps,
+        ADMIN,
+        START_TS,
+        ExecuteMsg::UpdateTradingRewardToken {
+            reward_token: reward_token.to_string(),
+        },
+    )
+    .unwrap();
+    execute_msg(
+        deps,
+        ADMIN,
+        START_TS,
+        ExecuteMsg::ScheduleTradingRewardEpoch {
+            start_ts,
+            end_ts,
+            reward_amount,
+        },
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_claim_referral_rewards() {
+    let mut deps = setup(1_000_000 * QUOTE_PRECISION.u128());
+    execute_msg(
+        &mut deps,
+        REFERRER,
+        START_TS,
+        ExecuteMsg::RegisterReferralCode {
+            code: "friends".to_string(),
+        },
+    )
+    .unwrap();
+    deposit(
+        &mut deps,
+        ALICE,
+        10_000 * QUOTE_PRECISION.u128() as u64,
+        Some("friends".to_string()),
+    );
+    open_position(
+        &mut deps,
+        ALICE,
+        START_TS,
+        PositionDirection::Long,
+        1_000 * QUOTE_PRECISION.u128(),
+    );
+
+    let referrer = Addr::unchecked(REFERRER);
+    let unclaimed = REFERRERS
+        .load(&deps.storage, &referrer)
+        .unwrap()
+        .unclaimed_reward;
+    assert!(!unclaimed.is_zero());
+
+    let res = execute_msg(
+        &mut deps,
+        REFERRER,
+        START_TS,
+        ExecuteMsg::ClaimReferralRewards {},
+    )
+    .unwrap();
+    let withdrawals: Vec<VaultInterface> = wasm_messages(&res, MOCK_CONTRACT_ADDR);
+    assert_eq!(
+        withdrawals,
+        vec![VaultInterface::Withdraw {
+            to_address: referrer.clone(),
+            amount: unclaimed,
+        }]
+    );
+    let stored = REFERRERS.load(&deps.storage, &referrer).unwrap();
+    assert!(stored.unclaimed_reward.is_zero());
+    assert_eq!(stored.total_reward, unclaimed);
+
+    let err = execute_msg(
+        &mut deps,
+        REFERRER,
+        START_TS,
+        ExecuteMsg::ClaimReferralRewards {},
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::NoReferralRewards));
+}
+
+#[test]
+fn test_claim_referral_rewards_without_referrals() {
+    let mut deps = setup(1_000_000 * QUOTE_PRECISION.u128());
+    deposit(
+        &mut deps,
+        ALICE,
+        10_000 * QUOTE_PRECISION.u128() as u64,
+        None,
+    );
+    let err = execute_msg(
+        &mut deps,
+        ALICE,
+        START_TS,
+        ExecuteMsg::ClaimReferralRewards {},
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::NoReferralRewards));
+}
+
+#[test]
+fn test_claim_trading_rewards_pro_rata() {
+    let mut deps = setup_traders();
+    let reward_amount = Uint128::from(1_000 * QUOTE_PRECISION.u128());
+    schedule_epoch(
+        &mut deps,
+        REWARD_TOKEN,
+        EPOCH_START_TS,
+        EPOCH_END_TS,
+        reward_amount,
+    );
+
+    // trades before the epoch starts don't score
+    open_position(
+        &mut deps,
+        ALICE,
+        START_TS,
+        PositionDirection::Long,
+        1_000 * QUOTE_PRECISION.u128(),
+    );
+    open_position(
+        &mut deps,
+        ALICE,
+        EPOCH_START_TS,
+        PositionDirection::Long,
+        1_000 * QUOTE_PRECISION.u128(),
+    );
+    open_position(
+        &mut deps,
+        BOB,
+        EPOCH_START_TS,
+        PositionDirection::Short,
+        3_000 * QUOTE_PRECISION.u128(),
+    );
+
+    let alice = Addr::unchecked(ALICE);
+    let bob = Addr::unchecked(BOB);
+    let alice_score = TRADING_REWARD_SCORES
+        .load(&deps.storage, (&alice, U64Key::from(1)))
+        .unwrap()
+        .score;
+    let bob_score = TRADING_REWARD_SCORES
+        .load(&deps.storage, (&bob, U64Key::from(1)))
+        .unwrap()
+        .score;
+    assert_eq!(bob_score, alice_score * Uint128::from(3u128));
+
+    // nothing is claimable while the epoch runs
+    let err = execute_msg(
+        &mut deps,
+        ALICE,
+        EPOCH_START_TS,
+        ExecuteMsg::ClaimTradingRewards {},
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::NoTradingRewards));
+
+    let res = execute_msg(
+        &mut deps,
+        ALICE,
+        EPOCH_END_TS,
+        ExecuteMsg::ClaimTradingRewards {},
+    )
+    .unwrap();
+    let transfers: Vec<Cw20Interface> = wasm_messages(&res, REWARD_TOKEN);
+    assert_eq!(
+        transfers,
+        vec![Cw20Interface::Transfer {
+            recipient: ALICE.to_string(),
+            amount: reward_amount.multiply_ratio(1u128, 4u128),
+        }]
+    );
+    let res = execute_msg(
+        &mut deps,
+        BOB,
+        EPOCH_END_TS,
+        ExecuteMsg::ClaimTradingRewards {},
+    )
+    .unwrap();
+    let transfers: Vec<Cw20Interface> = wasm_messages(&res, REWARD_TOKEN);
+    assert_eq!(
+        transfers,
+        vec![Cw20Interface::Transfer {
+            recipient: BOB.to_string(),
+            amount: reward_amount.multiply_ratio(3u128, 4u128),
+        }]
+    );
+
+    let epoch = TRADING_REWARD_EPOCHS
+        .load(&deps.storage, U64Key::from(1))
+        .unwrap();
+    assert_eq!(epoch.total_claimed, reward_amount);
+
+    // each epoch pays out once
+    let err = execute_msg(
+        &mut deps,
+        ALICE,
+        EPOCH_END_TS,
+        ExecuteMsg::ClaimTradingRewards {},
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::NoTradingRewards));
+}
+
+#[test]
+fn test_claim_trading_rewards_in_each_epochs_token() {
+    let mut deps = setup_traders();
+    let reward_amount = Uint128::from(1_000 * QUOTE_PRECISION.u128());
+    schedule_epoch(
+        &mut deps,
+        REWARD_TOKEN,
+        EPOCH_START_TS,
+        EPOCH_END_TS,
+        reward_amount,
+    );
+    // switching the token only applies to epochs scheduled afterwards
+    schedule_epoch(
+        &mut deps,
+        NEXT_REWARD_TOKEN,
+        EPOCH_END_TS,
+        EPOCH_END_TS + 100,
+        reward_amount,
+    );
+
+    open_position(
+        &mut deps,
+        ALICE,
+        EPOCH_START_TS,
+        PositionDirection::Long,
+        1_000 * QUOTE_PRECISION.u128(),
+    );
+    open_position(
+        &mut deps,
+        ALICE,
+        EPOCH_END_TS,
+        PositionDirection::Long,
+        1_000 * QUOTE_PRECISION.u128(),
+    );
+
+    let res = execute_msg(
+        &mut deps,
+        ALICE,
+        EPOCH_END_TS + 100,
+        ExecuteMsg::ClaimTradingRewards {},
+    )
+    .unwrap();
+    for reward_token in [REWARD_TOKEN, NEXT_REWARD_TOKEN] {
+        let transfers: Vec<Cw20Interface> = wasm_messages(&res, reward_token);
+        assert_eq!(
+            transfers,
+            vec![Cw20Interface::Transfer {
+                recipient: ALICE.to_string(),
+                amount: reward_amount,
+            }]
+        );
+    }
+}
+
+#[test]
+fn test_claim_trading_rewards_without_epochs() {
+    let mut deps = setup_traders();
+    let err = execute_msg(
+        &mut deps,
+        ALICE,
+        START_TS,
+        ExecuteMsg::ClaimTradingRewards {},
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::NoTradingRewards));
+}
+
+#[test]
+fn test_claim_trading_rewards_while_paused() {
+    let mut deps = setup_traders();
+    let reward_amount = Uint128::from(1_000 * QUOTE_PRECISION.u128());
+    schedule_epoch(
+        &mut deps,
+        REWARD_TOKEN,
+        EPOCH_START_TS,
+        EPOCH_END_TS,
+        reward_amount,
+    );
+    open_position(
+        &mut deps,
+        ALICE,
+        EPOCH_START_TS,
+        PositionDirection::Long,
+        1_000 * QUOTE_PRECISION.u128(),
+    );
+    execute_msg(
+        &mut deps,
+        ADMIN,
+        START_TS,
+        ExecuteMsg::UpdateExchangePaused {
+            exchange_paused: true,
+        },
+    )
+    .unwrap();
+
+    let err = execute_msg(
+        &mut deps,
+        ALICE,
+        EPOCH_END_TS,
+        ExecuteMsg::ClaimTradingRewards {},
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::ExchangePaused));
+}
+
+#[test]
+fn test_user_trading_rewards_are_paginated_by_epoch() {
+    let mut deps = setup_traders();
+    let reward_amount = Uint128::from(1_000 * QUOTE_PRECISION.u128());
+    schedule_epoch(
+        &mut deps,
+        REWARD_TOKEN,
+        EPOCH_START_TS,
+        EPOCH_END_TS,
+        reward_amount,
+    );
+    schedule_epoch(
+        &mut deps,
+        REWARD_TOKEN,
+        EPOCH_END_TS,
+        EPOCH_END_TS + 100,
+        reward_amount,
+    );
+    open_position(
+        &mut deps,
+        ALICE,
+        EPOCH_START_TS,
+        PositionDirection::Long,
+        1_000 * QUOTE_PRECISION.u128(),
+    );
+    open_position(
+        &mut deps,
+        ALICE,
+        EPOCH_END_TS,
+        PositionDirection::Long,
+        1_000 * QUOTE_PRECISION.u128(),
+    );
+
+    let page = |start_after: Option<u64>| -> Vec<u64> {
+        let rewards: UserTradingRewardsResponse = query_msg(
+            &deps,
+            QueryMsg::GetUserTradingRewards {
+                user_address: ALICE.to_string(),
+                start_after,
+                limit: Some(1),
+            },
+        );
+        rewards.epochs.iter().map(|e| e.epoch_id).collect()
+    };
+    assert_eq!(page(None), vec![1]);
+    assert_eq!(page(Some(1)), vec![2]);
+    assert!(page(Some(2)).is_empty());
+}
@@ -0,0 +1,113 @@
+use crate::helpers::fees::{add_to_rolling_volume, calculate_fee_tier, calculate_rolling_volume};
+use crate::package::types::{FeeStructure, FeeTier};
+use crate::states::constants::ONE_DAY;
+use crate::states::state::FEESTRUCTURE;
+use crate::tests::setup::*;
+
+use cosmwasm_std::{Decimal, Uint128};
+
+/// Replays `(ts, notional)` trades and returns the buckets with the time of the last update
+fn trade(trades: &[(u64, u128)]) -> (Vec<Uint128>, u64) {
+    let mut daily_volumes = vec![];
+    let mut last_update_ts = 0;
+    for (ts, amount) in trades {
+        daily_volumes =
+            add_to_rolling_volume(&daily_volumes, last_update_ts, *ts, Uint128::from(*amount))
+                .unwrap();
+        last_update_ts = *ts;
+    }
+    (daily_volumes, last_update_ts)
+}
+
+fn volume_at(trades: &[(u64, u128)], now: u64) -> u128 {
+    let (daily_volumes, last_update_ts) = trade(trades);
+    calculate_rolling_volume(&daily_volumes, last_update_ts, now)
+        .unwrap()
+        .u128()
+}
+
+#[test]
+fn test_rolling_volume_keeps_trades_for_thirty_days() {
+    let day = |n: u64| START_TS + n * ONE_DAY;
+    let trades = [(day(0), 100), (day(10), 200), (day(10) + 60, 50)];
+
+    assert_eq!(volume_at(&trades, day(10)), 350);
+    // nothing decays while a trade is in the window
+    assert_eq!(volume_at(&trades, day(29)), 350);
+    // each day's volume leaves the window whole, thirty days later
+    assert_eq!(volume_at(&trades, day(30)), 250);
+    assert_eq!(volume_at(&trades, day(39)), 250);
+    assert_eq!(volume_at(&trades, day(40)), 0);
+}
+
+#[test]
+fn test_rolling_volume_does_not_compound_with_frequent_trades() {
+    // one trade a day for thirty days is thirty days of volume, however often it's updated
+    let trades: Vec<(u64, u128)> = (0..30).map(|n| (START_TS + n * ONE_DAY, 1_000)).collect();
+    assert_eq!(volume_at(&trades, START_TS + 29 * ONE_DAY), 30_000);
+    assert_eq!(volume_at(&trades, START_TS + 30 * ONE_DAY), 29_000);
+}
+
+#[test]
+fn test_rolling_volume_after_a_long_pause() {
+    let trades = [(START_TS, 100), (START_TS + 365 * ONE_DAY, 10)];
+    assert_eq!(volume_at(&trades, START_TS + 365 * ONE_DAY), 10);
+}
+
+fn fee_tiers() -> Vec<FeeTier> {
+    vec![
+        FeeTier {
+            min_volume: Uint128::from(1_000u128),
+            taker_fee: Decimal::permille(1),
+            maker_fee: Decimal::zero(),
+            maker_rebate: Decimal::zero(),
+        },
+        FeeTier {
+            min_volume: Uint128::from(10_000u128),
+            taker_fee: Decimal::permille(1) / Uint128::from(2u128),
+            maker_fee: Decimal::zero(),
+            maker_rebate: Decimal::permille(1) / Uint128::from(10u128),
+        },
+    ]
+}
+
+fn fee_structure() -> FeeStructure {
+    let deps = setup(0);
+    FEESTRUCTURE.load(&deps.storage).unwrap()
+}
+
+#[test]
+fn test_fee_tier_below_the_first_tier_pays_the_flat_fee() {
+    let fee_structure = fee_structure();
+    let (tier, fee_tier) = calculate_fee_tier(&fee_structure, &fee_tiers(), Uint128::from(999u128));
+    assert_eq!(tier, None);
+    assert_eq!(fee_tier.taker_fee, fee_structure.fee);
+    assert_eq!(fee_tier.maker_fee, Decimal::zero());
+    assert_eq!(fee_tier.maker_rebate, Decimal::zero());
+}
+
+#[test]
+fn test_fee_tier_picks_the_highest_tier_reached() {
+    let fee_structure = fee_structure();
+    let tiers = fee_tiers();
+    assert_eq!(
+        calculate_fee_tier(&fee_structure, &tiers, Uint128::from(1_000u128)),
+        (Some(0), tiers[0].clone())
+    );
+    assert_eq!(
+        calculate_fee_tier(&fee_structure, &tiers, Uint128::from(9_999u128)),
+        (Some(0), tiers[0].clone())
+    );
+    assert_eq!(
+        calculate_fee_tier(&fee_structure, &tiers, Uint128::from(1_000_000u128)),
+        (Some(1), tiers[1].clone())
+    );
+}
+
+#[test]
+fn test_fee_tier_without_tiers() {
+    let fee_structure = fee_structure();
+    let (tier, fee_tier) = calculate_fee_tier(&fee_structure, &[], Uint128::from(1_000_000u128));
+    assert_eq!(tier, None);
+    assert_eq!(fee_tier.taker_fee, fee_structure.fee);
+}
@@ -0,0 +1,193 @@
+use crate::package::execute::ExecuteMsg;
+use crate::package::history::HistoryExecuteMsg;
+use crate::package::number::Number128;
+use crate::package::queries::QueryMsg;
+use crate::package::response::{FundingInfoResponse, PendingFundingResponse};
+use crate::package::types::PositionDirection;
+use crate::states::constants::{AMM_TO_QUOTE_PRECISION_RATIO_I128, QUOTE_PRECISION};
+use crate::states::market::MARKETS;
+use crate::states::user::POSITIONS;
+use crate::tests::setup::*;
+
+use cosmwasm_std::{Addr, Response};
+
+const ALICE: &str = "alice";
+const BOB: &str = "bob";
+const KEEPER: &str = "keeper";
+const TRADE_TS: u64 = START_TS + FUNDING_PERIOD;
+// 0.01 UST per unit of base
+const CUMULATIVE_FUNDING_RATE: i128 = 1_000_000_000_000;
+
+/// Alice and Bob are long while the longs' cumulative funding rate moved on
+fn setup_funding_owed() -> TestDeps {
+    let mut deps = setup(1_000_000 * QUOTE_PRECISION.u128());
+    for user in [ALICE, BOB] {
+        deposit(&mut deps, user, 100 * QUOTE_PRECISION.u128() as u64, None);
+        open_position(
+            &mut deps,
+            user,
+            TRADE_TS,
+            PositionDirection::Long,
+            100 * QUOTE_PRECISION.u128(),
+        );
+    }
+    let mut market = MARKETS
+        .load(&deps.storage, MARKET_INDEX.to_string())
+        .unwrap();
+    market.amm.cumulative_funding_rate_long = Number128::new(CUMULATIVE_FUNDING_RATE);
+    MARKETS
+        .save(&mut deps.storage, MARKET_INDEX.to_string(), &market)
+        .unwrap();
+    deps
+}
+
+fn settle_for(deps: &mut TestDeps, users: &[&str]) -> Response {
+    execute_msg(
+        deps,
+        KEEPER,
+        TRADE_TS,
+        ExecuteMsg::SettleFundingPaymentFor {
+            users: users.iter().map(|u| u.to_string()).collect(),
+        },
+    )
+    .unwrap()
+}
+
+fn last_cumulative_funding_rate(deps: &TestDeps, user: &str) -> i128 {
+    POSITIONS
+        .load(
+            &deps.storage,
+            (&Addr::unchecked(user), MARKET_INDEX.to_string()),
+        )
+        .unwrap()
+        .last_cumulative_funding_rate
+        .i128()
+}
+
+#[test]
+fn test_keeper_settles_funding_for_every_listed_user() {
+    let mut deps = setup_funding_owed();
+    // users without an account are skipped
+    let res = settle_for(&mut deps, &[ALICE, BOB, "carol"]);
+
+    match &history(&res)[..] {
+        [HistoryExecuteMsg::RecordFundingPaymentsMultiple { vecf }] => {
+            let users: Vec<_> = vecf.iter().map(|f| f.user.as_str()).collect();
+            assert_eq!(users, vec![ALICE, BOB]);
+        }
+        _ => panic!("expected one funding payments record"),
+    }
+    for user in [ALICE, BOB] {
+        assert_eq!(
+            last_cumulative_funding_rate(&deps, user),
+            CUMULATIVE_FUNDING_RATE
+        );
+    }
+}
+
+#[test]
+fn test_settled_users_are_not_settled_twice() {
+    let mut deps = setup_funding_owed();
+    settle_for(&mut deps, &[ALICE]);
+    let res = settle_for(&mut deps, &[ALICE, BOB]);
+
+    match &history(&res)[..] {
+        [HistoryExecuteMsg::RecordFundingPaymentsMultiple { vecf }] => {
+            assert_eq!(vecf.len(), 1);
+            assert_eq!(vecf[0].user.as_str(), BOB);
+        }
+        _ => panic!("expected one funding payments record"),
+    }
+}
+
+fn pending_funding(deps: &TestDeps, user: &str) -> PendingFundingResponse {
+    query_msg(
+        deps,
+        QueryMsg::GetPendingFunding {
+            user_address: user.to_string(),
+        },
+    )
+}
+
+#[test]
+fn test_pending_funding_is_what_settlement_pays() {
+    let mut deps = setup_funding_owed();
+    let pending = pending_funding(&deps, ALICE);
+    assert_eq!(pending.positions.len(), 1);
+    assert_eq!(pending.positions[0].market_index, MARKET_INDEX);
+    // longs pay while their cumulative funding rate rises
+    assert!(pending.total_pending_funding_payment.i128() < 0);
+
+    let res = settle_for(&mut deps, &[ALICE]);
+    let settled = match &history(&res)[..] {
+        [HistoryExecuteMsg::RecordFundingPaymentsMultiple { vecf }] => {
+            vecf[0].funding_payment.i128()
+        }
+        _ => panic!("expected one funding payments record"),
+    };
+    assert_eq!(
+        pending.total_pending_funding_payment.i128(),
+        settled / AMM_TO_QUOTE_PRECISION_RATIO_I128.u128() as i128
+    );
+    assert_eq!(
+        pending_funding(&deps, ALICE)
+            .total_pending_funding_payment
+            .i128(),
+        0
+    );
+}
+
+#[test]
+fn test_funding_info_counts_down_to_the_next_update() {
+    let deps = setup(0);
+    let info: FundingInfoResponse = query_msg(
+        &deps,
+        QueryMsg::GetFundingInfo {
+            market_index: MARKET_INDEX,
+        },
+    );
+    assert_eq!(info.next_funding_rate_ts, START_TS + FUNDING_PERIOD);
+    assert_eq!(info.seconds_until_next_update, FUNDING_PERIOD);
+    // a balanced market priced at the oracle owes no funding
+    assert_eq!(info.estimated_funding_rate.i128(), 0);
+}
+
+#[test]
+fn test_premium_index_is_time_weighted() {
+    let deps = setup(0);
+    let mut market = MARKETS
+        .load(&deps.storage, MARKET_INDEX.to_string())
+        .unwrap();
+    market.premium_index_sum = Number128::zero();
+    market.premium_index_duration = 0;
+    market.last_premium = Number128::new(100);
+    market.last_premium_ts = START_TS;
+
+    // 100 held for 30s, then -50 for 10s
+    market.accrue_premium_index(START_TS + 30).unwrap();
+    market.last_premium = Number128::new(-50);
+    market.accrue_premium_index(START_TS + 40).unwrap();
+
+    assert_eq!(market.premium_index_sum.i128(), 100 * 30 - 50 * 10);
+    assert_eq!(market.premium_index_duration, 40);
+    assert_eq!(
+        market.average_premium_index(),
+        Some((100 * 30 - 50 * 10) / 40)
+    );
+}
+
+#[test]
+fn test_premium_index_ignores_time_going_backwards() {
+    let deps = setup(0);
+    let mut market = MARKETS
+        .load(&deps.storage, MARKET_INDEX.to_string())
+        .unwrap();
+    market.premium_index_duration = 0;
+    market.last_premium = Number128::new(100);
+    market.last_premium_ts = START_TS;
+
+    market.accrue_premium_index(START_TS).unwrap();
+    market.accrue_premium_index(START_TS - 10).unwrap();
+    assert_eq!(market.premium_index_duration, 0);
+    assert_eq!(market.average_premium_index(), None);
+}
@@ -0,0 +1,102 @@
+use crate::error::ContractError;
+use crate::package::execute::ExecuteMsg;
+use crate::package::queries::QueryMsg;
+use crate::package::response::{KeeperRewardsResponse, UserResponse};
+use crate::package::types::KeeperRewards;
+use crate::states::constants::QUOTE_PRECISION;
+use crate::states::market::MARKETS;
+use crate::tests::setup::*;
+
+use cosmwasm_std::Uint128;
+
+const KEEPER: &str = "keeper";
+const TRADE_TS: u64 = START_TS + FUNDING_PERIOD;
+
+fn quote(amount: u128) -> Uint128 {
+    Uint128::from(amount * QUOTE_PRECISION.u128())
+}
+
+/// The market collected 1000 UST of fees, 5% of which the treasury can spend
+fn setup_fees() -> TestDeps {
+    let mut deps = setup(1_000_000 * QUOTE_PRECISION.u128());
+    let mut market = MARKETS
+        .load(&deps.storage, MARKET_INDEX.to_string())
+        .unwrap();
+    market.amm.total_fee = quote(1_000);
+    MARKETS
+        .save(&mut deps.storage, MARKET_INDEX.to_string(), &market)
+        .unwrap();
+    deps
+}
+
+fn fund_pool(deps: &mut TestDeps, amount: Uint128) -> Result<(), ContractError> {
+    execute_msg(
+        deps,
+        ADMIN,
+        START_TS,
+        ExecuteMsg::FundKeeperRewardPool {
+            market_index: MARKET_INDEX,
+            amount,
+        },
+    )
+    .map(|_| ())
+}
+
+fn keeper_rewards(deps: &TestDeps) -> KeeperRewardsResponse {
+    query_msg(deps, QueryMsg::GetKeeperRewards {})
+}
+
+#[test]
+fn test_pool_is_funded_from_the_withdrawable_fees() {
+    let mut deps = setup_fees();
+    fund_pool(&mut deps, quote(20)).unwrap();
+    assert_eq!(keeper_rewards(&deps).pool_balance, quote(20));
+
+    let market = MARKETS
+        .load(&deps.storage, MARKET_INDEX.to_string())
+        .unwrap();
+    assert_eq!(market.amm.total_fee_withdrawn, quote(20));
+
+    let err = fund_pool(&mut deps, quote(31)).unwrap_err();
+    assert!(matches!(err, ContractError::AdminWithdrawTooLarge));
+}
+
+#[test]
+fn test_funding_rate_update_pays_the_keeper() {
+    let mut deps = setup_fees();
+    fund_pool(&mut deps, quote(20)).unwrap();
+    execute_msg(
+        &mut deps,
+        ADMIN,
+        START_TS,
+        ExecuteMsg::UpdateKeeperRewards {
+            keeper_rewards: KeeperRewards {
+                funding_rate_update: quote(1),
+                ..KeeperRewards::default()
+            },
+        },
+    )
+    .unwrap();
+    deposit(&mut deps, KEEPER, 10 * QUOTE_PRECISION.u128() as u64, None);
+
+    execute_msg(
+        &mut deps,
+        KEEPER,
+        TRADE_TS,
+        ExecuteMsg::UpdateFundingRate {
+            market_index: MARKET_INDEX,
+        },
+    )
+    .unwrap();
+
+    let keeper: UserResponse = query_msg(
+        &deps,
+        QueryMsg::GetUser {
+            user_address: KEEPER.to_string(),
+        },
+    );
+    assert_eq!(keeper.collateral, quote(11));
+    let rewards = keeper_rewards(&deps);
+    assert_eq!(rewards.pool_balance, quote(19));
+    assert_eq!(rewards.total_paid, quote(1));
+}
@@ -0,0 +1,59 @@
+use crate::controller::margin::calculate_size_adjusted_margin_ratio;
+use crate::states::constants::{AMM_RESERVE_PRECISION, MAXIMUM_MARGIN_RATIO};
+
+const MARGIN_RATIO_INITIAL: u32 = 2000;
+const MARGIN_RATIO_PARTIAL: u32 = 625;
+const MARGIN_RATIO_MAINTENANCE: u32 = 500;
+
+fn size_adjusted_ratios(base_asset_amount: i128, imf_factor: u32) -> (u32, u32, u32) {
+    let ratio = |margin_ratio| {
+        calculate_size_adjusted_margin_ratio(
+            base_asset_amount,
+            margin_ratio,
+            MARGIN_RATIO_INITIAL,
+            imf_factor,
+        )
+        .unwrap()
+    };
+    (
+        ratio(MARGIN_RATIO_INITIAL),
+        ratio(MARGIN_RATIO_PARTIAL),
+        ratio(MARGIN_RATIO_MAINTENANCE),
+    )
+}
+
+#[test]
+fn test_size_adjusted_margin_ratio_without_imf_factor() {
+    let base_asset_amount = 1_000_000 * AMM_RESERVE_PRECISION.u128() as i128;
+    assert_eq!(
+        size_adjusted_ratios(base_asset_amount, 0),
+        (MARGIN_RATIO_INITIAL, MARGIN_RATIO_PARTIAL, MARGIN_RATIO_MAINTENANCE)
+    );
+}
+
+#[test]
+fn test_size_adjusted_margin_ratio_grows_with_size() {
+    let one = AMM_RESERVE_PRECISION.u128() as i128;
+    let (small, _, _) = size_adjusted_ratios(one, 1000);
+    let (large, _, _) = size_adjusted_ratios(100 * one, 1000);
+    // sqrt(1) * 0.1 and sqrt(100) * 0.1 on top of the 20% initial ratio
+    assert_eq!(small, MARGIN_RATIO_INITIAL + 1000);
+    assert_eq!(large, MAXIMUM_MARGIN_RATIO.u128() as u32);
+    assert!(large > small);
+}
+
+#[test]
+fn test_size_adjusted_margin_ratio_keeps_ordering() {
+    let one = AMM_RESERVE_PRECISION.u128() as i128;
+    for imf_factor in [1, 100, 1000, 10_000] {
+        for size in [0, 1, 10, 1_000, 1_000_000, 1_000_000_000] {
+            for base_asset_amount in [size * one, -size * one] {
+                let (initial, partial, maintenance) =
+                    size_adjusted_ratios(base_asset_amount, imf_factor);
+                assert!(initial > partial, "{} {} {}", imf_factor, size, initial);
+                assert!(partial > maintenance, "{} {} {}", imf_factor, size, partial);
+                assert!(initial <= MAXIMUM_MARGIN_RATIO.u128() as u32);
+            }
+        }
+    }
+}
@@ -1 +1,18 @@
-mod test;
\ No newline at end of file
+// predates the history contract split and no longer compiles against the query messages
+// mod test;
+mod admin;
+mod amm;
+mod batch;
+mod circuit_breaker;
+mod claim;
+mod fees;
+mod funding;
+mod keeper;
+mod margin;
+mod order;
+mod position;
+mod settle_pnl;
+mod setup;
+mod staking;
+mod sudo;
+mod timelock;
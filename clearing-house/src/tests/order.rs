@@ -0,0 +1,207 @@
+use crate::error::ContractError;
+use crate::package::execute::ExecuteMsg;
+use crate::package::number::Number128;
+use crate::package::queries::QueryMsg;
+use crate::package::response::OrderResponse;
+use crate::package::types::{
+    Order, OrderParams, OrderTriggerCondition, OrderType, PositionDirection,
+};
+use crate::states::constants::{MARK_PRICE_PRECISION, QUOTE_PRECISION};
+use crate::states::order::TwapOrder;
+use crate::tests::setup::*;
+
+use cosmwasm_std::Uint128;
+
+const ALICE: &str = "alice";
+const TRADE_TS: u64 = START_TS + FUNDING_PERIOD;
+
+fn setup_long() -> TestDeps {
+    let mut deps = setup(1_000_000 * QUOTE_PRECISION.u128());
+    deposit(&mut deps, ALICE, 100 * QUOTE_PRECISION.u128() as u64, None);
+    open_position(
+        &mut deps,
+        ALICE,
+        TRADE_TS,
+        PositionDirection::Long,
+        100 * QUOTE_PRECISION.u128(),
+    );
+    deps
+}
+
+fn price_pct(pct: u128) -> Option<Uint128> {
+    Some(MARK_PRICE_PRECISION * Uint128::from(pct) / Uint128::from(100u128))
+}
+
+fn set_triggers(
+    deps: &mut TestDeps,
+    stop_loss_price: Option<Uint128>,
+    take_profit_price: Option<Uint128>,
+) -> Result<(), ContractError> {
+    execute_msg(
+        deps,
+        ALICE,
+        TRADE_TS,
+        ExecuteMsg::SetPositionTriggers {
+            market_index: MARKET_INDEX,
+            stop_loss_price,
+            take_profit_price,
+        },
+    )
+    .map(|_| ())
+}
+
+fn orders(deps: &TestDeps) -> Vec<Order> {
+    open_orders(deps).into_iter().map(|o| o.order).collect()
+}
+
+#[test]
+fn test_triggers_close_the_whole_position() {
+    let mut deps = setup_long();
+    set_triggers(&mut deps, price_pct(90), price_pct(120)).unwrap();
+
+    let orders = orders(&deps);
+    assert_eq!(orders.len(), 2);
+    for order in &orders {
+        assert_eq!(order.order_type, OrderType::TriggerMarket);
+        assert_eq!(order.direction, PositionDirection::Short);
+        assert!(order.reduce_only);
+    }
+    assert_eq!(orders[0].trigger_price, price_pct(90).unwrap());
+    assert_eq!(orders[0].trigger_condition, OrderTriggerCondition::Below);
+    assert_eq!(orders[1].trigger_price, price_pct(120).unwrap());
+    assert_eq!(orders[1].trigger_condition, OrderTriggerCondition::Above);
+}
+
+#[test]
+fn test_new_triggers_replace_the_old_ones() {
+    let mut deps = setup_long();
+    set_triggers(&mut deps, price_pct(90), price_pct(120)).unwrap();
+    set_triggers(&mut deps, price_pct(80), None).unwrap();
+
+    let orders = orders(&deps);
+    assert_eq!(orders.len(), 1);
+    assert_eq!(orders[0].trigger_price, price_pct(80).unwrap());
+}
+
+#[test]
+fn test_stop_loss_above_the_mark_price_is_rejected() {
+    let mut deps = setup_long();
+    let err = set_triggers(&mut deps, price_pct(150), None).unwrap_err();
+    assert!(matches!(err, ContractError::InvalidPositionTrigger));
+}
+
+fn twap_order(base_asset_amount: u128, slices: u64) -> TwapOrder {
+    TwapOrder {
+        ts: 0,
+        market_index: 1,
+        direction: PositionDirection::Long,
+        base_asset_amount: Uint128::from(base_asset_amount),
+        base_asset_amount_filled: Uint128::zero(),
+        quote_asset_amount_filled: Uint128::zero(),
+        slices,
+        slices_filled: 0,
+        slices_skipped: 0,
+        interval: 60,
+        limit_price: None,
+        next_slice_ts: 0,
+    }
+}
+
+fn fill_next_slice(order: &mut TwapOrder) -> Uint128 {
+    let amount = order.next_slice_base_asset_amount().unwrap();
+    order.base_asset_amount_filled += amount;
+    order.slices_filled += 1;
+    amount
+}
+
+fn place_oracle_offset_bid(deps: &mut TestDeps, oracle_price_offset: i128) {
+    execute_msg(
+        deps,
+        ALICE,
+        START_TS,
+        ExecuteMsg::PlaceOrder {
+            order: OrderParams {
+                order_type: OrderType::Limit,
+                direction: PositionDirection::Long,
+                quote_asset_amount: Uint128::zero(),
+                base_asset_amount: Uint128::from(10 * QUOTE_PRECISION.u128()),
+                price: Uint128::zero(),
+                market_index: MARKET_INDEX,
+                reduce_only: false,
+                post_only: false,
+                immediate_or_cancel: false,
+                trigger_price: Uint128::zero(),
+                trigger_condition: OrderTriggerCondition::Above,
+                position_limit: Uint128::zero(),
+                oracle_price_offset: Number128::new(oracle_price_offset),
+            },
+        },
+    )
+    .unwrap();
+}
+
+fn open_orders(deps: &TestDeps) -> Vec<OrderResponse> {
+    query_msg(
+        deps,
+        QueryMsg::GetOpenOrders {
+            user_address: ALICE.to_string(),
+            market_index: MARKET_INDEX,
+        },
+    )
+}
+
+#[test]
+fn test_oracle_offset_order_follows_the_oracle() {
+    let mut deps = setup(1_000_000 * QUOTE_PRECISION.u128());
+    deposit(&mut deps, ALICE, 100 * QUOTE_PRECISION.u128() as u64, None);
+    place_oracle_offset_bid(&mut deps, -(price_pct(5).unwrap().u128() as i128));
+    assert_eq!(open_orders(&deps)[0].effective_price, price_pct(95));
+
+    feed_price(&mut deps, price_pct(110).unwrap().u128() as i128);
+    assert_eq!(open_orders(&deps)[0].effective_price, price_pct(105));
+}
+
+#[test]
+fn test_fixed_price_replaces_the_oracle_offset() {
+    let mut deps = setup(1_000_000 * QUOTE_PRECISION.u128());
+    deposit(&mut deps, ALICE, 100 * QUOTE_PRECISION.u128() as u64, None);
+    place_oracle_offset_bid(&mut deps, -(price_pct(5).unwrap().u128() as i128));
+    let order_id = open_orders(&deps)[0].order_id;
+
+    execute_msg(
+        &mut deps,
+        ALICE,
+        START_TS,
+        ExecuteMsg::ModifyOrder {
+            market_index: MARKET_INDEX,
+            order_id,
+            price: price_pct(90),
+            oracle_price_offset: None,
+            base_asset_amount: None,
+        },
+    )
+    .unwrap();
+
+    let orders = open_orders(&deps);
+    assert_eq!(orders[0].order.oracle_price_offset, Number128::zero());
+    assert_eq!(orders[0].effective_price, price_pct(90));
+}
+
+#[test]
+fn test_twap_last_slice_picks_up_rounding() {
+    let mut order = twap_order(100, 3);
+    assert_eq!(fill_next_slice(&mut order), Uint128::from(33u128));
+    assert_eq!(fill_next_slice(&mut order), Uint128::from(33u128));
+    assert_eq!(fill_next_slice(&mut order), Uint128::from(34u128));
+    assert_eq!(order.base_asset_amount_filled, order.base_asset_amount);
+}
+
+#[test]
+fn test_twap_skipped_slices_are_not_made_up() {
+    let mut order = twap_order(90, 3);
+    order.slices_skipped += 1;
+    assert_eq!(fill_next_slice(&mut order), Uint128::from(30u128));
+    assert_eq!(fill_next_slice(&mut order), Uint128::from(30u128));
+    assert_eq!(order.slices_executed(), 3);
+    assert_eq!(order.base_asset_amount_filled, Uint128::from(60u128));
+}
@@ -0,0 +1,186 @@
+use crate::error::ContractError;
+use crate::package::execute::ExecuteMsg;
+use crate::package::queries::QueryMsg;
+use crate::package::response::UserResponse;
+use crate::package::types::PositionDirection;
+use crate::states::constants::{MARK_PRICE_PRECISION, QUOTE_PRECISION};
+use crate::states::market::MARKETS;
+use crate::states::user::{Position, POSITIONS};
+use crate::tests::setup::*;
+
+use cosmwasm_std::{Addr, Decimal, Uint128};
+
+const ALICE: &str = "alice";
+const TRADE_TS: u64 = START_TS + FUNDING_PERIOD;
+
+fn set_max_leverage(
+    deps: &mut TestDeps,
+    max_leverage: Option<Decimal>,
+) -> Result<(), ContractError> {
+    execute_msg(
+        deps,
+        ALICE,
+        START_TS,
+        ExecuteMsg::SetUserMaxLeverage {
+            market_index: MARKET_INDEX,
+            max_leverage,
+        },
+    )
+    .map(|_| ())
+}
+
+fn open_long(deps: &mut TestDeps, quote_asset_amount: u128) -> Result<(), ContractError> {
+    execute_msg(
+        deps,
+        ALICE,
+        TRADE_TS,
+        ExecuteMsg::OpenPosition {
+            direction: PositionDirection::Long,
+            quote_asset_amount: Uint128::from(quote_asset_amount),
+            base_asset_amount: None,
+            market_index: MARKET_INDEX,
+            limit_price: None,
+        },
+    )
+    .map(|_| ())
+}
+
+fn close(
+    deps: &mut TestDeps,
+    ts: u64,
+    base_asset_amount: Option<Uint128>,
+    percentage: Option<Decimal>,
+) -> Result<(), ContractError> {
+    execute_msg(
+        deps,
+        ALICE,
+        ts,
+        ExecuteMsg::ClosePosition {
+            market_index: MARKET_INDEX,
+            base_asset_amount,
+            percentage,
+            limit_price: None,
+        },
+    )
+    .map(|_| ())
+}
+
+fn position(deps: &TestDeps) -> Position {
+    POSITIONS
+        .load(
+            &deps.storage,
+            (&Addr::unchecked(ALICE), MARKET_INDEX.to_string()),
+        )
+        .unwrap()
+}
+
+fn base_asset_amount(deps: &TestDeps) -> i128 {
+    position(deps).base_asset_amount.i128()
+}
+
+/// The funding update after the opening trade saves a stale mark twap timestamp, bring it up to
+/// date so a second trade one funding period later stays inside the twap window
+fn catch_up_mark_twap(deps: &mut TestDeps) {
+    let mut market = MARKETS
+        .load(&deps.storage, MARKET_INDEX.to_string())
+        .unwrap();
+    market.amm.last_mark_price_twap_ts = TRADE_TS;
+    MARKETS
+        .save(&mut deps.storage, MARKET_INDEX.to_string(), &market)
+        .unwrap();
+}
+
+#[test]
+fn test_max_leverage_below_one_is_rejected() {
+    let mut deps = setup(1_000_000 * QUOTE_PRECISION.u128());
+    let err = set_max_leverage(&mut deps, Some(Decimal::percent(50))).unwrap_err();
+    assert!(matches!(err, ContractError::InvalidMaxLeverage));
+}
+
+#[test]
+fn test_market_initial_margin_allows_five_times_leverage() {
+    let mut deps = setup(1_000_000 * QUOTE_PRECISION.u128());
+    deposit(&mut deps, ALICE, 100 * QUOTE_PRECISION.u128() as u64, None);
+    open_long(&mut deps, 300 * QUOTE_PRECISION.u128()).unwrap();
+}
+
+#[test]
+fn test_trade_past_the_user_leverage_cap_is_rejected() {
+    let mut deps = setup(1_000_000 * QUOTE_PRECISION.u128());
+    deposit(&mut deps, ALICE, 100 * QUOTE_PRECISION.u128() as u64, None);
+    set_max_leverage(&mut deps, Some(Decimal::from_ratio(2u128, 1u128))).unwrap();
+
+    let err = open_long(&mut deps, 300 * QUOTE_PRECISION.u128()).unwrap_err();
+    assert!(matches!(err, ContractError::InsufficientCollateral));
+}
+
+#[test]
+fn test_partial_close_keeps_the_rest_of_the_position() {
+    let mut deps = setup(1_000_000 * QUOTE_PRECISION.u128());
+    deposit(&mut deps, ALICE, 100 * QUOTE_PRECISION.u128() as u64, None);
+    open_long(&mut deps, 100 * QUOTE_PRECISION.u128()).unwrap();
+    let opened = base_asset_amount(&deps);
+    catch_up_mark_twap(&mut deps);
+
+    close(
+        &mut deps,
+        TRADE_TS + FUNDING_PERIOD,
+        None,
+        Some(Decimal::percent(25)),
+    )
+    .unwrap();
+    assert_eq!(base_asset_amount(&deps), opened - opened / 4);
+}
+
+#[test]
+fn test_closing_more_than_the_position_is_rejected() {
+    let mut deps = setup(1_000_000 * QUOTE_PRECISION.u128());
+    deposit(&mut deps, ALICE, 100 * QUOTE_PRECISION.u128() as u64, None);
+    open_long(&mut deps, 100 * QUOTE_PRECISION.u128()).unwrap();
+    let opened = base_asset_amount(&deps) as u128;
+
+    let err = close(
+        &mut deps,
+        TRADE_TS + FUNDING_PERIOD,
+        Some(Uint128::from(opened + 1)),
+        None,
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::InvalidCloseAmount));
+    assert_eq!(base_asset_amount(&deps) as u128, opened);
+}
+
+#[test]
+fn test_position_tracks_entry_price_fees_and_realized_pnl() {
+    let mut deps = setup(1_000_000 * QUOTE_PRECISION.u128());
+    deposit(&mut deps, ALICE, 100 * QUOTE_PRECISION.u128() as u64, None);
+    open_long(&mut deps, 100 * QUOTE_PRECISION.u128()).unwrap();
+
+    let opened = position(&deps);
+    // the long walked the price up from 1.0 while it filled
+    assert!(opened.entry_price > MARK_PRICE_PRECISION);
+    let user: UserResponse = query_msg(
+        &deps,
+        QueryMsg::GetUser {
+            user_address: ALICE.to_string(),
+        },
+    );
+    assert!(!opened.cumulative_fees_paid.is_zero());
+    assert_eq!(opened.cumulative_fees_paid, user.total_fee_paid);
+    assert_eq!(opened.realized_pnl.i128(), 0);
+
+    move_mark_price(&mut deps, 1_000);
+    catch_up_mark_twap(&mut deps);
+    close(
+        &mut deps,
+        TRADE_TS + FUNDING_PERIOD,
+        None,
+        Some(Decimal::percent(50)),
+    )
+    .unwrap();
+
+    let reduced = position(&deps);
+    assert!(reduced.realized_pnl.i128() > 0);
+    assert_eq!(reduced.entry_price, opened.entry_price);
+    assert!(reduced.cumulative_fees_paid > opened.cumulative_fees_paid);
+}
@@ -0,0 +1,214 @@
+use crate::error::ContractError;
+use crate::helpers::position::{
+    calculate_base_asset_value_and_pnl, calculate_base_asset_value_and_pnl_with_oracle_price,
+};
+use crate::package::execute::ExecuteMsg;
+use crate::package::history::HistoryExecuteMsg;
+use crate::package::types::PositionDirection;
+use crate::states::constants::{
+    QUOTE_PRECISION, SHARE_OF_FEES_ALLOCATED_TO_CLEARING_HOUSE_DENOMINATOR,
+    SHARE_OF_FEES_ALLOCATED_TO_CLEARING_HOUSE_NUMERATOR,
+};
+use crate::states::history::SettlePnlRecord;
+use crate::states::market::MARKETS;
+use crate::states::user::{POSITIONS, USERS};
+use crate::tests::setup::*;
+
+use cosmwasm_std::{Addr, Response, Uint128};
+
+const ALICE: &str = "alice";
+const BOB: &str = "bob";
+
+/// Alice goes long against Bob's short, then the AMM is moved by `move_bps` and the oracle follows it
+fn setup_long(move_bps: i128) -> TestDeps {
+    let mut deps = setup(1_000_000 * QUOTE_PRECISION.u128());
+    deposit(
+        &mut deps,
+        ALICE,
+        10_000 * QUOTE_PRECISION.u128() as u64,
+        None,
+    );
+    deposit(
+        &mut deps,
+        BOB,
+        100_000 * QUOTE_PRECISION.u128() as u64,
+        None,
+    );
+    open_position(
+        &mut deps,
+        ALICE,
+        START_TS,
+        PositionDirection::Long,
+        1_000 * QUOTE_PRECISION.u128(),
+    );
+    open_position(
+        &mut deps,
+        BOB,
+        START_TS,
+        PositionDirection::Short,
+        50_000 * QUOTE_PRECISION.u128(),
+    );
+
+    move_mark_price(&mut deps, move_bps);
+    deps
+}
+
+/// Moves the whole withdrawable share of fees into the pnl pool
+fn fund_pnl_pool(deps: &mut TestDeps) -> Uint128 {
+    let market = MARKETS
+        .load(&deps.storage, MARKET_INDEX.to_string())
+        .unwrap();
+    let amount = market.amm.total_fee.multiply_ratio(
+        SHARE_OF_FEES_ALLOCATED_TO_CLEARING_HOUSE_NUMERATOR,
+        SHARE_OF_FEES_ALLOCATED_TO_CLEARING_HOUSE_DENOMINATOR,
+    );
+    execute_msg(
+        deps,
+        ADMIN,
+        START_TS,
+        ExecuteMsg::FundPnlPool {
+            market_index: MARKET_INDEX,
+            amount,
+        },
+    )
+    .unwrap();
+    amount
+}
+
+fn settle_pnl(deps: &mut TestDeps, user: &str) -> Result<Response, ContractError> {
+    execute_msg(
+        deps,
+        user,
+        START_TS,
+        ExecuteMsg::SettlePnl {
+            market_index: MARKET_INDEX,
+        },
+    )
+}
+
+fn settle_pnl_records(res: &Response) -> Vec<SettlePnlRecord> {
+    history(res)
+        .into_iter()
+        .filter_map(|h| match h {
+            HistoryExecuteMsg::RecordSettlePnl { s } => Some(s),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn test_settle_pnl_pays_profit_up_to_the_pool() {
+    let mut deps = setup_long(1000);
+    let pool = fund_pnl_pool(&mut deps);
+    let alice = Addr::unchecked(ALICE);
+    let collateral_before = USERS.load(&deps.storage, &alice).unwrap().collateral;
+    let position_before = POSITIONS
+        .load(&deps.storage, (&alice, MARKET_INDEX.to_string()))
+        .unwrap();
+
+    let res = settle_pnl(&mut deps, ALICE).unwrap();
+
+    // a 10% move on a 1000 position is far more than the pool holds
+    let collateral_after = USERS.load(&deps.storage, &alice).unwrap().collateral;
+    assert_eq!(collateral_after, collateral_before + pool);
+    let market = MARKETS
+        .load(&deps.storage, MARKET_INDEX.to_string())
+        .unwrap();
+    assert!(market.pnl_pool.is_zero());
+
+    // the settled profit is folded into the cost basis, the size is untouched
+    let position_after = POSITIONS
+        .load(&deps.storage, (&alice, MARKET_INDEX.to_string()))
+        .unwrap();
+    assert_eq!(
+        position_after.base_asset_amount,
+        position_before.base_asset_amount
+    );
+    assert_eq!(
+        position_after.quote_asset_amount,
+        position_before.quote_asset_amount + pool
+    );
+    assert_eq!(position_after.realized_pnl.i128(), pool.u128() as i128);
+
+    let records = settle_pnl_records(&res);
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].user, alice);
+    assert_eq!(records[0].pnl.i128(), pool.u128() as i128);
+    assert!(records[0].pnl_pool_after.is_zero());
+
+    // nothing is left to pay out
+    let err = settle_pnl(&mut deps, ALICE).unwrap_err();
+    assert!(matches!(err, ContractError::PnlPoolEmpty));
+}
+
+#[test]
+fn test_settle_pnl_uses_the_lower_of_mark_and_oracle() {
+    let mut deps = setup_long(-500);
+    let alice = Addr::unchecked(ALICE);
+
+    // an oracle just under the mark, within the guard rails, values the long lower than the amm
+    let mark_price = mark_price(&deps);
+    let oracle_price = (mark_price.u128() * 99 / 100) as i128;
+    feed_price(&mut deps, oracle_price);
+
+    let position = POSITIONS
+        .load(&deps.storage, (&alice, MARKET_INDEX.to_string()))
+        .unwrap();
+    let market = MARKETS
+        .load(&deps.storage, MARKET_INDEX.to_string())
+        .unwrap();
+    let (_, amm_pnl) = calculate_base_asset_value_and_pnl(&position, &market.amm).unwrap();
+    let (_, oracle_pnl) =
+        calculate_base_asset_value_and_pnl_with_oracle_price(&position, oracle_price).unwrap();
+    assert!(oracle_pnl < amm_pnl);
+
+    // losses are settled in full so the record shows the oracle valuation as is
+    let res = settle_pnl(&mut deps, ALICE).unwrap();
+    let records = settle_pnl_records(&res);
+    assert_eq!(records[0].pnl.i128(), oracle_pnl);
+    assert_eq!(records[0].mark_price, mark_price);
+    assert_eq!(records[0].oracle_price.i128(), oracle_price);
+    assert_eq!(records[0].pnl_pool_after.u128() as i128, -oracle_pnl);
+}
+
+#[test]
+fn test_settle_pnl_rejects_divergent_oracle() {
+    let mut deps = setup_long(1000);
+    fund_pnl_pool(&mut deps);
+    let mark_price = mark_price(&deps);
+    feed_price(&mut deps, (mark_price.u128() * 12 / 10) as i128);
+
+    let err = settle_pnl(&mut deps, ALICE).unwrap_err();
+    assert!(matches!(err, ContractError::OracleMarkSpreadLimit));
+}
+
+#[test]
+fn test_settle_pnl_rejects_invalid_oracle() {
+    let mut deps = setup_long(1000);
+    fund_pnl_pool(&mut deps);
+    // a price this small is swamped by the feed's confidence interval
+    feed_price(&mut deps, 1);
+
+    let err = settle_pnl(&mut deps, ALICE).unwrap_err();
+    assert!(matches!(err, ContractError::InvalidOracle));
+}
+
+#[test]
+fn test_settle_pnl_with_empty_pool() {
+    let mut deps = setup_long(1000);
+    let err = settle_pnl(&mut deps, ALICE).unwrap_err();
+    assert!(matches!(err, ContractError::PnlPoolEmpty));
+}
+
+#[test]
+fn test_settle_pnl_without_position() {
+    let mut deps = setup_long(1000);
+    deposit(
+        &mut deps,
+        "carol",
+        1_000 * QUOTE_PRECISION.u128() as u64,
+        None,
+    );
+    let err = settle_pnl(&mut deps, "carol").unwrap_err();
+    assert!(matches!(err, ContractError::UserHasNoPositionInMarket));
+}
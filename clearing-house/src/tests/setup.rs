@@ -0,0 +1,184 @@
+use crate::contract::{execute, instantiate, query, sudo};
+use crate::error::ContractError;
+use crate::package::execute::{ExecuteMsg, InstantiateMsg, SudoMsg};
+use crate::package::history::HistoryExecuteMsg;
+use crate::package::queries::QueryMsg;
+use crate::package::types::{OracleSource, PositionDirection};
+use crate::states::constants::{AMM_RESERVE_PRECISION, MARK_PRICE_PRECISION, PEG_PRECISION};
+use crate::states::market::MARKETS;
+
+use serde::de::DeserializeOwned;
+
+use cosmwasm_std::testing::{
+    mock_dependencies, mock_env, mock_info, MockApi, MockQuerier, MockStorage, MOCK_CONTRACT_ADDR,
+};
+use cosmwasm_std::{
+    coins, from_binary, Coin, CosmosMsg, Env, OwnedDeps, Response, Timestamp, Uint128, WasmMsg,
+};
+
+pub const ADMIN: &str = "admin";
+pub const MARKET_INDEX: u64 = 1;
+// on the hour, so the first funding update is due one period later
+pub const START_TS: u64 = 1_599_998_400;
+pub const FUNDING_PERIOD: u64 = 3600;
+
+pub type TestDeps = OwnedDeps<MockStorage, MockApi, MockQuerier>;
+
+/// Clearing house with its market priced at 1.0, the mock contract address doubles as both vaults
+/// and holds `vault_balance` uusd
+pub fn setup(vault_balance: u128) -> TestDeps {
+    let mut deps = mock_dependencies(&coins(vault_balance, "uusd"));
+    let msg = InstantiateMsg {
+        collateral_vault: MOCK_CONTRACT_ADDR.to_string(),
+        insurance_vault: MOCK_CONTRACT_ADDR.to_string(),
+        admin_controls_prices: true,
+        oracle: MOCK_CONTRACT_ADDR.to_string(),
+    };
+    instantiate(deps.as_mut(), env_at(START_TS), mock_info(ADMIN, &[]), msg).unwrap();
+
+    // margin and funding walk markets 1..markets_length, an idle second market keeps the first in range
+    let reserve = Uint128::from(1_000_000u128) * AMM_RESERVE_PRECISION;
+    for (market_index, market_name) in [(MARKET_INDEX, "LUNA-UST"), (MARKET_INDEX + 1, "ANC-UST")] {
+        execute_msg(
+            &mut deps,
+            ADMIN,
+            START_TS,
+            ExecuteMsg::InitializeMarket {
+                market_index,
+                market_name: market_name.to_string(),
+                amm_base_asset_reserve: reserve,
+                amm_quote_asset_reserve: reserve,
+                amm_periodicity: FUNDING_PERIOD,
+                amm_peg_multiplier: PEG_PRECISION,
+                oracle_source: OracleSource::Oracle,
+                margin_ratio_initial: 2000,
+                margin_ratio_partial: 625,
+                margin_ratio_maintenance: 500,
+            },
+        )
+        .unwrap();
+    }
+    feed_price(&mut deps, MARK_PRICE_PRECISION.u128() as i128);
+    deps
+}
+
+pub fn env_at(ts: u64) -> Env {
+    let mut env = mock_env();
+    env.block.time = Timestamp::from_seconds(ts);
+    env
+}
+
+pub fn execute_msg(
+    deps: &mut TestDeps,
+    sender: &str,
+    ts: u64,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    execute(deps.as_mut(), env_at(ts), mock_info(sender, &[]), msg)
+}
+
+pub fn sudo_msg(deps: &mut TestDeps, msg: SudoMsg) -> Result<Response, ContractError> {
+    sudo(deps.as_mut(), env_at(START_TS), msg)
+}
+
+pub fn query_msg<T: DeserializeOwned>(deps: &TestDeps, msg: QueryMsg) -> T {
+    from_binary(&query(deps.as_ref(), env_at(START_TS), msg).unwrap()).unwrap()
+}
+
+pub fn feed_price(deps: &mut TestDeps, price: i128) {
+    execute_msg(
+        deps,
+        ADMIN,
+        START_TS,
+        ExecuteMsg::OracleFeeder {
+            market_index: MARKET_INDEX,
+            price,
+        },
+    )
+    .unwrap();
+}
+
+pub fn deposit(deps: &mut TestDeps, user: &str, amount: u64, referral_code: Option<String>) {
+    let funds = vec![Coin::new(amount as u128, "uusd")];
+    execute(
+        deps.as_mut(),
+        env_at(START_TS),
+        mock_info(user, &funds),
+        ExecuteMsg::DepositCollateral {
+            amount,
+            referral_code,
+        },
+    )
+    .unwrap();
+}
+
+pub fn open_position(
+    deps: &mut TestDeps,
+    user: &str,
+    ts: u64,
+    direction: PositionDirection,
+    quote_asset_amount: u128,
+) {
+    execute_msg(
+        deps,
+        user,
+        ts,
+        ExecuteMsg::OpenPosition {
+            direction,
+            quote_asset_amount: Uint128::from(quote_asset_amount),
+            base_asset_amount: None,
+            market_index: MARKET_INDEX,
+            limit_price: None,
+        },
+    )
+    .unwrap();
+}
+
+pub fn mark_price(deps: &TestDeps) -> Uint128 {
+    MARKETS
+        .load(&deps.storage, MARKET_INDEX.to_string())
+        .unwrap()
+        .amm
+        .mark_price()
+        .unwrap()
+}
+
+/// Moves the amm by roughly `move_bps` and has the oracle follow it
+pub fn move_mark_price(deps: &mut TestDeps, move_bps: i128) {
+    // reserves move in opposite directions so the price moves by roughly twice the reserve change
+    let reserve = 1_000_000 * AMM_RESERVE_PRECISION.u128() as i128;
+    let change = reserve * move_bps / 20_000;
+    execute_msg(
+        deps,
+        ADMIN,
+        START_TS,
+        ExecuteMsg::MoveAMMPrice {
+            base_asset_reserve: Uint128::from((reserve - change) as u128),
+            quote_asset_reserve: Uint128::from((reserve + change) as u128),
+            market_index: MARKET_INDEX,
+        },
+    )
+    .unwrap();
+    let mark_price = mark_price(deps);
+    feed_price(deps, mark_price.u128() as i128);
+}
+
+/// Messages of type `T` the response sends to `contract_addr`
+pub fn wasm_messages<T: DeserializeOwned>(res: &Response, contract_addr: &str) -> Vec<T> {
+    res.messages
+        .iter()
+        .filter_map(|m| match &m.msg {
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: addr,
+                msg,
+                ..
+            }) if addr == contract_addr => from_binary(msg).ok(),
+            _ => None,
+        })
+        .collect()
+}
+
+/// History messages, the instantiator doubles as the history contract
+pub fn history(res: &Response) -> Vec<HistoryExecuteMsg> {
+    wasm_messages(res, ADMIN)
+}
@@ -0,0 +1,86 @@
+use crate::error::ContractError;
+use crate::package::execute::ExecuteMsg;
+use crate::package::helper::{StakingInterface, VaultInterface};
+use crate::states::constants::QUOTE_PRECISION;
+use crate::states::market::MARKETS;
+use crate::tests::setup::*;
+
+use cosmwasm_std::testing::MOCK_CONTRACT_ADDR;
+use cosmwasm_std::{Addr, Response, Uint128};
+
+const STAKING: &str = "staking";
+
+fn quote(amount: u128) -> Uint128 {
+    Uint128::from(amount * QUOTE_PRECISION.u128())
+}
+
+/// The market collected 1000 UST of fees, 5% of which the treasury can spend
+fn setup_fees() -> TestDeps {
+    let mut deps = setup(1_000_000 * QUOTE_PRECISION.u128());
+    let mut market = MARKETS
+        .load(&deps.storage, MARKET_INDEX.to_string())
+        .unwrap();
+    market.amm.total_fee = quote(1_000);
+    MARKETS
+        .save(&mut deps.storage, MARKET_INDEX.to_string(), &market)
+        .unwrap();
+    deps
+}
+
+fn fund_staking_rewards(deps: &mut TestDeps, amount: Uint128) -> Result<Response, ContractError> {
+    execute_msg(
+        deps,
+        ADMIN,
+        START_TS,
+        ExecuteMsg::FundStakingRewards {
+            market_index: MARKET_INDEX,
+            amount,
+        },
+    )
+}
+
+#[test]
+fn test_staking_rewards_need_a_staking_contract() {
+    let mut deps = setup_fees();
+    let err = fund_staking_rewards(&mut deps, quote(20)).unwrap_err();
+    assert!(matches!(err, ContractError::StakingContractNotSet));
+}
+
+#[test]
+fn test_staking_rewards_are_sent_from_the_fee_pool() {
+    let mut deps = setup_fees();
+    execute_msg(
+        &mut deps,
+        ADMIN,
+        START_TS,
+        ExecuteMsg::UpdateStakingContract {
+            staking_contract: STAKING.to_string(),
+        },
+    )
+    .unwrap();
+
+    let res = fund_staking_rewards(&mut deps, quote(20)).unwrap();
+
+    // the rewards reach the staking contract before it books them
+    let withdrawals: Vec<VaultInterface> = wasm_messages(&res, MOCK_CONTRACT_ADDR);
+    assert_eq!(
+        withdrawals,
+        vec![VaultInterface::Withdraw {
+            to_address: Addr::unchecked(STAKING),
+            amount: quote(20),
+        }]
+    );
+    let distributions: Vec<StakingInterface> = wasm_messages(&res, STAKING);
+    assert_eq!(
+        distributions,
+        vec![StakingInterface::DistributeRewards { amount: quote(20) }]
+    );
+
+    let market = MARKETS
+        .load(&deps.storage, MARKET_INDEX.to_string())
+        .unwrap();
+    assert_eq!(market.amm.total_fee_withdrawn, quote(20));
+
+    let err = fund_staking_rewards(&mut deps, quote(31)).unwrap_err();
+    assert!(matches!(err, ContractError::AdminWithdrawTooLarge));
+}
@@ -0,0 +1,176 @@
+use crate::error::ContractError;
+use crate::helpers::position::calculate_base_asset_value_and_pnl_with_oracle_price;
+use crate::package::execute::{ExecuteMsg, SudoMsg};
+use crate::package::queries::QueryMsg;
+use crate::package::response::{AdminResponse, IsExchangePausedResponse};
+use crate::package::types::{PositionDirection, Role};
+use crate::states::constants::{MARK_PRICE_PRECISION, QUOTE_PRECISION};
+use crate::states::market::MARKETS;
+use crate::states::state::ROLES;
+use crate::states::user::{POSITIONS, USERS};
+use crate::tests::setup::*;
+
+use cosmwasm_std::{Addr, Uint128};
+
+const ALICE: &str = "alice";
+
+fn pause(deps: &mut TestDeps, exchange_paused: bool) -> Result<(), ContractError> {
+    execute_msg(
+        deps,
+        ADMIN,
+        START_TS,
+        ExecuteMsg::UpdateExchangePaused { exchange_paused },
+    )
+    .map(|_| ())
+}
+
+#[test]
+fn test_governance_pause_can_only_be_lifted_by_governance() {
+    let mut deps = setup(0);
+    sudo_msg(
+        &mut deps,
+        SudoMsg::UpdateExchangePaused {
+            exchange_paused: true,
+        },
+    )
+    .unwrap();
+    let paused: IsExchangePausedResponse = query_msg(&deps, QueryMsg::IsExchangePaused {});
+    assert!(paused.exchange_paused);
+    assert!(paused.paused_by_governance);
+
+    let err = pause(&mut deps, false).unwrap_err();
+    assert!(matches!(err, ContractError::PausedByGovernance));
+
+    sudo_msg(
+        &mut deps,
+        SudoMsg::UpdateExchangePaused {
+            exchange_paused: false,
+        },
+    )
+    .unwrap();
+    pause(&mut deps, true).unwrap();
+    pause(&mut deps, false).unwrap();
+}
+
+#[test]
+fn test_governance_replaces_admin() {
+    let mut deps = setup(0);
+    execute_msg(
+        &mut deps,
+        ADMIN,
+        START_TS,
+        ExecuteMsg::ProposeNewAdmin {
+            new_admin: "accomplice".to_string(),
+        },
+    )
+    .unwrap();
+
+    sudo_msg(
+        &mut deps,
+        SudoMsg::UpdateAdmin {
+            admin: "governed".to_string(),
+        },
+    )
+    .unwrap();
+    let admin: AdminResponse = query_msg(&deps, QueryMsg::GetAdmin {});
+    assert_eq!(admin.admin, "governed");
+    assert_eq!(admin.pending_admin, None);
+
+    // the replaced admin is stripped of every role it granted itself
+    for role in Role::all() {
+        let members = ROLES.load(&deps.storage, role.key()).unwrap();
+        assert!(!members.contains(&Addr::unchecked(ADMIN)));
+    }
+    let err = pause(&mut deps, true).unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+    let err = execute_msg(
+        &mut deps,
+        "accomplice",
+        START_TS,
+        ExecuteMsg::AcceptAdmin {},
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::NoPendingAdmin));
+}
+
+#[test]
+fn test_settled_market_closes_at_settlement_price() {
+    let mut deps = setup(1_000_000 * QUOTE_PRECISION.u128());
+    deposit(
+        &mut deps,
+        ALICE,
+        10_000 * QUOTE_PRECISION.u128() as u64,
+        None,
+    );
+    // a trade panics unless the funding rate is due for an update
+    let trade_ts = START_TS + FUNDING_PERIOD;
+    open_position(
+        &mut deps,
+        ALICE,
+        trade_ts,
+        PositionDirection::Long,
+        1_000 * QUOTE_PRECISION.u128(),
+    );
+
+    let settlement_price = (MARK_PRICE_PRECISION.u128() * 11 / 10) as i128;
+    feed_price(&mut deps, settlement_price);
+    sudo_msg(
+        &mut deps,
+        SudoMsg::SettleMarket {
+            market_index: MARKET_INDEX,
+        },
+    )
+    .unwrap();
+    let market = MARKETS
+        .load(&deps.storage, MARKET_INDEX.to_string())
+        .unwrap();
+    assert!(market.settled);
+    assert_eq!(
+        market.settlement_price,
+        Uint128::from(settlement_price as u128)
+    );
+
+    // nothing trades against the amm once the market is settled
+    let err = execute_msg(
+        &mut deps,
+        ALICE,
+        START_TS,
+        ExecuteMsg::OpenPosition {
+            direction: PositionDirection::Long,
+            quote_asset_amount: Uint128::from(100 * QUOTE_PRECISION.u128()),
+            base_asset_amount: None,
+            market_index: MARKET_INDEX,
+            limit_price: None,
+        },
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::MarketSettled));
+
+    let alice = Addr::unchecked(ALICE);
+    let position = POSITIONS
+        .load(&deps.storage, (&alice, MARKET_INDEX.to_string()))
+        .unwrap();
+    let (_, pnl) =
+        calculate_base_asset_value_and_pnl_with_oracle_price(&position, settlement_price).unwrap();
+    assert!(pnl > 0);
+    let collateral_before = USERS.load(&deps.storage, &alice).unwrap().collateral;
+
+    execute_msg(
+        &mut deps,
+        ALICE,
+        START_TS,
+        ExecuteMsg::SettlePosition {
+            market_index: MARKET_INDEX,
+        },
+    )
+    .unwrap();
+    let collateral_after = USERS.load(&deps.storage, &alice).unwrap().collateral;
+    assert_eq!(
+        collateral_after.u128() as i128,
+        collateral_before.u128() as i128 + pnl
+    );
+    let position = POSITIONS
+        .load(&deps.storage, (&alice, MARKET_INDEX.to_string()))
+        .unwrap();
+    assert_eq!(position.base_asset_amount.i128(), 0);
+}
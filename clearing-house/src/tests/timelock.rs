@@ -0,0 +1,104 @@
+use crate::error::ContractError;
+use crate::package::execute::ExecuteMsg;
+use crate::package::queries::QueryMsg;
+use crate::package::response::{PartialLiquidationClosePercentageResponse, PendingChangeResponse};
+use crate::package::types::ParameterChange;
+use crate::states::constants::DEFAULT_TIMELOCK_DELAY;
+use crate::tests::setup::*;
+
+use cosmwasm_std::Decimal;
+
+fn queue_close_percentage(deps: &mut TestDeps, value: Decimal) {
+    execute_msg(
+        deps,
+        ADMIN,
+        START_TS,
+        ExecuteMsg::UpdatePartialLiquidationClosePercentage { value },
+    )
+    .unwrap();
+}
+
+fn pending_changes(deps: &TestDeps) -> Vec<PendingChangeResponse> {
+    query_msg(
+        deps,
+        QueryMsg::GetPendingChanges {
+            start_after: None,
+            limit: None,
+        },
+    )
+}
+
+fn close_percentage(deps: &TestDeps) -> Decimal {
+    let res: PartialLiquidationClosePercentageResponse =
+        query_msg(deps, QueryMsg::GetPartialLiquidationClosePercentage {});
+    res.value
+}
+
+fn execute_change(deps: &mut TestDeps, ts: u64, change_id: u64) -> Result<(), ContractError> {
+    execute_msg(
+        deps,
+        "keeper",
+        ts,
+        ExecuteMsg::ExecutePendingChange { change_id },
+    )
+    .map(|_| ())
+}
+
+#[test]
+fn test_change_waits_for_its_eta() {
+    let mut deps = setup(0);
+    let before = close_percentage(&deps);
+    queue_close_percentage(&mut deps, Decimal::percent(50));
+    assert_eq!(close_percentage(&deps), before);
+
+    let pending = pending_changes(&deps);
+    assert_eq!(pending.len(), 1);
+    assert_eq!(pending[0].change_id, 1);
+    assert_eq!(pending[0].eta, START_TS + DEFAULT_TIMELOCK_DELAY);
+    assert_eq!(
+        pending[0].change,
+        ParameterChange::PartialLiquidationClosePercentage {
+            value: Decimal::percent(50)
+        }
+    );
+
+    let err = execute_change(&mut deps, pending[0].eta - 1, 1).unwrap_err();
+    assert!(matches!(err, ContractError::PendingChangeTimelocked));
+
+    execute_change(&mut deps, pending[0].eta, 1).unwrap();
+    assert_eq!(close_percentage(&deps), Decimal::percent(50));
+    assert!(pending_changes(&deps).is_empty());
+}
+
+#[test]
+fn test_cancelled_change_can_not_be_executed() {
+    let mut deps = setup(0);
+    queue_close_percentage(&mut deps, Decimal::percent(40));
+    queue_close_percentage(&mut deps, Decimal::percent(50));
+
+    let err = execute_msg(
+        &mut deps,
+        "mallory",
+        START_TS,
+        ExecuteMsg::CancelPendingChange { change_id: 1 },
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+
+    execute_msg(
+        &mut deps,
+        ADMIN,
+        START_TS,
+        ExecuteMsg::CancelPendingChange { change_id: 1 },
+    )
+    .unwrap();
+    let pending = pending_changes(&deps);
+    assert_eq!(pending.len(), 1);
+    assert_eq!(pending[0].change_id, 2);
+
+    let eta = START_TS + DEFAULT_TIMELOCK_DELAY;
+    let err = execute_change(&mut deps, eta, 1).unwrap_err();
+    assert!(matches!(err, ContractError::PendingChangeDoesNotExist));
+    execute_change(&mut deps, eta, 2).unwrap();
+    assert_eq!(close_percentage(&deps), Decimal::percent(50));
+}
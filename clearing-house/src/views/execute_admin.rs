@@ -9,19 +9,41 @@ use crate::states::market::{Amm, Market, MARKETS};
 use crate::states::state::OrderState;
 use crate::states::state::State;
 use crate::states::state::FEESTRUCTURE;
+use crate::states::state::FEE_TIERS;
+use crate::states::state::GOVERNANCE_PAUSE;
+use crate::states::state::KEEPER_REWARDS;
+use crate::states::state::KEEPER_REWARD_POOL;
 use crate::states::state::ORACLEGUARDRAILS;
 use crate::states::state::ORDERSTATE;
+use crate::states::state::PendingChange;
+use crate::states::state::PENDING_ADMIN;
+use crate::states::state::PENDING_CHANGES;
+use crate::states::state::ROLES;
+use crate::states::state::STAKING_CONTRACT;
 use crate::states::state::STATE;
+use crate::states::trading_rewards::{TradingRewardEpoch, TRADING_REWARDS, TRADING_REWARD_EPOCHS};
 
 use crate::package::helper::addr_validate_to_lower;
+use crate::package::helper::StakingInterface;
 use crate::package::helper::VaultInterface;
 use crate::package::number::Number128;
 use crate::package::types::OraclePriceData;
-use crate::package::types::{FeeStructure, OracleGuardRails, OracleSource};
+use crate::package::types::{
+    CircuitBreaker, FeeStructure, FeeTier, FundingParams, KAdjustmentParams, KeeperRewards, OracleGuardRails, OracleSource, ParameterChange, Role,
+};
 use cosmwasm_std::{
-    to_binary, CosmosMsg, Decimal, DepsMut, Env, MessageInfo, Response, Uint128, WasmMsg,
+    to_binary, Addr, CosmosMsg, Decimal, Deps, DepsMut, Env, MessageInfo, Response, Uint128,
+    WasmMsg,
 };
 
+pub fn assert_role(deps: Deps, sender: &Addr, role: Role) -> Result<(), ContractError> {
+    let members = ROLES.may_load(deps.storage, role.key())?.unwrap_or_default();
+    if !members.contains(sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+    Ok(())
+}
+
 pub fn try_initialize_market(
     deps: DepsMut,
     env: Env,
@@ -39,10 +61,8 @@ pub fn try_initialize_market(
 ) -> Result<Response, ContractError> {
     let now = env.block.time.seconds();
 
+    assert_role(deps.as_ref(), &_info.sender, Role::MarketAdmin)?;
     let state = STATE.load(deps.storage)?;
-    if state.admin != _info.sender.clone() {
-        return Err(ContractError::Unauthorized {});
-    }
     let existing_market = MARKETS.load(deps.storage, market_index.to_string());
     if existing_market.is_ok() {
         return Err(ContractError::MarketIndexAlreadyInitialized {});
@@ -81,6 +101,8 @@ pub fn try_initialize_market(
         last_oracle_price_twap_ts: now,
         last_oracle_price: Number128::zero(),
         minimum_base_asset_trade_size: Uint128::from(10000000 as u128),
+        base_spread: Uint128::zero(),
+        max_spread: Uint128::zero(),
     };
 
     // Verify there's no overflow
@@ -101,6 +123,8 @@ pub fn try_initialize_market(
     let market = Market {
         market_name: market_name,
         initialized: true,
+        settled: false,
+        settlement_price: Uint128::zero(),
         base_asset_amount_long: Number128::zero(),
         base_asset_amount_short: Number128::zero(),
         base_asset_amount: Number128::zero(),
@@ -108,7 +132,22 @@ pub fn try_initialize_market(
         margin_ratio_initial, // unit is 20% (+2 decimal places)
         margin_ratio_partial,
         margin_ratio_maintenance,
+        imf_factor: 0,
         amm: a,
+        circuit_breaker: CircuitBreaker::default(),
+        circuit_breaker_reference_price: Uint128::zero(),
+        circuit_breaker_reference_ts: 0,
+        cool_down_until_ts: 0,
+        k_adjustment: KAdjustmentParams::default(),
+        volume_since_last_k_update: Uint128::zero(),
+        last_k_update_ts: 0,
+        funding_params: FundingParams::default(),
+        premium_index_sum: Number128::zero(),
+        premium_index_duration: 0,
+        last_premium: Number128::zero(),
+        last_premium_ts: 0,
+        pnl_pool: Uint128::zero(),
+        fee_tiers: None,
     };
     MARKETS.save(deps.storage, market_index.to_string(), &market)?;
     STATE.update(deps.storage, |mut state| -> Result<_, ContractError> {
@@ -120,10 +159,12 @@ pub fn try_initialize_market(
 
 pub fn try_move_amm_price(
     mut deps: DepsMut,
+    info: MessageInfo,
     base_asset_reserve: Uint128,
     quote_asset_reserve: Uint128,
     market_index: u64,
 ) -> Result<Response, ContractError> {
+    assert_role(deps.as_ref(), &info.sender, Role::MarketAdmin)?;
     controller::amm::move_price(
         &mut deps,
         market_index,
@@ -139,12 +180,34 @@ pub fn try_withdraw_fees(
     market_index: u64,
     amount: u64,
 ) -> Result<Response, ContractError> {
+    assert_role(deps.as_ref(), &info.sender, Role::Treasury)?;
     let state = STATE.load(deps.storage)?;
-    if state.admin != info.sender.clone() {
-        return Err(ContractError::Unauthorized {});
-    }
     let mut market = MARKETS.load(deps.storage, market_index.to_string())?;
+    withdraw_from_fee_pool(&mut market, Uint128::from(amount))?;
 
+    //todo recipient who? is it only admin function
+    let message: CosmosMsg = CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: state.collateral_vault.to_string(),
+        msg: to_binary(&VaultInterface::Withdraw {
+            to_address: info.sender.clone(),
+            amount: Uint128::from(amount),
+        })?,
+        funds: vec![],
+    });
+
+    MARKETS.update(
+        deps.storage,
+        market_index.to_string(),
+        |_m| -> Result<Market, ContractError> { Ok(market) },
+    )?;
+
+    Ok(Response::new()
+        .add_message(message)
+        .add_attribute("method", "try_withdraw_fees"))
+}
+
+/// Books `amount` against the clearing house's share of the market's fees
+fn withdraw_from_fee_pool(market: &mut Market, amount: Uint128) -> Result<(), ContractError> {
     // A portion of fees must always remain in protocol to be used to keep markets optimal
     let max_withdraw = market
         .amm
@@ -153,34 +216,186 @@ pub fn try_withdraw_fees(
         .checked_div(SHARE_OF_FEES_ALLOCATED_TO_CLEARING_HOUSE_DENOMINATOR)?
         .checked_sub(market.amm.total_fee_withdrawn)?;
 
-    if amount as u128 > max_withdraw.u128() {
-        return Err(ContractError::AdminWithdrawTooLarge.into());
+    if amount > max_withdraw {
+        return Err(ContractError::AdminWithdrawTooLarge);
     }
 
-    //todo recipient who? is it only admin function
-    let message: CosmosMsg = CosmosMsg::Wasm(WasmMsg::Execute {
+    market.amm.total_fee_withdrawn = market.amm.total_fee_withdrawn.checked_add(amount)?;
+    Ok(())
+}
+
+pub fn try_fund_keeper_reward_pool(
+    deps: DepsMut,
+    info: MessageInfo,
+    market_index: u64,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    assert_role(deps.as_ref(), &info.sender, Role::Treasury)?;
+    let mut market = MARKETS.load(deps.storage, market_index.to_string())?;
+
+    // the pool is funded from the same share of fees the treasury could withdraw
+    withdraw_from_fee_pool(&mut market, amount)?;
+    MARKETS.save(deps.storage, market_index.to_string(), &market)?;
+
+    let mut pool = KEEPER_REWARD_POOL
+        .may_load(deps.storage)?
+        .unwrap_or_default();
+    pool.balance = pool.balance.checked_add(amount)?;
+    KEEPER_REWARD_POOL.save(deps.storage, &pool)?;
+
+    Ok(Response::new().add_attribute("method", "try_fund_keeper_reward_pool"))
+}
+
+pub fn try_fund_pnl_pool(
+    deps: DepsMut,
+    info: MessageInfo,
+    market_index: u64,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    assert_role(deps.as_ref(), &info.sender, Role::Treasury)?;
+    let mut market = MARKETS.load(deps.storage, market_index.to_string())?;
+
+    // like the keeper reward pool, only the withdrawable share of fees can back settled profits
+    withdraw_from_fee_pool(&mut market, amount)?;
+    market.pnl_pool = market.pnl_pool.checked_add(amount)?;
+    MARKETS.save(deps.storage, market_index.to_string(), &market)?;
+
+    Ok(Response::new().add_attribute("method", "try_fund_pnl_pool"))
+}
+
+pub fn try_fund_staking_rewards(
+    deps: DepsMut,
+    info: MessageInfo,
+    market_index: u64,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    assert_role(deps.as_ref(), &info.sender, Role::Treasury)?;
+    let state = STATE.load(deps.storage)?;
+    let staking_contract = STAKING_CONTRACT
+        .may_load(deps.storage)?
+        .ok_or(ContractError::StakingContractNotSet)?;
+    let mut market = MARKETS.load(deps.storage, market_index.to_string())?;
+
+    // stakers are paid from the same share of fees the treasury could withdraw
+    withdraw_from_fee_pool(&mut market, amount)?;
+    MARKETS.save(deps.storage, market_index.to_string(), &market)?;
+
+    // the vault transfer runs first so the rewards are in place when the staking contract books them
+    let withdraw_message: CosmosMsg = CosmosMsg::Wasm(WasmMsg::Execute {
         contract_addr: state.collateral_vault.to_string(),
         msg: to_binary(&VaultInterface::Withdraw {
-            to_address: info.sender.clone(),
-            amount: amount as u128,
+            to_address: staking_contract.clone(),
+            amount,
         })?,
         funds: vec![],
     });
+    let distribute_message: CosmosMsg = CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: staking_contract.to_string(),
+        msg: to_binary(&StakingInterface::DistributeRewards { amount })?,
+        funds: vec![],
+    });
 
-    market.amm.total_fee_withdrawn = market
-        .amm
-        .total_fee_withdrawn
-        .checked_add(Uint128::from(amount))?;
+    Ok(Response::new()
+        .add_message(withdraw_message)
+        .add_message(distribute_message)
+        .add_attribute("method", "try_fund_staking_rewards")
+        .add_attribute("amount", amount))
+}
 
-    MARKETS.update(
+pub fn try_update_keeper_rewards(
+    deps: DepsMut,
+    info: MessageInfo,
+    keeper_rewards: KeeperRewards,
+) -> Result<Response, ContractError> {
+    assert_role(deps.as_ref(), &info.sender, Role::Treasury)?;
+    KEEPER_REWARDS.save(deps.storage, &keeper_rewards)?;
+    Ok(Response::new().add_attribute("method", "try_update_keeper_rewards"))
+}
+
+pub fn try_update_trading_reward_token(
+    deps: DepsMut,
+    info: MessageInfo,
+    reward_token: String,
+) -> Result<Response, ContractError> {
+    assert_role(deps.as_ref(), &info.sender, Role::Treasury)?;
+    let reward_token = addr_validate_to_lower(deps.api, &reward_token)?;
+    let mut trading_rewards = TRADING_REWARDS.may_load(deps.storage)?.unwrap_or_default();
+    trading_rewards.reward_token = Some(reward_token.clone());
+    TRADING_REWARDS.save(deps.storage, &trading_rewards)?;
+    Ok(Response::new()
+        .add_attribute("method", "try_update_trading_reward_token")
+        .add_attribute("reward_token", reward_token))
+}
+
+pub fn try_schedule_trading_reward_epoch(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    start_ts: u64,
+    end_ts: u64,
+    reward_amount: Uint128,
+) -> Result<Response, ContractError> {
+    assert_role(deps.as_ref(), &info.sender, Role::Treasury)?;
+    let mut trading_rewards = TRADING_REWARDS.may_load(deps.storage)?.unwrap_or_default();
+    let reward_token = trading_rewards
+        .reward_token
+        .clone()
+        .ok_or(ContractError::TradingRewardTokenNotSet)?;
+
+    // epochs can't overlap so every trade counts towards at most one of them
+    let previous_end_ts = if trading_rewards.epochs_length > 0 {
+        TRADING_REWARD_EPOCHS
+            .load(deps.storage, trading_rewards.epochs_length.into())?
+            .end_ts
+    } else {
+        0
+    };
+    if start_ts < env.block.time.seconds() || start_ts < previous_end_ts || end_ts <= start_ts {
+        return Err(ContractError::InvalidTradingRewardEpoch);
+    }
+
+    trading_rewards.epochs_length = trading_rewards
+        .epochs_length
+        .checked_add(1)
+        .ok_or(ContractError::MathError)?;
+    let epoch_id = trading_rewards.epochs_length;
+    TRADING_REWARD_EPOCHS.save(
         deps.storage,
-        market_index.to_string(),
-        |_m| -> Result<Market, ContractError> { Ok(market) },
+        epoch_id.into(),
+        &TradingRewardEpoch {
+            reward_token,
+            start_ts,
+            end_ts,
+            reward_amount,
+            total_score: Uint128::zero(),
+            total_claimed: Uint128::zero(),
+        },
     )?;
+    TRADING_REWARDS.save(deps.storage, &trading_rewards)?;
 
     Ok(Response::new()
-        .add_message(message)
-        .add_attribute("method", "try_withdraw_fees"))
+        .add_attribute("method", "try_schedule_trading_reward_epoch")
+        .add_attribute("epoch_id", epoch_id.to_string()))
+}
+
+pub fn try_update_trading_reward_epoch(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    epoch_id: u64,
+    reward_amount: Uint128,
+) -> Result<Response, ContractError> {
+    assert_role(deps.as_ref(), &info.sender, Role::Treasury)?;
+    let mut epoch = TRADING_REWARD_EPOCHS.load(deps.storage, epoch_id.into())?;
+    // once an epoch ends its rewards become claimable and can't change anymore
+    if env.block.time.seconds() >= epoch.end_ts {
+        return Err(ContractError::TradingRewardEpochEnded);
+    }
+    epoch.reward_amount = reward_amount;
+    TRADING_REWARD_EPOCHS.save(deps.storage, epoch_id.into(), &epoch)?;
+    Ok(Response::new()
+        .add_attribute("method", "try_update_trading_reward_epoch")
+        .add_attribute("epoch_id", epoch_id.to_string()))
 }
 
 pub fn try_withdraw_from_insurance_vault_to_market(
@@ -189,10 +404,8 @@ pub fn try_withdraw_from_insurance_vault_to_market(
     market_index: u64,
     amount: u64,
 ) -> Result<Response, ContractError> {
+    assert_role(deps.as_ref(), &info.sender, Role::Treasury)?;
     let state = STATE.load(deps.storage)?;
-    if state.admin != info.sender.clone() {
-        return Err(ContractError::Unauthorized {});
-    }
 
     let mut market = MARKETS.load(deps.storage, market_index.to_string())?;
     market.amm.total_fee_minus_distributions = market
@@ -204,7 +417,7 @@ pub fn try_withdraw_from_insurance_vault_to_market(
         contract_addr: state.insurance_vault.to_string(),
         msg: to_binary(&VaultInterface::Withdraw {
             to_address: state.collateral_vault.clone(),
-            amount: amount as u128,
+            amount: Uint128::from(amount),
         })?,
         funds: vec![],
     });
@@ -221,9 +434,11 @@ pub fn try_withdraw_from_insurance_vault_to_market(
 pub fn try_repeg_amm_curve(
     mut deps: DepsMut,
     env: Env,
+    info: MessageInfo,
     new_peg_candidate: Uint128,
     market_index: u64,
 ) -> Result<Response, ContractError> {
+    assert_role(deps.as_ref(), &info.sender, Role::MarketAdmin)?;
     let now = env.block.time.seconds();
     let market = MARKETS.load(deps.storage, market_index.to_string())?;
     let OraclePriceData {
@@ -264,6 +479,7 @@ pub fn try_repeg_amm_curve(
         total_fee_minus_distributions: market.amm.total_fee_minus_distributions,
         adjustment_cost: Number128::new(adjustment_cost),
         oracle_price,
+        record_type: Type::Repeg,
     };
     let state = STATE.load(deps.storage)?;
     let message: CosmosMsg = CosmosMsg::Wasm(WasmMsg::Execute {
@@ -277,12 +493,20 @@ pub fn try_repeg_amm_curve(
 }
 
 pub fn try_update_amm_oracle_twap(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: Env,
+    info: MessageInfo,
     market_index: u64,
 ) -> Result<Response, ContractError> {
     let now = env.block.time.seconds();
     let mut market = MARKETS.load(deps.storage, market_index.to_string())?;
+    let keeper_rewards = KEEPER_REWARDS.may_load(deps.storage)?.unwrap_or_default();
+    let last_oracle_price_twap = market.amm.last_oracle_price_twap;
+    let reward_due_ts = market
+        .amm
+        .last_oracle_price_twap_ts
+        .checked_add(keeper_rewards.oracle_twap_update_interval)
+        .ok_or(ContractError::MathError)?;
     // todo get_oracle_twap is not defined yet
     let oracle_twap = market.amm.get_oracle_twap()?;
 
@@ -311,13 +535,29 @@ pub fn try_update_amm_oracle_twap(
         return Err(ContractError::InvalidOracle.into());
     }
 
+    // only an update that moved the twap, and not more often than the interval, earns a reward
+    let rewarded =
+        market.amm.last_oracle_price_twap != last_oracle_price_twap && now >= reward_due_ts;
+
     MARKETS.update(
         deps.storage,
         market_index.to_string(),
         |_m| -> Result<Market, ContractError> { Ok(market) },
     )?;
 
-    Ok(Response::new().add_attribute("method", "try_update_amm_oracle_twap"))
+    let keeper_reward = if rewarded {
+        controller::keeper::pay_keeper_reward(
+            &mut deps,
+            &info.sender,
+            keeper_rewards.oracle_twap_update,
+        )?
+    } else {
+        Uint128::zero()
+    };
+
+    Ok(Response::new()
+        .add_attribute("method", "try_update_amm_oracle_twap")
+        .add_attribute("keeper_reward", keeper_reward.to_string()))
 }
 
 pub fn try_reset_amm_oracle_twap(
@@ -349,11 +589,29 @@ pub fn try_reset_amm_oracle_twap(
 pub fn try_update_funding_rate(
     mut deps: DepsMut,
     env: Env,
+    info: MessageInfo,
     market_index: u64,
 ) -> Result<Response, ContractError> {
     let now = env.block.time.seconds();
     let funding_paused = STATE.load(deps.storage).unwrap().funding_paused;
     let state = STATE.load(deps.storage)?;
+    let mut messages: Vec<CosmosMsg> = vec![];
+    if let Some(c) = controller::repeg::formulaic_repeg(&mut deps, market_index, now)? {
+        let message: CosmosMsg = CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: state.history_contract.clone().to_string(),
+            msg: to_binary(&HistoryExecuteMsg::RecordCurve { c })?,
+            funds: vec![],
+        });
+        messages.push(message);
+    }
+    if let Some(c) = controller::amm::auto_adjust_k(&mut deps, market_index, now)? {
+        let message: CosmosMsg = CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: state.history_contract.clone().to_string(),
+            msg: to_binary(&HistoryExecuteMsg::RecordCurve { c })?,
+            funds: vec![],
+        });
+        messages.push(message);
+    }
     let f = controller::funding::update_funding_rate(
         &mut deps,
         market_index,
@@ -362,22 +620,36 @@ pub fn try_update_funding_rate(
         None,
     )?;
 
-    let message: CosmosMsg = CosmosMsg::Wasm(WasmMsg::Execute {
-        contract_addr: state.history_contract.clone().to_string(),
-        msg: to_binary(&HistoryExecuteMsg::RecordFundingRate { f: f.unwrap() })?,
-        funds: vec![],
-    });
+    // the keeper is only paid when the funding rate was actually updated
+    let mut keeper_reward = Uint128::zero();
+    if let Some(f) = f {
+        let keeper_rewards = KEEPER_REWARDS.may_load(deps.storage)?.unwrap_or_default();
+        keeper_reward = controller::keeper::pay_keeper_reward(
+            &mut deps,
+            &info.sender,
+            keeper_rewards.funding_rate_update,
+        )?;
+        let message: CosmosMsg = CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: state.history_contract.clone().to_string(),
+            msg: to_binary(&HistoryExecuteMsg::RecordFundingRate { f })?,
+            funds: vec![],
+        });
+        messages.push(message);
+    }
     Ok(Response::new()
-        .add_message(message)
-        .add_attribute("method", "try_update_funding_rate"))
+        .add_messages(messages)
+        .add_attribute("method", "try_update_funding_rate")
+        .add_attribute("keeper_reward", keeper_reward.to_string()))
 }
 
 pub fn try_update_k(
     mut deps: DepsMut,
     env: Env,
+    info: MessageInfo,
     market_index: u64,
     sqrt_k: Uint128,
 ) -> Result<Response, ContractError> {
+    assert_role(deps.as_ref(), &info.sender, Role::MarketAdmin)?;
     let now = env.block.time.seconds();
     let mut market = MARKETS.load(deps.storage, market_index.to_string())?;
     let state = STATE.load(deps.storage)?;
@@ -476,6 +748,7 @@ pub fn try_update_k(
         total_fee,
         total_fee_minus_distributions,
         oracle_price,
+        record_type: Type::UpdateK,
     };
 
     let message = CosmosMsg::Wasm(WasmMsg::Execute {
@@ -491,124 +764,111 @@ pub fn try_update_k(
 
 pub fn try_update_margin_ratio(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
     market_index: u64,
     margin_ratio_initial: u32,
     margin_ratio_partial: u32,
     margin_ratio_maintenance: u32,
 ) -> Result<Response, ContractError> {
-    let state = STATE.load(deps.storage)?;
-    if state.admin != info.sender.clone() {
-        return Err(ContractError::Unauthorized {});
-    }
+    assert_role(deps.as_ref(), &info.sender, Role::RiskAdmin)?;
 
     controller::margin::validate_margin(
         margin_ratio_initial,
         margin_ratio_partial,
         margin_ratio_maintenance,
     )?;
-    let mut market = MARKETS.load(deps.storage, market_index.to_string())?;
-    MARKETS.update(
-        deps.storage,
-        market_index.to_string(),
-        |_m| -> Result<Market, ContractError> {
-            market.margin_ratio_initial = margin_ratio_initial;
-            market.margin_ratio_partial = margin_ratio_partial;
-            market.margin_ratio_maintenance = margin_ratio_maintenance;
-            Ok(market)
+    MARKETS.load(deps.storage, market_index.to_string())?;
+    queue_parameter_change(
+        deps,
+        env,
+        info,
+        ParameterChange::MarginRatio {
+            market_index,
+            margin_ratio_initial,
+            margin_ratio_partial,
+            margin_ratio_maintenance,
         },
-    )?;
-    Ok(Response::new().add_attribute("method", "try_update_margin_ratio"))
+    )
+}
+
+pub fn try_update_imf_factor(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    market_index: u64,
+    imf_factor: u32,
+) -> Result<Response, ContractError> {
+    assert_role(deps.as_ref(), &info.sender, Role::RiskAdmin)?;
+
+    if Uint128::from(imf_factor) > MAXIMUM_MARGIN_RATIO {
+        return Err(ContractError::InvalidMarginRatio);
+    }
+    MARKETS.load(deps.storage, market_index.to_string())?;
+    queue_parameter_change(
+        deps,
+        env,
+        info,
+        ParameterChange::ImfFactor {
+            market_index,
+            imf_factor,
+        },
+    )
 }
 
 pub fn try_update_partial_liquidation_close_percentage(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
     value: Decimal,
 ) -> Result<Response, ContractError> {
-    STATE.update(deps.storage, |mut state| -> Result<_, ContractError> {
-        if state.admin != info.sender.clone() {
-            return Err(ContractError::Unauthorized {});
-        }
-        state.partial_liquidation_close_percentage = value;
-        Ok(state)
-    })?;
-
-    Ok(Response::new().add_attribute("method", "try_update_partial_liquidation_close_percentage"))
+    assert_role(deps.as_ref(), &info.sender, Role::RiskAdmin)?;
+    queue_parameter_change(deps, env, info, ParameterChange::PartialLiquidationClosePercentage { value })
 }
 
 pub fn try_update_partial_liquidation_penalty_percentage(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
     value: Decimal,
 ) -> Result<Response, ContractError> {
-    STATE.update(deps.storage, |mut state| -> Result<_, ContractError> {
-        if state.admin != info.sender.clone() {
-            return Err(ContractError::Unauthorized {});
-        }
-
-        state.partial_liquidation_penalty_percentage = value;
-        Ok(state)
-    })?;
-    Ok(Response::new().add_attribute(
-        "method",
-        "try_update_partial_liquidation_penalty_percentage",
-    ))
+    assert_role(deps.as_ref(), &info.sender, Role::RiskAdmin)?;
+    queue_parameter_change(deps, env, info, ParameterChange::PartialLiquidationPenaltyPercentage { value })
 }
 
 pub fn try_update_full_liquidation_penalty_percentage(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
     value: Decimal,
 ) -> Result<Response, ContractError> {
-    STATE.update(deps.storage, |mut state| -> Result<_, ContractError> {
-        if state.admin != info.sender.clone() {
-            return Err(ContractError::Unauthorized {});
-        }
-        state.full_liquidation_penalty_percentage = value;
-        Ok(state)
-    })?;
-    Ok(Response::new().add_attribute("method", "try_update_full_liquidation_penalty_percentage"))
+    assert_role(deps.as_ref(), &info.sender, Role::RiskAdmin)?;
+    queue_parameter_change(deps, env, info, ParameterChange::FullLiquidationPenaltyPercentage { value })
 }
 
 pub fn try_update_partial_liquidation_liquidator_share_denominator(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
     denominator: u64,
 ) -> Result<Response, ContractError> {
-    STATE.update(deps.storage, |mut state| -> Result<_, ContractError> {
-        if state.admin != info.sender.clone() {
-            return Err(ContractError::Unauthorized {});
-        }
-        state.partial_liquidation_liquidator_share_denominator = denominator;
-        Ok(state)
-    })?;
-    Ok(Response::new().add_attribute(
-        "method",
-        "try_update_partial_liquidation_liquidator_share_denominator",
-    ))
+    assert_role(deps.as_ref(), &info.sender, Role::RiskAdmin)?;
+    queue_parameter_change(deps, env, info, ParameterChange::PartialLiquidationLiquidatorShareDenominator { denominator })
 }
 
 pub fn try_update_full_liquidation_liquidator_share_denominator(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
     denominator: u64,
 ) -> Result<Response, ContractError> {
-    STATE.update(deps.storage, |mut state| -> Result<_, ContractError> {
-        if state.admin != info.sender.clone() {
-            return Err(ContractError::Unauthorized {});
-        }
-        state.full_liquidation_liquidator_share_denominator = denominator;
-        Ok(state)
-    })?;
-    Ok(Response::new().add_attribute(
-        "method",
-        "try_update_full_liquidation_liquidator_share_denominator",
-    ))
+    assert_role(deps.as_ref(), &info.sender, Role::RiskAdmin)?;
+    queue_parameter_change(deps, env, info, ParameterChange::FullLiquidationLiquidatorShareDenominator { denominator })
 }
 
 pub fn try_update_fee(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
     fee: Decimal,
     first_tier_minimum_balance: Uint128,
@@ -622,10 +882,7 @@ pub fn try_update_fee(
     referrer_reward: Decimal,
     referee_discount: Decimal,
 ) -> Result<Response, ContractError> {
-    let state = STATE.load(deps.storage)?;
-    if state.admin != info.sender.clone() {
-        return Err(ContractError::Unauthorized {});
-    }
+    assert_role(deps.as_ref(), &info.sender, Role::MarketAdmin)?;
     let fee_structure = FeeStructure {
         fee,
         first_tier_minimum_balance,
@@ -639,11 +896,57 @@ pub fn try_update_fee(
         referrer_reward,
         referee_discount,
     };
-    FEESTRUCTURE.update(
-        deps.storage,
-        |mut _f| -> Result<FeeStructure, ContractError> { Ok(fee_structure) },
-    )?;
-    Ok(Response::new().add_attribute("method", "try_update_fee"))
+    queue_parameter_change(deps, env, info, ParameterChange::Fee { fee_structure })
+}
+
+fn validate_fee_tiers(fee_tiers: &[FeeTier]) -> Result<(), ContractError> {
+    let ascending = fee_tiers
+        .windows(2)
+        .all(|tiers| tiers[0].min_volume < tiers[1].min_volume);
+    let valid_rates = fee_tiers.iter().all(|tier| {
+        tier.taker_fee <= Decimal::one()
+            && tier.maker_fee <= Decimal::one()
+            && tier.maker_rebate <= Decimal::one()
+            && (tier.maker_fee.is_zero() || tier.maker_rebate.is_zero())
+    });
+    if !ascending || !valid_rates {
+        return Err(ContractError::InvalidFeeTiers);
+    }
+    Ok(())
+}
+
+pub fn try_update_fee_tiers(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    fee_tiers: Vec<FeeTier>,
+) -> Result<Response, ContractError> {
+    assert_role(deps.as_ref(), &info.sender, Role::MarketAdmin)?;
+    validate_fee_tiers(&fee_tiers)?;
+    queue_parameter_change(deps, env, info, ParameterChange::FeeTiers { fee_tiers })
+}
+
+pub fn try_update_market_fee_tiers(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    market_index: u64,
+    fee_tiers: Option<Vec<FeeTier>>,
+) -> Result<Response, ContractError> {
+    assert_role(deps.as_ref(), &info.sender, Role::MarketAdmin)?;
+    if let Some(fee_tiers) = &fee_tiers {
+        validate_fee_tiers(fee_tiers)?;
+    }
+    MARKETS.load(deps.storage, market_index.to_string())?;
+    queue_parameter_change(
+        deps,
+        env,
+        info,
+        ParameterChange::MarketFeeTiers {
+            market_index,
+            fee_tiers,
+        },
+    )
 }
 
 pub fn try_update_order_state_structure(
@@ -653,10 +956,7 @@ pub fn try_update_order_state_structure(
     reward: Decimal,
     time_based_reward_lower_bound: Uint128,
 ) -> Result<Response, ContractError> {
-    let state = STATE.load(deps.storage)?;
-    if state.admin != info.sender.clone() {
-        return Err(ContractError::Unauthorized {});
-    }
+    assert_role(deps.as_ref(), &info.sender, Role::MarketAdmin)?;
     let order_state = OrderState {
         min_order_quote_asset_amount,
         reward,
@@ -676,10 +976,7 @@ pub fn try_update_market_oracle(
     oracle: String,
     oracle_source: OracleSource,
 ) -> Result<Response, ContractError> {
-    let state = STATE.load(deps.storage)?;
-    if state.admin != info.sender.clone() {
-        return Err(ContractError::Unauthorized {});
-    }
+    assert_role(deps.as_ref(), &info.sender, Role::MarketAdmin)?;
     let mut market = MARKETS.load(deps.storage, market_index.to_string())?;
     market.amm.oracle = addr_validate_to_lower(deps.api, &oracle)?;
     market.amm.oracle_source = oracle_source;
@@ -693,6 +990,7 @@ pub fn try_update_market_oracle(
 
 pub fn try_update_oracle_guard_rails(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
     use_for_liquidations: bool,
     mark_oracle_divergence: Decimal,
@@ -700,10 +998,7 @@ pub fn try_update_oracle_guard_rails(
     confidence_interval_max_size: Uint128,
     too_volatile_ratio: i128,
 ) -> Result<Response, ContractError> {
-    let state = STATE.load(deps.storage)?;
-    if state.admin != info.sender.clone() {
-        return Err(ContractError::Unauthorized {});
-    }
+    assert_role(deps.as_ref(), &info.sender, Role::RiskAdmin)?;
 
     let oracle_gr = OracleGuardRails {
         use_for_liquidations,
@@ -712,12 +1007,14 @@ pub fn try_update_oracle_guard_rails(
         confidence_interval_max_size,
         too_volatile_ratio: Number128::new(too_volatile_ratio),
     };
-    ORACLEGUARDRAILS.update(
-        deps.storage,
-        |mut _o| -> Result<OracleGuardRails, ContractError> { Ok(oracle_gr) },
-    )?;
-
-    Ok(Response::new().add_attribute("method", "try_update_oracle_guard_rails"))
+    queue_parameter_change(
+        deps,
+        env,
+        info,
+        ParameterChange::OracleGuardRails {
+            oracle_guard_rails: oracle_gr,
+        },
+    )
 }
 
 pub fn try_update_max_deposit(
@@ -725,30 +1022,72 @@ pub fn try_update_max_deposit(
     info: MessageInfo,
     max_deposit: Uint128,
 ) -> Result<Response, ContractError> {
+    assert_role(deps.as_ref(), &info.sender, Role::RiskAdmin)?;
     STATE.update(deps.storage, |mut state| -> Result<_, ContractError> {
-        if state.admin != info.sender.clone() {
-            return Err(ContractError::Unauthorized {});
-        }
         state.max_deposit = max_deposit;
         Ok(state)
     })?;
     Ok(Response::new().add_attribute("method", "try_max_deposit"))
 }
 
-pub fn try_update_admin(
+pub fn try_propose_new_admin(
     deps: DepsMut,
     info: MessageInfo,
-    admin: String,
+    new_admin: String,
 ) -> Result<Response, ContractError> {
-    let api = deps.api;
+    let state = STATE.load(deps.storage)?;
+    if state.admin != info.sender.clone() {
+        return Err(ContractError::Unauthorized {});
+    }
+    let new_admin = addr_validate_to_lower(deps.api, &new_admin)?;
+    PENDING_ADMIN.save(deps.storage, &new_admin)?;
+    Ok(Response::new()
+        .add_attribute("method", "try_propose_new_admin")
+        .add_attribute("pending_admin", new_admin))
+}
+
+pub fn try_accept_admin(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    let pending_admin = PENDING_ADMIN
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NoPendingAdmin)?;
+    if pending_admin != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    let old_admin = STATE.load(deps.storage)?.admin;
     STATE.update(deps.storage, |mut state| -> Result<_, ContractError> {
-        if state.admin != info.sender.clone() {
-            return Err(ContractError::Unauthorized {});
-        }
-        state.admin = addr_validate_to_lower(api, &admin)?;
+        state.admin = pending_admin.clone();
         Ok(state)
     })?;
-    Ok(Response::new().add_attribute("method", "try_max_deposit"))
+    // the handover includes every role the old admin held
+    for role in Role::all() {
+        if let Some(mut members) = ROLES.may_load(deps.storage, role.key())? {
+            if !members.contains(&old_admin) {
+                continue;
+            }
+            members.retain(|m| *m != old_admin && *m != pending_admin);
+            members.push(pending_admin.clone());
+            ROLES.save(deps.storage, role.key(), &members)?;
+        }
+    }
+    PENDING_ADMIN.remove(deps.storage);
+    Ok(Response::new()
+        .add_attribute("method", "try_accept_admin")
+        .add_attribute("admin", pending_admin))
+}
+
+pub fn try_cancel_admin_proposal(
+    deps: DepsMut,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let state = STATE.load(deps.storage)?;
+    if state.admin != info.sender.clone() {
+        return Err(ContractError::Unauthorized {});
+    }
+    if PENDING_ADMIN.may_load(deps.storage)?.is_none() {
+        return Err(ContractError::NoPendingAdmin);
+    }
+    PENDING_ADMIN.remove(deps.storage);
+    Ok(Response::new().add_attribute("method", "try_cancel_admin_proposal"))
 }
 
 pub fn try_update_exchange_paused(
@@ -756,11 +1095,12 @@ pub fn try_update_exchange_paused(
     info: MessageInfo,
     exchange_paused: bool,
 ) -> Result<Response, ContractError> {
+    assert_role(deps.as_ref(), &info.sender, Role::Pauser)?;
+    let governance_pause = GOVERNANCE_PAUSE.may_load(deps.storage)?.unwrap_or_default();
+    if !exchange_paused && governance_pause.exchange_paused {
+        return Err(ContractError::PausedByGovernance);
+    }
     STATE.update(deps.storage, |mut state| -> Result<_, ContractError> {
-        if state.admin != info.sender.clone() {
-            return Err(ContractError::Unauthorized {});
-        }
-
         state.exchange_paused = exchange_paused;
         Ok(state)
     })?;
@@ -785,10 +1125,12 @@ pub fn try_update_funding_paused(
     info: MessageInfo,
     funding_paused: bool,
 ) -> Result<Response, ContractError> {
+    assert_role(deps.as_ref(), &info.sender, Role::Pauser)?;
+    let governance_pause = GOVERNANCE_PAUSE.may_load(deps.storage)?.unwrap_or_default();
+    if !funding_paused && governance_pause.funding_paused {
+        return Err(ContractError::PausedByGovernance);
+    }
     STATE.update(deps.storage, |mut state| -> Result<_, ContractError> {
-        if state.admin != info.sender.clone() {
-            return Err(ContractError::Unauthorized {});
-        }
         state.funding_paused = funding_paused;
         Ok(state)
     })?;
@@ -801,10 +1143,7 @@ pub fn try_update_market_minimum_quote_asset_trade_size(
     market_index: u64,
     minimum_trade_size: Uint128,
 ) -> Result<Response, ContractError> {
-    let state = STATE.load(deps.storage)?;
-    if state.admin != info.sender.clone() {
-        return Err(ContractError::Unauthorized {});
-    }
+    assert_role(deps.as_ref(), &info.sender, Role::MarketAdmin)?;
     let mut market = MARKETS.load(deps.storage, market_index.to_string())?;
     MARKETS.update(
         deps.storage,
@@ -823,10 +1162,7 @@ pub fn try_update_market_minimum_base_asset_trade_size(
     market_index: u64,
     minimum_trade_size: Uint128,
 ) -> Result<Response, ContractError> {
-    let state = STATE.load(deps.storage)?;
-    if state.admin != info.sender.clone() {
-        return Err(ContractError::Unauthorized {});
-    }
+    assert_role(deps.as_ref(), &info.sender, Role::MarketAdmin)?;
     
     MARKETS.update(
         deps.storage,
@@ -844,15 +1180,131 @@ pub fn try_update_market_minimum_base_asset_trade_size(
     Ok(Response::new().add_attribute("method", "try_update_market_minimum_base_asset_trade_size"))
 }
 
+pub fn try_update_circuit_breaker(
+    deps: DepsMut,
+    info: MessageInfo,
+    market_index: u64,
+    circuit_breaker: CircuitBreaker,
+) -> Result<Response, ContractError> {
+    assert_role(deps.as_ref(), &info.sender, Role::RiskAdmin)?;
+    if circuit_breaker.max_price_move > Decimal::one() {
+        return Err(ContractError::InvalidCircuitBreaker);
+    }
+
+    MARKETS.update(
+        deps.storage,
+        market_index.to_string(),
+        |m| -> Result<_, ContractError> {
+            match m {
+                Some(mut mr) => {
+                    mr.circuit_breaker = circuit_breaker;
+                    // restart the window from the next trade
+                    mr.circuit_breaker_reference_price = Uint128::zero();
+                    Ok(mr)
+                }
+                None => Err(ContractError::MarketIndexNotInitialized),
+            }
+        },
+    )?;
+    Ok(Response::new().add_attribute("method", "try_update_circuit_breaker"))
+}
+
+pub fn try_update_k_adjustment_params(
+    deps: DepsMut,
+    info: MessageInfo,
+    market_index: u64,
+    k_adjustment: KAdjustmentParams,
+) -> Result<Response, ContractError> {
+    assert_role(deps.as_ref(), &info.sender, Role::MarketAdmin)?;
+    // adjust_k_cost rejects decreasing k by more than 2.5% in one go
+    if k_adjustment.k_change > Decimal::permille(25) {
+        return Err(ContractError::InvalidKAdjustmentParams);
+    }
+
+    MARKETS.update(
+        deps.storage,
+        market_index.to_string(),
+        |m| -> Result<_, ContractError> {
+            match m {
+                Some(mut mr) => {
+                    mr.k_adjustment = k_adjustment;
+                    Ok(mr)
+                }
+                None => Err(ContractError::MarketIndexNotInitialized),
+            }
+        },
+    )?;
+    Ok(Response::new().add_attribute("method", "try_update_k_adjustment_params"))
+}
+
+pub fn try_update_funding_params(
+    deps: DepsMut,
+    info: MessageInfo,
+    market_index: u64,
+    funding_params: FundingParams,
+) -> Result<Response, ContractError> {
+    assert_role(deps.as_ref(), &info.sender, Role::RiskAdmin)?;
+    // the interest component alone must fit under the cap
+    if funding_params.max_funding_rate > Decimal::one()
+        || funding_params.interest_rate > Decimal::one()
+        || (!funding_params.max_funding_rate.is_zero()
+            && funding_params.interest_rate > funding_params.max_funding_rate)
+    {
+        return Err(ContractError::InvalidFundingParams);
+    }
+
+    MARKETS.update(
+        deps.storage,
+        market_index.to_string(),
+        |m| -> Result<_, ContractError> {
+            match m {
+                Some(mut mr) => {
+                    mr.funding_params = funding_params;
+                    Ok(mr)
+                }
+                None => Err(ContractError::MarketIndexNotInitialized),
+            }
+        },
+    )?;
+    Ok(Response::new().add_attribute("method", "try_update_funding_params"))
+}
+
+pub fn try_update_market_spread(
+    deps: DepsMut,
+    info: MessageInfo,
+    market_index: u64,
+    base_spread: Uint128,
+    max_spread: Uint128,
+) -> Result<Response, ContractError> {
+    assert_role(deps.as_ref(), &info.sender, Role::MarketAdmin)?;
+    if base_spread > max_spread || max_spread >= PRICE_SPREAD_PRECISION_U128 {
+        return Err(ContractError::InvalidSpread);
+    }
+
+    MARKETS.update(
+        deps.storage,
+        market_index.to_string(),
+        |m| -> Result<_, ContractError> {
+            match m {
+                Some(mut mr) => {
+                    mr.amm.base_spread = base_spread;
+                    mr.amm.max_spread = max_spread;
+                    Ok(mr)
+                }
+                None => Err(ContractError::MarketIndexNotInitialized),
+            }
+        },
+    )?;
+    Ok(Response::new().add_attribute("method", "try_update_market_spread"))
+}
+
 pub fn try_update_oracle_address(
     deps: DepsMut,
     info: MessageInfo,
     oracle: String,
 ) -> Result<Response, ContractError> {
+    assert_role(deps.as_ref(), &info.sender, Role::MarketAdmin)?;
     let mut state = STATE.load(deps.storage)?;
-    if state.admin != info.sender.clone() {
-        return Err(ContractError::Unauthorized {});
-    }
     
     state.oracle = addr_validate_to_lower(deps.api, &oracle)?;
     STATE.update(deps.storage, |_state| -> Result<State, ContractError> {
@@ -867,10 +1319,7 @@ pub fn try_feeding_price(
     market_index: u64,
     price: i128,
 ) -> Result<Response, ContractError> {
-    let state = STATE.load(deps.storage)?;
-    if state.admin != info.sender.clone() {
-        return Err(ContractError::Unauthorized {});
-    }
+    assert_role(deps.as_ref(), &info.sender, Role::PriceFeeder)?;
     
     let mut market = MARKETS.load(deps.storage, market_index.to_string())?;
     market.amm.last_oracle_price = Number128::new(price);
@@ -900,3 +1349,262 @@ pub fn try_update_history_contract(
     })?;
     Ok(Response::new().add_attribute("method", "try_update_history_contract"))
 }
+
+pub fn try_update_staking_contract(
+    deps: DepsMut,
+    info: MessageInfo,
+    staking_contract: String,
+) -> Result<Response, ContractError> {
+    let state = STATE.load(deps.storage)?;
+    if state.admin != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let staking_contract = deps.api.addr_validate(&staking_contract)?;
+    STAKING_CONTRACT.save(deps.storage, &staking_contract)?;
+    Ok(Response::new()
+        .add_attribute("method", "try_update_staking_contract")
+        .add_attribute("staking_contract", staking_contract))
+}
+
+pub fn try_grant_role(
+    deps: DepsMut,
+    info: MessageInfo,
+    role: Role,
+    address: String,
+) -> Result<Response, ContractError> {
+    let state = STATE.load(deps.storage)?;
+    if state.admin != info.sender.clone() {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let addr = addr_validate_to_lower(deps.api, &address)?;
+    let mut members = ROLES.may_load(deps.storage, role.key())?.unwrap_or_default();
+    if members.contains(&addr) {
+        return Err(ContractError::RoleAlreadyGranted);
+    }
+    members.push(addr.clone());
+    ROLES.save(deps.storage, role.key(), &members)?;
+    Ok(Response::new()
+        .add_attribute("method", "try_grant_role")
+        .add_attribute("role", role.key())
+        .add_attribute("address", addr))
+}
+
+pub fn try_revoke_role(
+    deps: DepsMut,
+    info: MessageInfo,
+    role: Role,
+    address: String,
+) -> Result<Response, ContractError> {
+    let state = STATE.load(deps.storage)?;
+    if state.admin != info.sender.clone() {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let addr = addr_validate_to_lower(deps.api, &address)?;
+    let mut members = ROLES.may_load(deps.storage, role.key())?.unwrap_or_default();
+    if !members.contains(&addr) {
+        return Err(ContractError::RoleNotGranted);
+    }
+    members.retain(|m| *m != addr);
+    ROLES.save(deps.storage, role.key(), &members)?;
+    Ok(Response::new()
+        .add_attribute("method", "try_revoke_role")
+        .add_attribute("role", role.key())
+        .add_attribute("address", addr))
+}
+
+pub fn try_update_timelock_delay(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    delay: u64,
+) -> Result<Response, ContractError> {
+    let state = STATE.load(deps.storage)?;
+    if state.admin != info.sender.clone() {
+        return Err(ContractError::Unauthorized {});
+    }
+    // shortening the delay is itself a sensitive change, so it waits behind the current one
+    queue_parameter_change(deps, env, info, ParameterChange::TimelockDelay { delay })
+}
+
+fn queue_parameter_change(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    change: ParameterChange,
+) -> Result<Response, ContractError> {
+    let now = env.block.time.seconds();
+    let mut state = STATE.load(deps.storage)?;
+    let change_id = state
+        .pending_changes_length
+        .checked_add(1)
+        .ok_or(ContractError::MathError)?;
+    let eta = now
+        .checked_add(state.timelock_delay)
+        .ok_or(ContractError::MathError)?;
+
+    PENDING_CHANGES.save(
+        deps.storage,
+        change_id.into(),
+        &PendingChange {
+            change,
+            proposer: info.sender.clone(),
+            queued_ts: now,
+            eta,
+        },
+    )?;
+    state.pending_changes_length = change_id;
+    STATE.save(deps.storage, &state)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "queue_parameter_change")
+        .add_attribute("change_id", change_id.to_string())
+        .add_attribute("eta", eta.to_string()))
+}
+
+pub fn try_execute_pending_change(
+    deps: DepsMut,
+    env: Env,
+    change_id: u64,
+) -> Result<Response, ContractError> {
+    let now = env.block.time.seconds();
+    let pending = PENDING_CHANGES
+        .may_load(deps.storage, change_id.into())?
+        .ok_or(ContractError::PendingChangeDoesNotExist)?;
+    if now < pending.eta {
+        return Err(ContractError::PendingChangeTimelocked);
+    }
+
+    match pending.change {
+        ParameterChange::MarginRatio {
+            market_index,
+            margin_ratio_initial,
+            margin_ratio_partial,
+            margin_ratio_maintenance,
+        } => {
+            controller::margin::validate_margin(
+                margin_ratio_initial,
+                margin_ratio_partial,
+                margin_ratio_maintenance,
+            )?;
+            let mut market = MARKETS.load(deps.storage, market_index.to_string())?;
+            MARKETS.update(
+                deps.storage,
+                market_index.to_string(),
+                |_m| -> Result<Market, ContractError> {
+                    market.margin_ratio_initial = margin_ratio_initial;
+                    market.margin_ratio_partial = margin_ratio_partial;
+                    market.margin_ratio_maintenance = margin_ratio_maintenance;
+                    Ok(market)
+                },
+            )?;
+        }
+        ParameterChange::ImfFactor {
+            market_index,
+            imf_factor,
+        } => {
+            MARKETS.update(
+                deps.storage,
+                market_index.to_string(),
+                |m| -> Result<Market, ContractError> {
+                    match m {
+                        Some(mut market) => {
+                            market.imf_factor = imf_factor;
+                            Ok(market)
+                        }
+                        None => Err(ContractError::MarketIndexNotInitialized),
+                    }
+                },
+            )?;
+        }
+        ParameterChange::PartialLiquidationClosePercentage { value } => {
+            STATE.update(deps.storage, |mut state| -> Result<_, ContractError> {
+                state.partial_liquidation_close_percentage = value;
+                Ok(state)
+            })?;
+        }
+        ParameterChange::PartialLiquidationPenaltyPercentage { value } => {
+            STATE.update(deps.storage, |mut state| -> Result<_, ContractError> {
+                state.partial_liquidation_penalty_percentage = value;
+                Ok(state)
+            })?;
+        }
+        ParameterChange::FullLiquidationPenaltyPercentage { value } => {
+            STATE.update(deps.storage, |mut state| -> Result<_, ContractError> {
+                state.full_liquidation_penalty_percentage = value;
+                Ok(state)
+            })?;
+        }
+        ParameterChange::PartialLiquidationLiquidatorShareDenominator { denominator } => {
+            STATE.update(deps.storage, |mut state| -> Result<_, ContractError> {
+                state.partial_liquidation_liquidator_share_denominator = denominator;
+                Ok(state)
+            })?;
+        }
+        ParameterChange::FullLiquidationLiquidatorShareDenominator { denominator } => {
+            STATE.update(deps.storage, |mut state| -> Result<_, ContractError> {
+                state.full_liquidation_liquidator_share_denominator = denominator;
+                Ok(state)
+            })?;
+        }
+        ParameterChange::Fee { fee_structure } => {
+            FEESTRUCTURE.save(deps.storage, &fee_structure)?;
+        }
+        ParameterChange::FeeTiers { fee_tiers } => {
+            FEE_TIERS.save(deps.storage, &fee_tiers)?;
+        }
+        ParameterChange::MarketFeeTiers {
+            market_index,
+            fee_tiers,
+        } => {
+            MARKETS.update(
+                deps.storage,
+                market_index.to_string(),
+                |m| -> Result<Market, ContractError> {
+                    match m {
+                        Some(mut market) => {
+                            market.fee_tiers = fee_tiers;
+                            Ok(market)
+                        }
+                        None => Err(ContractError::MarketIndexNotInitialized),
+                    }
+                },
+            )?;
+        }
+        ParameterChange::OracleGuardRails { oracle_guard_rails } => {
+            ORACLEGUARDRAILS.save(deps.storage, &oracle_guard_rails)?;
+        }
+        ParameterChange::TimelockDelay { delay } => {
+            STATE.update(deps.storage, |mut state| -> Result<_, ContractError> {
+                state.timelock_delay = delay;
+                Ok(state)
+            })?;
+        }
+    }
+    PENDING_CHANGES.remove(deps.storage, change_id.into());
+
+    Ok(Response::new()
+        .add_attribute("method", "try_execute_pending_change")
+        .add_attribute("change_id", change_id.to_string()))
+}
+
+pub fn try_cancel_pending_change(
+    deps: DepsMut,
+    info: MessageInfo,
+    change_id: u64,
+) -> Result<Response, ContractError> {
+    let state = STATE.load(deps.storage)?;
+    if state.admin != info.sender.clone() {
+        return Err(ContractError::Unauthorized {});
+    }
+    if !PENDING_CHANGES.has(deps.storage, change_id.into()) {
+        return Err(ContractError::PendingChangeDoesNotExist);
+    }
+    PENDING_CHANGES.remove(deps.storage, change_id.into());
+
+    Ok(Response::new()
+        .add_attribute("method", "try_cancel_pending_change")
+        .add_attribute("change_id", change_id.to_string()))
+}
@@ -0,0 +1,91 @@
+use crate::ContractError;
+
+use crate::states::market::{Market, MARKETS};
+use crate::states::state::GOVERNANCE_PAUSE;
+use crate::states::state::PENDING_ADMIN;
+use crate::states::state::ROLES;
+use crate::states::state::STATE;
+
+use crate::package::types::Role;
+
+use crate::package::helper::addr_validate_to_lower;
+use cosmwasm_std::{DepsMut, Response, Uint128};
+
+pub fn try_sudo_update_exchange_paused(
+    deps: DepsMut,
+    exchange_paused: bool,
+) -> Result<Response, ContractError> {
+    let mut governance_pause = GOVERNANCE_PAUSE.may_load(deps.storage)?.unwrap_or_default();
+    governance_pause.exchange_paused = exchange_paused;
+    GOVERNANCE_PAUSE.save(deps.storage, &governance_pause)?;
+    STATE.update(deps.storage, |mut state| -> Result<_, ContractError> {
+        state.exchange_paused = exchange_paused;
+        Ok(state)
+    })?;
+    Ok(Response::new()
+        .add_attribute("method", "try_sudo_update_exchange_paused")
+        .add_attribute("exchange_paused", exchange_paused.to_string()))
+}
+
+pub fn try_sudo_update_funding_paused(
+    deps: DepsMut,
+    funding_paused: bool,
+) -> Result<Response, ContractError> {
+    let mut governance_pause = GOVERNANCE_PAUSE.may_load(deps.storage)?.unwrap_or_default();
+    governance_pause.funding_paused = funding_paused;
+    GOVERNANCE_PAUSE.save(deps.storage, &governance_pause)?;
+    STATE.update(deps.storage, |mut state| -> Result<_, ContractError> {
+        state.funding_paused = funding_paused;
+        Ok(state)
+    })?;
+    Ok(Response::new()
+        .add_attribute("method", "try_sudo_update_funding_paused")
+        .add_attribute("funding_paused", funding_paused.to_string()))
+}
+
+pub fn try_sudo_update_admin(deps: DepsMut, admin: String) -> Result<Response, ContractError> {
+    let admin = addr_validate_to_lower(deps.api, &admin)?;
+    let old_admin = STATE.load(deps.storage)?.admin;
+    STATE.update(deps.storage, |mut state| -> Result<_, ContractError> {
+        state.admin = admin.clone();
+        Ok(state)
+    })?;
+    // the replaced admin keeps no access through roles granted to itself
+    for role in Role::all() {
+        if let Some(mut members) = ROLES.may_load(deps.storage, role.key())? {
+            members.retain(|m| *m != old_admin);
+            ROLES.save(deps.storage, role.key(), &members)?;
+        }
+    }
+    // a transfer proposed by the replaced admin must not be completable
+    PENDING_ADMIN.remove(deps.storage);
+    Ok(Response::new()
+        .add_attribute("method", "try_sudo_update_admin")
+        .add_attribute("admin", admin))
+}
+
+pub fn try_sudo_settle_market(deps: DepsMut, market_index: u64) -> Result<Response, ContractError> {
+    let market = MARKETS.update(
+        deps.storage,
+        market_index.to_string(),
+        |m| -> Result<Market, ContractError> {
+            match m {
+                Some(mut market) => {
+                    // the oracle twap is harder to move in the settling block than mark or spot oracle
+                    let oracle_price_twap = market.amm.last_oracle_price_twap.i128();
+                    if oracle_price_twap <= 0 {
+                        return Err(ContractError::InvalidSettlementPrice);
+                    }
+                    market.settled = true;
+                    market.settlement_price = Uint128::from(oracle_price_twap.unsigned_abs());
+                    Ok(market)
+                }
+                None => Err(ContractError::MarketIndexNotInitialized),
+            }
+        },
+    )?;
+    Ok(Response::new()
+        .add_attribute("method", "try_sudo_settle_market")
+        .add_attribute("market_index", market_index.to_string())
+        .add_attribute("settlement_price", market.settlement_price.to_string()))
+}
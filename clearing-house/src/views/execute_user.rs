@@ -3,6 +3,7 @@ use std::ops::Div;
 use crate::controller;
 use crate::helpers;
 use crate::helpers::position::calculate_withdrawal_amounts;
+use crate::package::execute::BatchAction;
 use crate::package::history::HistoryExecuteMsg;
 use crate::states::constants::*;
 use crate::states::history::*;
@@ -11,19 +12,26 @@ use crate::ContractError;
 use crate::states::market::LiquidationStatus;
 use crate::states::market::LiquidationType;
 use crate::states::market::{Market, MARKETS};
+use crate::states::order::TWAP_ORDERS;
+use crate::states::referral::{REFERRAL_CODES, REFERRERS};
+use crate::states::trading_rewards::{TRADING_REWARD_EPOCHS, TRADING_REWARD_SCORES};
 use crate::states::state::FEESTRUCTURE;
+use crate::states::state::KEEPER_REWARDS;
 use crate::states::state::ORACLEGUARDRAILS;
 use crate::states::state::STATE;
-use crate::states::user::{User, POSITIONS, USERS};
+use crate::states::user::{Position, User, POSITIONS, USERS};
 
 use crate::package::helper::addr_validate_to_lower;
 use crate::package::helper::assert_sent_uusd_balance;
 use crate::package::helper::query_balance;
+use crate::package::helper::parse_u64_key;
+use crate::package::helper::Cw20Interface;
 use crate::package::helper::VaultInterface;
 use crate::package::number::Number128;
-use crate::package::types::{DepositDirection, PositionDirection};
+use crate::package::types::{DepositDirection, OrderParams, OrderType, PositionDirection};
 use cosmwasm_std::{
-    coins, to_binary, CosmosMsg, DepsMut, Env, Fraction, MessageInfo, Response, Uint128, WasmMsg,
+    coins, to_binary, Addr, CosmosMsg, Decimal, DepsMut, Env, Fraction, MessageInfo, Order,
+    Response, StdResult, Uint128, WasmMsg,
 };
 
 pub fn try_deposit_collateral(
@@ -31,7 +39,7 @@ pub fn try_deposit_collateral(
     env: Env,
     info: MessageInfo,
     amount: u64,
-    referrer: Option<String>,
+    referral_code: Option<String>,
 ) -> Result<Response, ContractError> {
     let user_address = info.sender.clone();
     let existing_user = USERS.may_load(deps.storage, &user_address)?;
@@ -41,27 +49,20 @@ pub fn try_deposit_collateral(
         // user = existing_user.unwrap();
         user = existing_user.unwrap();
     } else {
-        if referrer.is_some() {
-            user = User {
-                collateral: Uint128::zero(),
-                cumulative_deposits: Uint128::zero(),
-                total_fee_paid: Uint128::zero(),
-                total_token_discount: Uint128::zero(),
-                total_referral_reward: Uint128::zero(),
-                total_referee_discount: Uint128::zero(),
-                referrer: Some(addr_validate_to_lower(deps.api, &referrer.unwrap())?),
-            };
-        } else {
-            user = User {
-                collateral: Uint128::zero(),
-                cumulative_deposits: Uint128::zero(),
-                total_fee_paid: Uint128::zero(),
-                total_token_discount: Uint128::zero(),
-                total_referral_reward: Uint128::zero(),
-                total_referee_discount: Uint128::zero(),
-                referrer: None,
-            };
-        }
+        user = User {
+            collateral: Uint128::zero(),
+            cumulative_deposits: Uint128::zero(),
+            total_fee_paid: Uint128::zero(),
+            total_token_discount: Uint128::zero(),
+            total_referral_reward: Uint128::zero(),
+            total_referee_discount: Uint128::zero(),
+            daily_volumes: vec![],
+            last_volume_update_ts: 0,
+            referrer: None,
+        };
+    }
+    if let Some(referral_code) = referral_code {
+        controller::referral::set_referrer(&mut deps, &user_address, &mut user, referral_code)?;
     }
 
     if amount == 0 {
@@ -70,6 +71,9 @@ pub fn try_deposit_collateral(
 
     assert_sent_uusd_balance(&info.clone(), amount as u128)?;
     let state = STATE.load(deps.storage)?;
+    if state.exchange_paused {
+        return Err(ContractError::ExchangePaused);
+    }
 
     let collateral_before = user.collateral;
     let cumulative_deposits_before = user.cumulative_deposits;
@@ -125,8 +129,150 @@ pub fn try_withdraw_collateral(
     info: MessageInfo,
     amount: u64,
 ) -> Result<Response, ContractError> {
-    let user_address = info.sender.clone();
-    let existing_user = USERS.may_load(deps.storage, &user_address)?;
+    let state = STATE.load(deps.storage)?;
+    let mut history: Vec<HistoryExecuteMsg> = vec![];
+    let mut messages: Vec<CosmosMsg> = vec![];
+    withdraw_collateral(&mut deps, &env, &info.sender, amount, &mut history, &mut messages)?;
+
+    if !controller::margin::meets_initial_margin_requirement(&mut deps, &info.sender)? {
+        return Err(ContractError::InsufficientCollateral);
+    }
+
+    Ok(Response::new()
+        .add_messages(history_messages(&state.history_contract, history)?)
+        .add_messages(messages)
+        .add_attribute("method", "try_withdraw_collateral"))
+}
+
+pub fn try_register_referral_code(
+    deps: DepsMut,
+    info: MessageInfo,
+    code: String,
+) -> Result<Response, ContractError> {
+    let valid_code = (3..=32).contains(&code.len())
+        && code
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_');
+    if !valid_code {
+        return Err(ContractError::InvalidReferralCode);
+    }
+    if REFERRAL_CODES.has(deps.storage, code.clone()) {
+        return Err(ContractError::ReferralCodeTaken);
+    }
+
+    let mut referrer = REFERRERS
+        .may_load(deps.storage, &info.sender)?
+        .unwrap_or_default();
+    if !referrer.code.is_empty() {
+        return Err(ContractError::ReferralCodeAlreadyRegistered);
+    }
+    referrer.code = code.clone();
+    REFERRERS.save(deps.storage, &info.sender, &referrer)?;
+    REFERRAL_CODES.save(deps.storage, code.clone(), &info.sender)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "try_register_referral_code")
+        .add_attribute("code", code))
+}
+
+pub fn try_claim_referral_rewards(
+    deps: DepsMut,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let state = STATE.load(deps.storage)?;
+    if state.exchange_paused {
+        return Err(ContractError::ExchangePaused);
+    }
+
+    let mut referrer = REFERRERS
+        .may_load(deps.storage, &info.sender)?
+        .ok_or(ContractError::NoReferralRewards)?;
+    let amount = referrer.unclaimed_reward;
+    if amount.is_zero() {
+        return Err(ContractError::NoReferralRewards);
+    }
+    referrer.unclaimed_reward = Uint128::zero();
+    REFERRERS.save(deps.storage, &info.sender, &referrer)?;
+
+    let message: CosmosMsg = CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: state.collateral_vault.to_string(),
+        msg: to_binary(&VaultInterface::Withdraw {
+            to_address: info.sender.clone(),
+            amount,
+        })?,
+        funds: vec![],
+    });
+
+    Ok(Response::new()
+        .add_message(message)
+        .add_attribute("method", "try_claim_referral_rewards")
+        .add_attribute("amount", amount))
+}
+
+pub fn try_claim_trading_rewards(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let now = env.block.time.seconds();
+    let state = STATE.load(deps.storage)?;
+    if state.exchange_paused {
+        return Err(ContractError::ExchangePaused);
+    }
+    let scores = TRADING_REWARD_SCORES
+        .prefix(&info.sender)
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    // pays out every epoch that has ended since the last claim, each in its own reward token
+    let mut rewards: Vec<(Addr, Uint128)> = vec![];
+    for (k, mut score) in scores {
+        if score.claimed {
+            continue;
+        }
+        let epoch_id = parse_u64_key(&k).ok_or(ContractError::MathError)?;
+        let mut epoch = TRADING_REWARD_EPOCHS.load(deps.storage, epoch_id.into())?;
+        if now < epoch.end_ts {
+            continue;
+        }
+        let reward = epoch.reward_for_score(score.score);
+        epoch.total_claimed = epoch.total_claimed.checked_add(reward)?;
+        TRADING_REWARD_EPOCHS.save(deps.storage, epoch_id.into(), &epoch)?;
+        score.claimed = true;
+        TRADING_REWARD_SCORES.save(deps.storage, (&info.sender, epoch_id.into()), &score)?;
+        controller::trading_rewards::add_reward(&mut rewards, &epoch.reward_token, reward)?;
+    }
+    rewards.retain(|(_, amount)| !amount.is_zero());
+    if rewards.is_empty() {
+        return Err(ContractError::NoTradingRewards);
+    }
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    let mut response = Response::new().add_attribute("method", "try_claim_trading_rewards");
+    for (reward_token, amount) in rewards {
+        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: reward_token.to_string(),
+            msg: to_binary(&Cw20Interface::Transfer {
+                recipient: info.sender.to_string(),
+                amount,
+            })?,
+            funds: vec![],
+        }));
+        response = response.add_attribute(format!("amount_{}", reward_token), amount);
+    }
+
+    Ok(response.add_messages(messages))
+}
+
+fn withdraw_collateral(
+    deps: &mut DepsMut,
+    env: &Env,
+    user_address: &Addr,
+    amount: u64,
+    history: &mut Vec<HistoryExecuteMsg>,
+    messages: &mut Vec<CosmosMsg>,
+) -> Result<(), ContractError> {
+    let existing_user = USERS.may_load(deps.storage, user_address)?;
     let now = env.block.time.seconds();
     let mut user;
     if existing_user.is_none() {
@@ -137,15 +283,12 @@ pub fn try_withdraw_collateral(
     let collateral_before = user.collateral;
     let cumulative_deposits_before = user.cumulative_deposits;
     let state = STATE.load(deps.storage)?;
-    let f = controller::funding::settle_funding_payment(&mut deps, &user_address, now)?;
-    let mut messages: Vec<CosmosMsg> = vec![];
-    let message: CosmosMsg = CosmosMsg::Wasm(WasmMsg::Execute {
-        contract_addr: state.history_contract.clone().to_string(),
-        msg: to_binary(&HistoryExecuteMsg::RecordFundingPaymentsMultiple { vecf: f })?,
-        funds: vec![],
-    });
-    messages.push(message);
-    user = USERS.may_load(deps.storage, &user_address)?.unwrap();
+    if state.exchange_paused {
+        return Err(ContractError::ExchangePaused);
+    }
+    let f = controller::funding::settle_funding_payment(deps, user_address, now)?;
+    history.push(HistoryExecuteMsg::RecordFundingPaymentsMultiple { vecf: f });
+    user = USERS.may_load(deps.storage, user_address)?.unwrap();
 
     if (amount as u128) > user.collateral.u128() {
         return Err(ContractError::InsufficientCollateral.into());
@@ -173,15 +316,11 @@ pub fn try_withdraw_collateral(
         .checked_sub(Uint128::from(collateral_account_withdrawal))?
         .checked_sub(Uint128::from(insurance_account_withdrawal))?;
 
-    if !controller::margin::meets_initial_margin_requirement(&mut deps, &info.sender.clone())? {
-        return Err(ContractError::InsufficientCollateral.into());
-    }
-
     messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
         contract_addr: state.collateral_vault.clone().to_string(),
         msg: to_binary(&VaultInterface::Withdraw {
-            to_address: info.sender.clone(),
-            amount: collateral_account_withdrawal.u128(),
+            to_address: user_address.clone(),
+            amount: collateral_account_withdrawal,
         })?,
         funds: vec![],
     }));
@@ -190,35 +329,29 @@ pub fn try_withdraw_collateral(
         messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
             contract_addr: state.insurance_vault.to_string(),
             msg: to_binary(&VaultInterface::Withdraw {
-                to_address: info.sender.clone(),
-                amount: insurance_account_withdrawal.u128(),
+                to_address: user_address.clone(),
+                amount: insurance_account_withdrawal,
             })?,
             funds: vec![],
         }));
     }
 
-    messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
-        contract_addr: state.history_contract.clone().to_string(),
-        msg: to_binary(&HistoryExecuteMsg::RecordDeposit {
-            d: DepositRecord {
-                ts: now,
-                user: user_address.clone(),
-                direction: DepositDirection::WITHDRAW,
-                collateral_before,
-                cumulative_deposits_before,
-                amount: amount_withdraw.u128() as u64,
-            },
-        })?,
-        funds: vec![],
-    }));
+    history.push(HistoryExecuteMsg::RecordDeposit {
+        d: DepositRecord {
+            ts: now,
+            user: user_address.clone(),
+            direction: DepositDirection::WITHDRAW,
+            collateral_before,
+            cumulative_deposits_before,
+            amount: amount_withdraw.u128() as u64,
+        },
+    });
     USERS.update(
         deps.storage,
-        &user_address.clone(),
+        user_address,
         |_u| -> Result<User, ContractError> { Ok(user) },
     )?;
-    Ok(Response::new()
-        .add_messages(messages)
-        .add_attribute("method", "try_withdraw_collateral"))
+    Ok(())
 }
 
 pub fn try_open_position(
@@ -227,35 +360,81 @@ pub fn try_open_position(
     info: MessageInfo,
     direction: PositionDirection,
     quote_asset_amount: Uint128,
+    base_asset_amount_to_trade: Option<Uint128>,
     market_index: u64,
     limit_price: Option<Uint128>,
 ) -> Result<Response, ContractError> {
-    let user_address = info.sender.clone();
+    let state = STATE.load(deps.storage)?;
+    let mut history: Vec<HistoryExecuteMsg> = vec![];
+    let potentially_risk_increasing = open_position(
+        &mut deps,
+        &env,
+        &info.sender,
+        direction,
+        quote_asset_amount,
+        base_asset_amount_to_trade,
+        market_index,
+        limit_price,
+        &mut history,
+    )?;
+
+    if potentially_risk_increasing
+        && !controller::margin::meets_initial_margin_requirement(&mut deps, &info.sender)?
+    {
+        return Err(ContractError::InsufficientCollateral);
+    }
+
+    Ok(Response::new()
+        .add_messages(history_messages(&state.history_contract, history)?)
+        .add_attribute("method", "try_open_position"))
+}
+
+// opens or adds to a position, the caller is responsible for the initial margin check
+fn open_position(
+    deps: &mut DepsMut,
+    env: &Env,
+    user_address: &Addr,
+    direction: PositionDirection,
+    quote_asset_amount: Uint128,
+    base_asset_amount_to_trade: Option<Uint128>,
+    market_index: u64,
+    limit_price: Option<Uint128>,
+    history: &mut Vec<HistoryExecuteMsg>,
+) -> Result<bool, ContractError> {
+    let user_address = user_address.clone();
 
     let now = env.block.time.seconds();
     let state = STATE.load(deps.storage)?;
+    if state.exchange_paused {
+        return Err(ContractError::ExchangePaused);
+    }
     let oracle_guard_rails = ORACLEGUARDRAILS.load(deps.storage)?;
     let fee_structure = FEESTRUCTURE.load(deps.storage)?;
 
-    if quote_asset_amount.is_zero() {
+    if MARKETS.load(deps.storage, market_index.to_string())?.settled {
+        return Err(ContractError::MarketSettled);
+    }
+    if base_asset_amount_to_trade.is_some() && !quote_asset_amount.is_zero() {
+        return Err(ContractError::AmbiguousTradeSize);
+    }
+    if quote_asset_amount.is_zero() && base_asset_amount_to_trade.unwrap_or_default().is_zero() {
         return Err(ContractError::TradeSizeTooSmall.into());
     }
-    let f = controller::funding::settle_funding_payment(&mut deps, &user_address, now)?;
-    let mut messages: Vec<CosmosMsg> = vec![];
-    let message: CosmosMsg = CosmosMsg::Wasm(WasmMsg::Execute {
-        contract_addr: state.history_contract.clone().to_string(),
-        msg: to_binary(&HistoryExecuteMsg::RecordFundingPaymentsMultiple { vecf: f })?,
-        funds: vec![],
-    });
-    messages.push(message);
+    let f = controller::funding::settle_funding_payment(deps, &user_address, now)?;
+    history.push(HistoryExecuteMsg::RecordFundingPaymentsMultiple { vecf: f });
     let position_index = market_index.clone();
+    if let Some(c) = controller::repeg::formulaic_repeg(deps, market_index, now)? {
+        history.push(HistoryExecuteMsg::RecordCurve { c });
+    }
     let mark_price_before: Uint128;
     let oracle_mark_spread_pct_before: i128;
     let is_oracle_valid: bool;
+    let in_cool_down_before: bool;
 
     {
         let market = MARKETS.load(deps.storage, market_index.to_string())?;
         mark_price_before = market.amm.mark_price()?;
+        in_cool_down_before = market.in_cool_down(now);
         let oracle_price_data = market.amm.get_oracle_price()?;
         oracle_mark_spread_pct_before = helpers::amm::calculate_oracle_mark_spread_pct(
             &market.amm,
@@ -271,7 +450,7 @@ pub fn try_open_position(
                 Some(mark_price_before),
             )?;
             controller::amm::update_oracle_price_twap(
-                &mut deps,
+                deps,
                 market_index,
                 now,
                 normalised_oracle_price,
@@ -279,26 +458,52 @@ pub fn try_open_position(
         }
     }
 
+    let realized_pnl_before = POSITIONS
+        .may_load(deps.storage, (&user_address, position_index.to_string()))?
+        .map(|p| p.realized_pnl.i128())
+        .unwrap_or(0);
     let potentially_risk_increasing;
     let base_asset_amount;
     let mut quote_asset_amount = quote_asset_amount;
 
     {
         let (_potentially_risk_increasing, _, _base_asset_amount, _quote_asset_amount, _) =
-            controller::position::update_position_with_quote_asset_amount(
-                &mut deps,
-                quote_asset_amount,
-                direction,
-                &user_address,
-                position_index,
-                mark_price_before,
-                now,
-            )?;
+            match base_asset_amount_to_trade {
+                Some(base_asset_amount_to_trade) => {
+                    let market = MARKETS.load(deps.storage, market_index.to_string())?;
+                    if base_asset_amount_to_trade < market.amm.minimum_base_asset_trade_size {
+                        return Err(ContractError::TradeSizeTooSmall);
+                    }
+                    if !POSITIONS.has(deps.storage, (&user_address, position_index.to_string())) {
+                        controller::position::add_new_position(deps, &user_address, market_index)?;
+                    }
+                    controller::position::update_position_with_base_asset_amount(
+                        deps,
+                        base_asset_amount_to_trade,
+                        direction,
+                        &user_address,
+                        position_index,
+                        mark_price_before,
+                        now,
+                        None,
+                    )?
+                }
+                None => controller::position::update_position_with_quote_asset_amount(
+                    deps,
+                    quote_asset_amount,
+                    direction,
+                    &user_address,
+                    position_index,
+                    mark_price_before,
+                    now,
+                )?,
+            };
 
         potentially_risk_increasing = _potentially_risk_increasing;
         base_asset_amount = _base_asset_amount;
         quote_asset_amount = _quote_asset_amount;
     }
+    controller::order::cancel_trigger_orders_if_closed(deps, &user_address, position_index)?;
     let mut user = USERS.load(deps.storage, &user_address)?;
     let mark_price_after: Uint128;
     let oracle_price_after: i128;
@@ -315,19 +520,41 @@ pub fn try_open_position(
         oracle_price_after = oracle_price_data.price.i128();
     }
 
-    let meets_initial_margin_requirement =
-        controller::margin::meets_initial_margin_requirement(&mut deps, &user_address)?;
-    if !meets_initial_margin_requirement && potentially_risk_increasing {
-        return Err(ContractError::InsufficientCollateral.into());
+    let (in_cool_down, circuit_breaker_tripped) = controller::amm::update_circuit_breaker(
+        deps,
+        market_index,
+        now,
+        mark_price_before,
+        mark_price_after,
+    )?;
+
+    if potentially_risk_increasing && in_cool_down_before {
+        return Err(ContractError::MarketInCoolDown);
+    }
+
+    if potentially_risk_increasing && circuit_breaker_tripped {
+        return Err(ContractError::CircuitBreakerTripped);
     }
 
-    // todo add referrer and discount token
     let referrer = user.referrer.clone();
-    let discount_token = Uint128::zero();
+    let discount_token =
+        helpers::fees::get_discount_token_amount(deps.storage, &deps.querier, &user_address)?;
+    let trailing_volume = helpers::fees::calculate_rolling_volume(
+        &user.daily_volumes,
+        user.last_volume_update_ts,
+        now,
+    )?;
+    let fee_tiers = helpers::fees::get_fee_tiers(
+        deps.storage,
+        &MARKETS.load(deps.storage, market_index.to_string())?,
+    )?;
+    let (_, fee_tier) =
+        helpers::fees::calculate_fee_tier(&fee_structure, &fee_tiers, trailing_volume);
     let (user_fee, fee_to_market, token_discount, referrer_reward, referee_discount) =
         helpers::fees::calculate_fee_for_trade(
             quote_asset_amount,
             &fee_structure,
+            &fee_tier,
             discount_token,
             &referrer,
         )?;
@@ -339,6 +566,8 @@ pub fn try_open_position(
             .amm
             .total_fee_minus_distributions
             .checked_add(fee_to_market)?;
+        market.volume_since_last_k_update =
+            market.volume_since_last_k_update.checked_add(quote_asset_amount)?;
         MARKETS.update(
             deps.storage,
             market_index.to_string(),
@@ -352,23 +581,29 @@ pub fn try_open_position(
         user.collateral = Uint128::zero();
     }
 
+    user.daily_volumes = helpers::fees::add_to_rolling_volume(
+        &user.daily_volumes,
+        user.last_volume_update_ts,
+        now,
+        quote_asset_amount,
+    )?;
+    user.last_volume_update_ts = now;
+
     // Increment the user's total fee variables
     user.total_fee_paid = user.total_fee_paid.checked_add(user_fee)?;
     user.total_token_discount = user.total_token_discount.checked_add(token_discount)?;
     user.total_referee_discount = user.total_referee_discount.checked_add(referee_discount)?;
-
-    // Update the referrer's collateral with their reward
-    if referrer.is_some() {
-        let mut _referrer = USERS.load(deps.storage, &referrer.clone().unwrap())?;
-        _referrer.total_referral_reward = _referrer
-            .total_referral_reward
-            .checked_add(referrer_reward)?;
-        // todo what this signifies
-        // referrer.exit(ctx.program_id)?;
-        USERS.update(
-            deps.storage,
-            &referrer.unwrap().clone(),
-            |_m| -> Result<User, ContractError> { Ok(_referrer) },
+    let market_position =
+        controller::position::add_trade_fee(deps, &user_address, position_index, user_fee)?;
+    controller::trading_rewards::record_trading_fee(deps, &user_address, user_fee, now)?;
+
+    // Book the referrer's reward as a claimable balance
+    if let Some(referrer) = &referrer {
+        controller::referral::credit_referral_reward(
+            deps,
+            referrer,
+            &user_address,
+            referrer_reward,
         )?;
     }
 
@@ -386,29 +621,45 @@ pub fn try_open_position(
         return Err(ContractError::OracleMarkSpreadLimit.into());
     }
 
-    let message: CosmosMsg = CosmosMsg::Wasm(WasmMsg::Execute {
-        contract_addr: state.insurance_vault.to_string(),
-        msg: to_binary(&HistoryExecuteMsg::RecordTrade {
-            t: TradeRecord {
-                ts: now,
-                user: user_address.clone(),
-                direction,
-                base_asset_amount,
-                quote_asset_amount,
-                mark_price_before,
-                mark_price_after,
-                fee: user_fee,
-                referrer_reward,
-                referee_discount,
-                token_discount,
-                liquidation: false,
-                market_index,
-                oracle_price: Number128::new(oracle_price_after),
-            },
-        })?,
-        funds: vec![],
+    if is_oracle_valid && !is_oracle_mark_too_divergent_after {
+        controller::funding::sample_premium_index(
+            deps,
+            market_index,
+            mark_price_after,
+            oracle_price_after,
+            now,
+        )?;
+    }
+
+    history.push(HistoryExecuteMsg::RecordTrade {
+        t: TradeRecord {
+            ts: now,
+            user: user_address.clone(),
+            direction,
+            base_asset_amount,
+            quote_asset_amount,
+            mark_price_before,
+            mark_price_after,
+            fee: user_fee,
+            referrer_reward,
+            referee_discount,
+            token_discount,
+            liquidation: false,
+            market_index,
+            oracle_price: Number128::new(oracle_price_after),
+            circuit_breaker_cool_down: in_cool_down,
+            realized_pnl: Number128::new(
+                market_position
+                    .realized_pnl
+                    .i128()
+                    .checked_sub(realized_pnl_before)
+                    .ok_or(ContractError::MathError)?,
+            ),
+            entry_price: market_position.entry_price,
+            cumulative_funding_paid: market_position.cumulative_funding_paid,
+            cumulative_fees_paid: market_position.cumulative_fees_paid,
+        },
     });
-    messages.push(message);
     if limit_price.is_some()
         && !helpers::order::limit_price_satisfied(
             limit_price.unwrap(),
@@ -422,18 +673,15 @@ pub fn try_open_position(
 
     {
         let f = controller::funding::update_funding_rate(
-            &mut deps,
+            deps,
             market_index,
             now,
             state.funding_paused,
             Some(mark_price_before),
         )?;
-        let message: CosmosMsg = CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: state.history_contract.clone().to_string(),
-            msg: to_binary(&HistoryExecuteMsg::RecordFundingRate { f: f.unwrap() })?,
-            funds: vec![],
-        });
-        messages.push(message);
+        if let Some(f) = f {
+            history.push(HistoryExecuteMsg::RecordFundingRate { f });
+        }
     }
 
     USERS.update(
@@ -442,9 +690,75 @@ pub fn try_open_position(
         |_m| -> Result<User, ContractError> { Ok(user) },
     )?;
 
-    Ok(Response::new()
-        .add_messages(messages)
-        .add_attribute("method", "try_open_position"))
+    Ok(potentially_risk_increasing)
+}
+
+pub fn try_set_user_max_leverage(
+    deps: DepsMut,
+    info: MessageInfo,
+    market_index: u64,
+    max_leverage: Option<Decimal>,
+) -> Result<Response, ContractError> {
+    if let Some(max_leverage) = max_leverage {
+        if max_leverage < Decimal::one() {
+            return Err(ContractError::InvalidMaxLeverage);
+        }
+    }
+    MARKETS.load(deps.storage, market_index.to_string())?;
+
+    let user_address = info.sender;
+    let position = POSITIONS.may_load(deps.storage, (&user_address, market_index.to_string()))?;
+    let position = match position {
+        Some(mut p) => {
+            p.max_leverage = max_leverage;
+            p
+        }
+        None => Position {
+            market_index,
+            base_asset_amount: Number128::zero(),
+            quote_asset_amount: Uint128::zero(),
+            last_cumulative_funding_rate: Number128::zero(),
+            last_cumulative_repeg_rebate: Uint128::zero(),
+            last_funding_rate_ts: 0,
+            order_length: 0,
+            next_order_id: 0,
+            max_leverage,
+            entry_price: Uint128::zero(),
+            realized_pnl: Number128::zero(),
+            cumulative_funding_paid: Number128::zero(),
+            cumulative_fees_paid: Uint128::zero(),
+        },
+    };
+    POSITIONS.save(deps.storage, (&user_address, market_index.to_string()), &position)?;
+
+    Ok(Response::new().add_attribute("method", "try_set_user_max_leverage"))
+}
+
+pub fn try_set_position_triggers(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    market_index: u64,
+    stop_loss_price: Option<Uint128>,
+    take_profit_price: Option<Uint128>,
+) -> Result<Response, ContractError> {
+    let now = env.block.time.seconds();
+    let state = STATE.load(deps.storage)?;
+    if state.exchange_paused {
+        return Err(ContractError::ExchangePaused);
+    }
+
+    controller::order::set_position_triggers(
+        &mut deps,
+        &info.sender,
+        market_index,
+        stop_loss_price,
+        take_profit_price,
+        now,
+        &state.oracle,
+    )?;
+
+    Ok(Response::new().add_attribute("method", "try_set_position_triggers"))
 }
 
 pub fn try_close_position(
@@ -452,26 +766,60 @@ pub fn try_close_position(
     env: Env,
     info: MessageInfo,
     market_index: u64,
+    base_asset_amount_to_close: Option<Uint128>,
+    percentage: Option<Decimal>,
+    limit_price: Option<Uint128>,
 ) -> Result<Response, ContractError> {
-    let user_address = info.sender.clone();
+    let state = STATE.load(deps.storage)?;
+    let mut history: Vec<HistoryExecuteMsg> = vec![];
+    close_position(
+        &mut deps,
+        &env,
+        &info.sender,
+        market_index,
+        base_asset_amount_to_close,
+        percentage,
+        limit_price,
+        &mut history,
+    )?;
+
+    Ok(Response::new()
+        .add_messages(history_messages(&state.history_contract, history)?)
+        .add_attribute("method", "try_close_position"))
+}
+
+fn close_position(
+    deps: &mut DepsMut,
+    env: &Env,
+    user_address: &Addr,
+    market_index: u64,
+    base_asset_amount_to_close: Option<Uint128>,
+    percentage: Option<Decimal>,
+    limit_price: Option<Uint128>,
+    history: &mut Vec<HistoryExecuteMsg>,
+) -> Result<(), ContractError> {
+    let user_address = user_address.clone();
     let now = env.block.time.seconds();
     let state = STATE.load(deps.storage)?;
+    if state.exchange_paused {
+        return Err(ContractError::ExchangePaused);
+    }
     let oracle_guard_rails = ORACLEGUARDRAILS.load(deps.storage)?;
     let fee_structure = FEESTRUCTURE.load(deps.storage)?;
-    let f = controller::funding::settle_funding_payment(&mut deps, &user_address, now)?;
-    let mut messages: Vec<CosmosMsg> = vec![];
-    let message: CosmosMsg = CosmosMsg::Wasm(WasmMsg::Execute {
-        contract_addr: state.history_contract.clone().to_string(),
-        msg: to_binary(&HistoryExecuteMsg::RecordFundingPaymentsMultiple { vecf: f })?,
-        funds: vec![],
-    });
-    messages.push(message);
+    let f = controller::funding::settle_funding_payment(deps, &user_address, now)?;
+    history.push(HistoryExecuteMsg::RecordFundingPaymentsMultiple { vecf: f });
     let position_index = market_index.clone();
+    if let Some(c) = controller::repeg::formulaic_repeg(deps, market_index, now)? {
+        history.push(HistoryExecuteMsg::RecordCurve { c });
+    }
     let market_position = POSITIONS.load(
         deps.storage,
         (&user_address.clone(), market_index.to_string()),
     )?;
     let mut market = MARKETS.load(deps.storage, market_index.to_string())?;
+    if market.settled {
+        return Err(ContractError::MarketSettled);
+    }
     let mark_price_before = market.amm.mark_price()?;
     let oracle_price_data = market.amm.get_oracle_price()?;
     let oracle_mark_spread_pct_before = helpers::amm::calculate_oracle_mark_spread_pct(
@@ -482,27 +830,83 @@ pub fn try_close_position(
     let direction_to_close =
         helpers::position::direction_to_close_position(market_position.base_asset_amount.i128());
 
-    let (quote_asset_amount, base_asset_amount, _) = controller::position::close(
-        &mut deps,
-        &user_address,
-        market_index,
-        position_index,
-        now,
-        None,
-        Some(mark_price_before),
-    )?;
+    let position_base_asset_amount =
+        Uint128::from(market_position.base_asset_amount.i128().unsigned_abs());
+    let base_asset_amount_to_close = match (base_asset_amount_to_close, percentage) {
+        (Some(_), Some(_)) => return Err(ContractError::InvalidCloseAmount),
+        (Some(base_asset_amount_to_close), None) => base_asset_amount_to_close,
+        (None, Some(percentage)) => {
+            if percentage > Decimal::one() {
+                return Err(ContractError::InvalidCloseAmount);
+            }
+            position_base_asset_amount * percentage
+        }
+        (None, None) => position_base_asset_amount,
+    };
+    if base_asset_amount_to_close.is_zero()
+        || base_asset_amount_to_close > position_base_asset_amount
+    {
+        return Err(ContractError::InvalidCloseAmount);
+    }
+
+    let (quote_asset_amount, base_asset_amount) =
+        if base_asset_amount_to_close < position_base_asset_amount {
+            let (quote_asset_amount, _) = controller::position::reduce_with_base_asset_amount(
+                deps,
+                direction_to_close,
+                base_asset_amount_to_close,
+                &user_address,
+                position_index,
+                now,
+                None,
+                Some(mark_price_before),
+            )?;
+            (quote_asset_amount, base_asset_amount_to_close.u128() as i128)
+        } else {
+            let (quote_asset_amount, base_asset_amount, _) = controller::position::close(
+                deps,
+                &user_address,
+                market_index,
+                position_index,
+                now,
+                None,
+                Some(mark_price_before),
+            )?;
+            controller::order::cancel_trigger_orders(deps, &user_address, position_index)?;
+            (quote_asset_amount, base_asset_amount)
+        };
 
     let mut user = USERS.load(deps.storage, &user_address)?;
 
     market = MARKETS.load(deps.storage, market_index.to_string())?;
     let base_asset_amount = Uint128::from(base_asset_amount.unsigned_abs());
+    if let Some(limit_price) = limit_price {
+        if !helpers::order::limit_price_satisfied(
+            limit_price,
+            quote_asset_amount,
+            base_asset_amount,
+            direction_to_close,
+        )? {
+            return Err(ContractError::SlippageOutsideLimit);
+        }
+    }
     let referrer = user.referrer.clone();
-    let discount_token = Uint128::zero();
+    let discount_token =
+        helpers::fees::get_discount_token_amount(deps.storage, &deps.querier, &user_address)?;
+    let trailing_volume = helpers::fees::calculate_rolling_volume(
+        &user.daily_volumes,
+        user.last_volume_update_ts,
+        now,
+    )?;
+    let fee_tiers = helpers::fees::get_fee_tiers(deps.storage, &market)?;
+    let (_, fee_tier) =
+        helpers::fees::calculate_fee_tier(&fee_structure, &fee_tiers, trailing_volume);
 
     let (user_fee, fee_to_market, token_discount, referrer_reward, referee_discount) =
         helpers::fees::calculate_fee_for_trade(
             quote_asset_amount,
             &fee_structure,
+            &fee_tier,
             discount_token,
             &referrer,
         )?;
@@ -512,6 +916,8 @@ pub fn try_close_position(
         .amm
         .total_fee_minus_distributions
         .checked_add(fee_to_market)?;
+    market.volume_since_last_k_update =
+        market.volume_since_last_k_update.checked_add(quote_asset_amount)?;
 
     if user.collateral.gt(&user_fee) {
         user.collateral = user.collateral.checked_sub(user_fee)?;
@@ -519,98 +925,509 @@ pub fn try_close_position(
         user.collateral = Uint128::zero();
     }
 
+    user.daily_volumes = helpers::fees::add_to_rolling_volume(
+        &user.daily_volumes,
+        user.last_volume_update_ts,
+        now,
+        quote_asset_amount,
+    )?;
+    user.last_volume_update_ts = now;
+
     user.total_fee_paid = user.total_fee_paid.checked_add(user_fee)?;
     user.total_token_discount = user.total_token_discount.checked_add(token_discount)?;
     user.total_referee_discount = user.total_referee_discount.checked_add(referee_discount)?;
-
-    if referrer.is_some() {
-        let mut _referrer = USERS.load(deps.storage, &referrer.clone().unwrap())?;
-        _referrer.total_referral_reward = _referrer
-            .total_referral_reward
-            .checked_add(referrer_reward)?;
-        USERS.update(
-            deps.storage,
-            &referrer.unwrap().clone(),
-            |_m| -> Result<User, ContractError> { Ok(_referrer) },
+    let realized_pnl_before = market_position.realized_pnl.i128();
+    let market_position =
+        controller::position::add_trade_fee(deps, &user_address, position_index, user_fee)?;
+    controller::trading_rewards::record_trading_fee(deps, &user_address, user_fee, now)?;
+
+    if let Some(referrer) = &referrer {
+        controller::referral::credit_referral_reward(
+            deps,
+            referrer,
+            &user_address,
+            referrer_reward,
         )?;
     }
 
     let mark_price_after = market.amm.mark_price()?;
 
-    let oracle_mark_spread_pct_after = helpers::amm::calculate_oracle_mark_spread_pct(
-        &market.amm,
-        &oracle_price_data,
-        Some(mark_price_after),
+    let oracle_mark_spread_pct_after = helpers::amm::calculate_oracle_mark_spread_pct(
+        &market.amm,
+        &oracle_price_data,
+        Some(mark_price_after),
+    )?;
+
+    let oracle_price_after = oracle_price_data.price;
+
+    let is_oracle_valid =
+        helpers::amm::is_oracle_valid(&market.amm, &oracle_price_data, &oracle_guard_rails)?;
+
+    MARKETS.update(
+        deps.storage,
+        market_index.to_string(),
+        |_m| -> Result<Market, ContractError> { Ok(market.clone()) },
+    )?;
+
+    USERS.update(
+        deps.storage,
+        &user_address.clone(),
+        |_m| -> Result<User, ContractError> { Ok(user) },
+    )?;
+
+    let (in_cool_down, _) = controller::amm::update_circuit_breaker(
+        deps,
+        market_index,
+        now,
+        mark_price_before,
+        mark_price_after,
+    )?;
+
+    if is_oracle_valid {
+        let normalised_oracle_price = helpers::amm::normalise_oracle_price(
+            &market.amm,
+            &oracle_price_data,
+            Some(mark_price_before),
+        )?;
+        controller::amm::update_oracle_price_twap(
+            deps,
+            market_index,
+            now,
+            normalised_oracle_price,
+        )?;
+    }
+
+    let is_oracle_mark_too_divergent_before = helpers::amm::is_oracle_mark_too_divergent(
+        oracle_mark_spread_pct_before,
+        &oracle_guard_rails,
+    )?;
+    let is_oracle_mark_too_divergent_after = helpers::amm::is_oracle_mark_too_divergent(
+        oracle_mark_spread_pct_after,
+        &oracle_guard_rails,
+    )?;
+
+    if (is_oracle_mark_too_divergent_after && !is_oracle_mark_too_divergent_before)
+        && is_oracle_valid
+    {
+        return Err(ContractError::OracleMarkSpreadLimit.into());
+    }
+
+    if is_oracle_valid && !is_oracle_mark_too_divergent_after {
+        controller::funding::sample_premium_index(
+            deps,
+            market_index,
+            mark_price_after,
+            oracle_price_after.i128(),
+            now,
+        )?;
+    }
+
+    let t = TradeRecord {
+        ts: now,
+        user: user_address.clone(),
+        direction: direction_to_close,
+        base_asset_amount,
+        quote_asset_amount,
+        mark_price_before,
+        mark_price_after,
+        fee: user_fee,
+        referrer_reward,
+        referee_discount,
+        token_discount,
+        liquidation: false,
+        market_index,
+        oracle_price: oracle_price_after,
+        circuit_breaker_cool_down: in_cool_down,
+        realized_pnl: Number128::new(
+            market_position
+                .realized_pnl
+                .i128()
+                .checked_sub(realized_pnl_before)
+                .ok_or(ContractError::MathError)?,
+        ),
+        entry_price: market_position.entry_price,
+        cumulative_funding_paid: market_position.cumulative_funding_paid,
+        cumulative_fees_paid: market_position.cumulative_fees_paid,
+    };
+    history.push(HistoryExecuteMsg::RecordTrade { t });
+    let f = controller::funding::update_funding_rate(
+        deps,
+        market_index,
+        now,
+        state.funding_paused,
+        Some(mark_price_before),
+    )?;
+    if let Some(f) = f {
+        history.push(HistoryExecuteMsg::RecordFundingRate { f });
+    }
+
+    Ok(())
+}
+
+pub fn try_batch(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    actions: Vec<BatchAction>,
+) -> Result<Response, ContractError> {
+    let state = STATE.load(deps.storage)?;
+    let mut history: Vec<HistoryExecuteMsg> = vec![];
+    let mut messages: Vec<CosmosMsg> = vec![];
+    let mut risk_increasing = false;
+
+    for action in actions {
+        match action {
+            BatchAction::OpenPosition {
+                direction,
+                quote_asset_amount,
+                base_asset_amount,
+                market_index,
+                limit_price,
+            } => {
+                risk_increasing |= open_position(
+                    &mut deps,
+                    &env,
+                    &info.sender,
+                    direction,
+                    quote_asset_amount,
+                    base_asset_amount,
+                    market_index,
+                    limit_price,
+                    &mut history,
+                )?;
+            }
+            BatchAction::ClosePosition {
+                market_index,
+                base_asset_amount,
+                percentage,
+                limit_price,
+            } => close_position(
+                &mut deps,
+                &env,
+                &info.sender,
+                market_index,
+                base_asset_amount,
+                percentage,
+                limit_price,
+                &mut history,
+            )?,
+            BatchAction::WithdrawCollateral { amount } => {
+                withdraw_collateral(&mut deps, &env, &info.sender, amount, &mut history, &mut messages)?;
+                risk_increasing = true;
+            }
+        }
+    }
+
+    // only the final state has to be safe
+    if risk_increasing
+        && !controller::margin::meets_initial_margin_requirement(&mut deps, &info.sender)?
+    {
+        return Err(ContractError::InsufficientCollateral);
+    }
+
+    // funding payments go out in one message and every other record in one batch
+    let mut vecf: Vec<FundingPaymentRecord> = vec![];
+    let mut records: Vec<HistoryExecuteMsg> = vec![];
+    for msg in history {
+        match msg {
+            HistoryExecuteMsg::RecordFundingPaymentsMultiple { vecf: f } => vecf.extend(f),
+            msg => records.push(msg),
+        }
+    }
+    let mut history: Vec<HistoryExecuteMsg> = vec![];
+    if !vecf.is_empty() {
+        history.push(HistoryExecuteMsg::RecordFundingPaymentsMultiple { vecf });
+    }
+    if !records.is_empty() {
+        history.push(HistoryExecuteMsg::RecordBatch { records });
+    }
+
+    Ok(Response::new()
+        .add_messages(history_messages(&state.history_contract, history)?)
+        .add_messages(messages)
+        .add_attribute("method", "try_batch"))
+}
+
+//new limit order interfaces
+pub fn try_place_order(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    order: OrderParams,
+) -> Result<Response, ContractError> {
+    let now = env.block.time.seconds();
+    let user_address = info.sender.clone();
+    let state = STATE.load(deps.storage)?;
+    if state.exchange_paused {
+        return Err(ContractError::ExchangePaused);
+    }
+    let oracle = state.oracle;
+    if order.order_type == OrderType::Market {
+        return Err(ContractError::MarketOrderMustBeInPlaceAndFill);
+    }
+
+    if !POSITIONS.has(deps.storage, (&user_address, order.market_index.to_string())) {
+        controller::position::add_new_position(&mut deps, &user_address, order.market_index)?;
+    }
+    controller::order::place_order(&mut deps, &user_address, now, order, &oracle)?;
+    Ok(Response::new().add_attribute("method", "try_place_order"))
+}
+
+pub fn try_modify_order(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    market_index: u64,
+    order_id: u64,
+    price: Option<Uint128>,
+    oracle_price_offset: Option<Number128>,
+    base_asset_amount: Option<Uint128>,
+) -> Result<Response, ContractError> {
+    let now = env.block.time.seconds();
+    let state = STATE.load(deps.storage)?;
+    if state.exchange_paused {
+        return Err(ContractError::ExchangePaused);
+    }
+    if price.is_some() && oracle_price_offset.is_some() {
+        return Err(ContractError::InvalidOrder);
+    }
+    controller::order::modify_order(
+        &mut deps,
+        &info.sender,
+        market_index,
+        order_id,
+        price,
+        oracle_price_offset,
+        base_asset_amount,
+        &state.oracle,
+        now,
+    )?;
+    Ok(Response::new().add_attribute("method", "try_modify_order"))
+}
+
+pub fn try_cancel_order(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    market_index: u64,
+    order_id: u64,
+) -> Result<Response, ContractError> {
+    let now = env.block.time.seconds();
+    let state = STATE.load(deps.storage)?;
+    let oracle = state.oracle;
+    controller::order::cancel_order(
+        &mut deps,
+        &info.sender.clone(),
+        market_index,
+        order_id,
+        &oracle,
+        now,
+    )?;
+    Ok(Response::new().add_attribute("method", "try_cancel_order"))
+}
+
+//todo who is filler? is sender is filler and passing the user address?
+// pub fn try_expire_orders(
+//     mut deps: DepsMut,
+//     env: Env,
+//     info: MessageInfo,
+//     user_address: String,
+// ) -> Result<Response, ContractError> {
+//     let now = env.block.time.seconds();
+//     let user_address = addr_validate_to_lower(deps.api, &user_address.to_string())?;
+//     controller::order::expire_orders(&mut deps, &user_address, now, &info.sender.clone())?;
+//     Ok(Response::new().add_attribute("method", "try_expire_orders"))
+// }
+
+pub fn try_fill_order(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    order_id: u64,
+    user_address: String,
+    market_index: u64,
+) -> Result<Response, ContractError> {
+    let now = env.block.time.seconds();
+    let state = STATE.load(deps.storage)?;
+    if state.exchange_paused {
+        return Err(ContractError::ExchangePaused);
+    }
+    let user_address = addr_validate_to_lower(deps.api, &user_address.to_string())?;
+    let f = controller::funding::settle_funding_payment(&mut deps, &user_address, now)?;
+    let mut messages: Vec<CosmosMsg> = vec![];
+    let message: CosmosMsg = CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: state.history_contract.clone().to_string(),
+        msg: to_binary(&HistoryExecuteMsg::RecordFundingPaymentsMultiple { vecf: f })?,
+        funds: vec![],
+    });
+    messages.push(message);
+    if let Some(c) = controller::repeg::formulaic_repeg(&mut deps, market_index, now)? {
+        let message: CosmosMsg = CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: state.history_contract.clone().to_string(),
+            msg: to_binary(&HistoryExecuteMsg::RecordCurve { c })?,
+            funds: vec![],
+        });
+        messages.push(message);
+    }
+    let mark_price_before = MARKETS
+        .load(deps.storage, market_index.to_string())?
+        .amm
+        .mark_price()?;
+
+    let (base_asset_amount, trade_record) = controller::order::fill_order(
+        &mut deps,
+        &user_address,
+        &info.sender.clone(),
+        market_index,
+        order_id,
+        now,
     )?;
+    if base_asset_amount.is_zero() {
+        return Err(ContractError::CouldNotFillOrder);
+    }
 
-    let oracle_price_after = oracle_price_data.price;
+    // a filled stop loss or take profit that closes the position takes its sibling with it
+    controller::order::cancel_trigger_orders_if_closed(&mut deps, &user_address, market_index)?;
 
-    let is_oracle_valid =
-        helpers::amm::is_oracle_valid(&market.amm, &oracle_price_data, &oracle_guard_rails)?;
+    if let Some(t) = trade_record {
+        let message: CosmosMsg = CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: state.history_contract.clone().to_string(),
+            msg: to_binary(&HistoryExecuteMsg::RecordTrade { t })?,
+            funds: vec![],
+        });
+        messages.push(message);
+    }
 
-    MARKETS.update(
-        deps.storage,
-        market_index.to_string(),
-        |_m| -> Result<Market, ContractError> { Ok(market.clone()) },
+    let f = controller::funding::update_funding_rate(
+        &mut deps,
+        market_index,
+        now,
+        state.funding_paused,
+        Some(mark_price_before),
     )?;
+    if let Some(f) = f {
+        let message: CosmosMsg = CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: state.history_contract.clone().to_string(),
+            msg: to_binary(&HistoryExecuteMsg::RecordFundingRate { f })?,
+            funds: vec![],
+        });
+        messages.push(message);
+    }
 
-    USERS.update(
-        deps.storage,
-        &user_address.clone(),
-        |_m| -> Result<User, ContractError> { Ok(user) },
-    )?;
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attribute("method", "try_fill_order"))
+}
 
-    if is_oracle_valid {
-        let normalised_oracle_price = helpers::amm::normalise_oracle_price(
-            &market.amm,
-            &oracle_price_data,
-            Some(mark_price_before),
-        )?;
-        controller::amm::update_oracle_price_twap(
-            &mut deps,
-            market_index,
-            now,
-            normalised_oracle_price,
-        )?;
+pub fn try_place_twap_order(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    market_index: u64,
+    direction: PositionDirection,
+    base_asset_amount: Uint128,
+    slices: u64,
+    interval: u64,
+    limit_price: Option<Uint128>,
+) -> Result<Response, ContractError> {
+    let now = env.block.time.seconds();
+    let state = STATE.load(deps.storage)?;
+    if state.exchange_paused {
+        return Err(ContractError::ExchangePaused);
     }
 
-    let is_oracle_mark_too_divergent_before = helpers::amm::is_oracle_mark_too_divergent(
-        oracle_mark_spread_pct_before,
-        &oracle_guard_rails,
-    )?;
-    let is_oracle_mark_too_divergent_after = helpers::amm::is_oracle_mark_too_divergent(
-        oracle_mark_spread_pct_after,
-        &oracle_guard_rails,
+    let twap_order_id = controller::order::place_twap_order(
+        &mut deps,
+        &info.sender,
+        market_index,
+        direction,
+        base_asset_amount,
+        slices,
+        interval,
+        limit_price,
+        now,
     )?;
 
-    if (is_oracle_mark_too_divergent_after && !is_oracle_mark_too_divergent_before)
-        && is_oracle_valid
-    {
-        return Err(ContractError::OracleMarkSpreadLimit.into());
+    Ok(Response::new()
+        .add_attribute("method", "try_place_twap_order")
+        .add_attribute("twap_order_id", twap_order_id.to_string()))
+}
+
+pub fn try_cancel_twap_order(
+    mut deps: DepsMut,
+    info: MessageInfo,
+    twap_order_id: u64,
+) -> Result<Response, ContractError> {
+    controller::order::cancel_twap_order(&mut deps, &info.sender, twap_order_id)?;
+    Ok(Response::new().add_attribute("method", "try_cancel_twap_order"))
+}
+
+pub fn try_execute_twap_slice(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    user_address: String,
+    twap_order_id: u64,
+) -> Result<Response, ContractError> {
+    let now = env.block.time.seconds();
+    let state = STATE.load(deps.storage)?;
+    if state.exchange_paused {
+        return Err(ContractError::ExchangePaused);
     }
+    let user_address = addr_validate_to_lower(deps.api, &user_address)?;
+    let f = controller::funding::settle_funding_payment(&mut deps, &user_address, now)?;
     let mut messages: Vec<CosmosMsg> = vec![];
-    let t = TradeRecord {
-        ts: now,
-        user: user_address.clone(),
-        direction: direction_to_close,
-        base_asset_amount,
-        quote_asset_amount,
-        mark_price_before,
-        mark_price_after,
-        fee: user_fee,
-        referrer_reward,
-        referee_discount,
-        token_discount,
-        liquidation: false,
-        market_index,
-        oracle_price: oracle_price_after,
-    };
     let message: CosmosMsg = CosmosMsg::Wasm(WasmMsg::Execute {
         contract_addr: state.history_contract.clone().to_string(),
-        msg: to_binary(&HistoryExecuteMsg::RecordTrade { t })?,
+        msg: to_binary(&HistoryExecuteMsg::RecordFundingPaymentsMultiple { vecf: f })?,
         funds: vec![],
     });
     messages.push(message);
+
+    let twap_order = TWAP_ORDERS
+        .may_load(deps.storage, (&user_address, twap_order_id.into()))?
+        .ok_or(ContractError::OrderDoesNotExist)?;
+    let market_index = twap_order.market_index;
+    if let Some(c) = controller::repeg::formulaic_repeg(&mut deps, market_index, now)? {
+        let message: CosmosMsg = CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: state.history_contract.clone().to_string(),
+            msg: to_binary(&HistoryExecuteMsg::RecordCurve { c })?,
+            funds: vec![],
+        });
+        messages.push(message);
+    }
+    let mark_price_before = MARKETS
+        .load(deps.storage, market_index.to_string())?
+        .amm
+        .mark_price()?;
+
+    let (base_asset_amount, trade_record) = controller::order::execute_twap_slice(
+        &mut deps,
+        &user_address,
+        &info.sender,
+        twap_order_id,
+        now,
+        &state.oracle,
+    )?;
+    // nothing traded, the slice broke the limit price and the schedule moved past it
+    if base_asset_amount.is_zero() {
+        return Ok(Response::new()
+            .add_messages(messages)
+            .add_attribute("method", "try_execute_twap_slice")
+            .add_attribute("slice_skipped", "true"));
+    }
+
+    controller::order::cancel_trigger_orders_if_closed(&mut deps, &user_address, market_index)?;
+
+    if let Some(t) = trade_record {
+        let message: CosmosMsg = CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: state.history_contract.clone().to_string(),
+            msg: to_binary(&HistoryExecuteMsg::RecordTrade { t })?,
+            funds: vec![],
+        });
+        messages.push(message);
+    }
+
     let f = controller::funding::update_funding_rate(
         &mut deps,
         market_index,
@@ -618,95 +1435,20 @@ pub fn try_close_position(
         state.funding_paused,
         Some(mark_price_before),
     )?;
-    let message: CosmosMsg = CosmosMsg::Wasm(WasmMsg::Execute {
-        contract_addr: state.history_contract.clone().to_string(),
-        msg: to_binary(&HistoryExecuteMsg::RecordFundingRate { f: f.unwrap() })?,
-        funds: vec![],
-    });
-    messages.push(message);
+    if let Some(f) = f {
+        let message: CosmosMsg = CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: state.history_contract.clone().to_string(),
+            msg: to_binary(&HistoryExecuteMsg::RecordFundingRate { f })?,
+            funds: vec![],
+        });
+        messages.push(message);
+    }
 
     Ok(Response::new()
         .add_messages(messages)
-        .add_attribute("method", "try_close_position"))
+        .add_attribute("method", "try_execute_twap_slice"))
 }
 
-//new limit order interfaces
-// pub fn try_place_order(
-//     mut deps: DepsMut,
-//     env: Env,
-//     info: MessageInfo,
-//     order: OrderParams,
-// ) -> Result<Response, ContractError> {
-//     let now = env.block.time.seconds();
-//     let user_address = info.sender.clone();
-//     let state = STATE.load(deps.storage)?;
-//     let oracle = state.oracle;
-//     if order.order_type == OrderType::Market {
-//         return Err(ContractError::MarketOrderMustBeInPlaceAndFill.into());
-//     }
-
-//     controller::order::place_order(&mut deps, &user_address, now, order, &oracle)?;
-//     Ok(Response::new().add_attribute("method", "try_place_order"))
-// }
-
-// pub fn try_cancel_order(
-//     mut deps: DepsMut,
-//     env: Env,
-//     info: MessageInfo,
-//     market_index: u64,
-//     order_id: u64,
-// ) -> Result<Response, ContractError> {
-//     let now = env.block.time.seconds();
-//     let state = STATE.load(deps.storage)?;
-//     let oracle = state.oracle;
-//     controller::order::cancel_order(
-//         &mut deps,
-//         &info.sender.clone(),
-//         market_index,
-//         order_id,
-//         &oracle,
-//         now,
-//     )?;
-//     Ok(Response::new().add_attribute("method", "try_cancel_order"))
-// }
-
-//todo who is filler? is sender is filler and passing the user address?
-// pub fn try_expire_orders(
-//     mut deps: DepsMut,
-//     env: Env,
-//     info: MessageInfo,
-//     user_address: String,
-// ) -> Result<Response, ContractError> {
-//     let now = env.block.time.seconds();
-//     let user_address = addr_validate_to_lower(deps.api, &user_address.to_string())?;
-//     controller::order::expire_orders(&mut deps, &user_address, now, &info.sender.clone())?;
-//     Ok(Response::new().add_attribute("method", "try_expire_orders"))
-// }
-
-// pub fn try_fill_order(
-//     mut deps: DepsMut,
-//     env: Env,
-//     info: MessageInfo,
-//     order_id: u64,
-//     user_address: String,
-//     market_index: u64,
-// ) -> Result<Response, ContractError> {
-//     let now = env.block.time.seconds();
-//     let user_address = addr_validate_to_lower(deps.api, &user_address.to_string())?;
-//     let base_asset_amount = controller::order::fill_order(
-//         &mut deps,
-//         &user_address,
-//         &info.sender.clone(),
-//         market_index,
-//         order_id,
-//         now,
-//     )?;
-//     if base_asset_amount.is_zero() {
-//         return Err(ContractError::CouldNotFillOrder);
-//     }
-//     Ok(Response::new().add_attribute("method", "try_fill_order"))
-// }
-
 //todo later
 
 pub fn try_liquidate(
@@ -717,6 +1459,9 @@ pub fn try_liquidate(
     market_index: u64,
 ) -> Result<Response, ContractError> {
     let state = STATE.load(deps.storage)?;
+    if state.exchange_paused {
+        return Err(ContractError::ExchangePaused);
+    }
     let user_address = addr_validate_to_lower(deps.api, &user)?;
     let now = env.block.time.seconds();
     let mut messages: Vec<CosmosMsg> = vec![];
@@ -889,6 +1634,7 @@ pub fn try_liquidate(
                     None,
                     Some(mark_price_before),
                 )?;
+                controller::order::cancel_trigger_orders(&mut deps, &user_address, market_index)?;
 
                 (quote_asset_amount, base_asset_amount)
             };
@@ -896,6 +1642,8 @@ pub fn try_liquidate(
             let base_asset_amount = Uint128::from(base_asset_amount.unsigned_abs());
             base_asset_value_closed = base_asset_value_closed.checked_add(quote_asset_amount)?;
             let mark_price_after = market.amm.mark_price()?;
+            let position_after =
+                POSITIONS.load(deps.storage, (&user_address, market_index.to_string()))?;
             let message_h = CosmosMsg::Wasm(WasmMsg::Execute {
                 contract_addr: state.history_contract.clone().to_string(),
                 msg: to_binary(&HistoryExecuteMsg::RecordTrade {
@@ -914,6 +1662,17 @@ pub fn try_liquidate(
                         liquidation: true,
                         market_index,
                         oracle_price: market_status.oracle_status.price_data.price,
+                        circuit_breaker_cool_down: market.in_cool_down(now),
+                        realized_pnl: Number128::new(
+                            position_after
+                                .realized_pnl
+                                .i128()
+                                .checked_sub(market_position.realized_pnl.i128())
+                                .ok_or(ContractError::MathError)?,
+                        ),
+                        entry_price: position_after.entry_price,
+                        cumulative_funding_paid: position_after.cumulative_funding_paid,
+                        cumulative_fees_paid: position_after.cumulative_fees_paid,
                     },
                 })?,
                 funds: vec![],
@@ -1082,6 +1841,8 @@ pub fn try_liquidate(
             .unsigned_abs();
 
             let mark_price_after = market.amm.mark_price()?;
+            let position_after =
+                POSITIONS.load(deps.storage, (&user_address, market_index.to_string()))?;
             let message_h = CosmosMsg::Wasm(WasmMsg::Execute {
                 contract_addr: state.history_contract.clone().to_string(),
                 msg: to_binary(&HistoryExecuteMsg::RecordTrade {
@@ -1100,6 +1861,17 @@ pub fn try_liquidate(
                         liquidation: true,
                         market_index,
                         oracle_price: market_status.oracle_status.price_data.price,
+                        circuit_breaker_cool_down: market.in_cool_down(now),
+                        realized_pnl: Number128::new(
+                            position_after
+                                .realized_pnl
+                                .i128()
+                                .checked_sub(market_position.realized_pnl.i128())
+                                .ok_or(ContractError::MathError)?,
+                        ),
+                        entry_price: position_after.entry_price,
+                        cumulative_funding_paid: position_after.cumulative_funding_paid,
+                        cumulative_fees_paid: position_after.cumulative_fees_paid,
                     },
                 })?,
                 funds: vec![],
@@ -1179,7 +1951,7 @@ pub fn try_liquidate(
             contract_addr: state.collateral_vault.to_string(),
             msg: to_binary(&VaultInterface::Withdraw {
                 to_address: state.insurance_vault.clone(),
-                amount: fee_to_insurance_fund.u128(),
+                amount: fee_to_insurance_fund,
             })?,
             funds: vec![],
         });
@@ -1221,6 +1993,7 @@ pub fn try_settle_funding_payment(
     let now = env.block.time.seconds();
     let user_address = info.sender;
 
+    // settling one's own funding earns no keeper reward
     let f = controller::funding::settle_funding_payment(&mut deps, &user_address, now)?;
     let state = STATE.load(deps.storage)?;
     let mut messages: Vec<CosmosMsg> = vec![];
@@ -1235,3 +2008,147 @@ pub fn try_settle_funding_payment(
         .add_messages(messages)
         .add_attribute("method", "try_settle_funding_payment"))
 }
+
+pub fn try_settle_position(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    market_index: u64,
+) -> Result<Response, ContractError> {
+    let now = env.block.time.seconds();
+    let state = STATE.load(deps.storage)?;
+    if state.exchange_paused {
+        return Err(ContractError::ExchangePaused);
+    }
+    let user_address = info.sender;
+    let mut history: Vec<HistoryExecuteMsg> = vec![];
+
+    let f = controller::funding::settle_funding_payment(&mut deps, &user_address, now)?;
+    history.push(HistoryExecuteMsg::RecordFundingPaymentsMultiple { vecf: f });
+
+    let (quote_asset_amount, base_asset_amount, pnl) =
+        controller::position::close_at_settlement_price(&mut deps, &user_address, market_index)?;
+    controller::order::cancel_trigger_orders(&mut deps, &user_address, market_index)?;
+
+    let market = MARKETS.load(deps.storage, market_index.to_string())?;
+    let market_position = POSITIONS.load(deps.storage, (&user_address, market_index.to_string()))?;
+    let t = TradeRecord {
+        ts: now,
+        user: user_address.clone(),
+        direction: helpers::position::direction_to_close_position(base_asset_amount),
+        base_asset_amount: Uint128::from(base_asset_amount.unsigned_abs()),
+        quote_asset_amount,
+        mark_price_before: market.settlement_price,
+        mark_price_after: market.settlement_price,
+        fee: Uint128::zero(),
+        referrer_reward: Uint128::zero(),
+        referee_discount: Uint128::zero(),
+        token_discount: Uint128::zero(),
+        liquidation: false,
+        market_index,
+        oracle_price: Number128::new(market.settlement_price.u128() as i128),
+        circuit_breaker_cool_down: false,
+        realized_pnl: Number128::new(pnl),
+        entry_price: market_position.entry_price,
+        cumulative_funding_paid: market_position.cumulative_funding_paid,
+        cumulative_fees_paid: market_position.cumulative_fees_paid,
+    };
+    history.push(HistoryExecuteMsg::RecordTrade { t });
+
+    Ok(Response::new()
+        .add_messages(history_messages(&state.history_contract, history)?)
+        .add_attribute("method", "try_settle_position")
+        .add_attribute("settlement_price", market.settlement_price.to_string())
+        .add_attribute("pnl", pnl.to_string()))
+}
+
+pub fn try_settle_funding_payment_for(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    users: Vec<String>,
+) -> Result<Response, ContractError> {
+    let now = env.block.time.seconds();
+    let state = STATE.load(deps.storage)?;
+    let keeper_rewards = KEEPER_REWARDS.may_load(deps.storage)?.unwrap_or_default();
+
+    let mut records = vec![];
+    let mut keeper_reward = Uint128::zero();
+    for user in users {
+        let user_address = addr_validate_to_lower(deps.api, &user)?;
+        let f = controller::funding::settle_funding_payment(&mut deps, &user_address, now)?;
+        // the keeper is paid per other user whose funding was actually pending
+        if !f.is_empty() && user_address != info.sender {
+            let reward = controller::keeper::pay_keeper_reward(
+                &mut deps,
+                &info.sender,
+                keeper_rewards.funding_settlement,
+            )?;
+            keeper_reward = keeper_reward.checked_add(reward)?;
+        }
+        records.extend(f);
+    }
+
+    let message: CosmosMsg = CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: state.history_contract.to_string(),
+        msg: to_binary(&HistoryExecuteMsg::RecordFundingPaymentsMultiple { vecf: records })?,
+        funds: vec![],
+    });
+
+    Ok(Response::new()
+        .add_message(message)
+        .add_attribute("method", "try_settle_funding_payment_for")
+        .add_attribute("keeper_reward", keeper_reward.to_string()))
+}
+
+pub fn try_settle_pnl(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    market_index: u64,
+) -> Result<Response, ContractError> {
+    let now = env.block.time.seconds();
+    let state = STATE.load(deps.storage)?;
+    if state.exchange_paused {
+        return Err(ContractError::ExchangePaused);
+    }
+    let user_address = info.sender;
+
+    let f = controller::funding::settle_funding_payment(&mut deps, &user_address, now)?;
+    // profits are never paid beyond what the collateral vault actually holds
+    let vault_balance = query_balance(&deps.querier, state.collateral_vault.clone())?;
+    let s = controller::position::settle_pnl(
+        &mut deps,
+        &user_address,
+        market_index,
+        Uint128::from(vault_balance),
+        now,
+    )?;
+    let pnl = s.pnl.i128();
+
+    let history = vec![
+        HistoryExecuteMsg::RecordFundingPaymentsMultiple { vecf: f },
+        HistoryExecuteMsg::RecordSettlePnl { s },
+    ];
+
+    Ok(Response::new()
+        .add_messages(history_messages(&state.history_contract, history)?)
+        .add_attribute("method", "try_settle_pnl")
+        .add_attribute("pnl_settled", pnl.to_string()))
+}
+
+// wraps every history record into its own message to the history contract
+fn history_messages(
+    history_contract: &Addr,
+    history: Vec<HistoryExecuteMsg>,
+) -> Result<Vec<CosmosMsg>, ContractError> {
+    let mut messages: Vec<CosmosMsg> = vec![];
+    for msg in history {
+        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: history_contract.to_string(),
+            msg: to_binary(&msg)?,
+            funds: vec![],
+        }));
+    }
+    Ok(messages)
+}
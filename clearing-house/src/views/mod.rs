@@ -1,3 +1,4 @@
 pub mod execute_admin;
+pub mod execute_sudo;
 pub mod execute_user;
 pub mod query;
\ No newline at end of file
@@ -1,24 +1,38 @@
-use crate::helpers::amm::use_oracle_price_for_margin_calculation;
+use crate::helpers::amm::{calculate_bid_ask_price, use_oracle_price_for_margin_calculation};
 use crate::helpers::position::{calculate_updated_collateral, calculate_slippage};
+use std::cmp::max;
+
+use crate::helpers::fees::{calculate_fee_tier, calculate_rolling_volume, get_fee_tiers};
+use crate::helpers::funding::{
+    calculate_funding_payment, calculate_funding_rate, calculate_next_funding_update_wait,
+};
 use crate::states::constants::{
-    MARGIN_PRECISION,
+    AMM_TO_QUOTE_PRECISION_RATIO_I128, DEFAULT_LIMIT, FUNDING_PAYMENT_PRECISION, MARGIN_PRECISION,
+    MARK_PRICE_PRECISION, MAX_LIMIT, ONE_YEAR,
 };
+use crate::controller::margin::calculate_size_adjusted_margin_ratio;
+use crate::controller::trading_rewards::add_reward;
 use crate::helpers::oracle::get_oracle_status;
 use crate::helpers::position::{
     calculate_base_asset_value_and_pnl, calculate_base_asset_value_and_pnl_with_oracle_price,
 };
 use crate::ContractError;
 use crate::states::market::{LiquidationStatus, LiquidationType, MarketStatus, MARKETS};
-use crate::states::state::{STATE, ORACLEGUARDRAILS, ORDERSTATE, FEESTRUCTURE};
+use crate::states::state::{STATE, ORACLEGUARDRAILS, ORDERSTATE, FEESTRUCTURE, KEEPER_REWARDS, KEEPER_REWARD_POOL, PENDING_ADMIN, PENDING_CHANGES, ROLES, GOVERNANCE_PAUSE, STAKING_CONTRACT};
+use crate::helpers::order::get_valid_oracle_price;
+use crate::states::order::{get_limit_price, get_position_orders, TWAP_ORDERS};
+use crate::states::referral::{REFEREES, REFERRAL_CODES, REFERRERS};
+use crate::states::trading_rewards::{TRADING_REWARDS, TRADING_REWARD_EPOCHS, TRADING_REWARD_SCORES};
 use crate::states::user::{POSITIONS, USERS};
 
-use crate::package::helper::addr_validate_to_lower;
+use crate::package::helper::{addr_validate_to_lower, parse_u64_key};
 
 use crate::package::number::Number128;
 use crate::package::response::*;
 
-use crate::package::types::{OracleGuardRails};
-use cosmwasm_std::{Addr, Deps,  Uint128};
+use crate::package::types::{OracleGuardRails, Role};
+use cosmwasm_std::{Addr, Decimal, Deps, Env, Order, Uint128};
+use cw_storage_plus::{Bound, PrimaryKey, U64Key};
 
 pub fn get_user(deps: Deps, user_address: String) -> Result<UserResponse, ContractError> {
     let user = USERS.load(
@@ -57,15 +71,22 @@ pub fn get_user_position(
         quote_asset_amount: position.quote_asset_amount,
         last_cumulative_funding_rate: position.last_cumulative_funding_rate,
         last_cumulative_repeg_rebate: position.last_cumulative_repeg_rebate,
-        last_funding_rate_ts: position.last_funding_rate_ts
+        last_funding_rate_ts: position.last_funding_rate_ts,
+        max_leverage: position.max_leverage,
+        entry_price: position.entry_price,
+        realized_pnl: position.realized_pnl,
+        cumulative_funding_paid: position.cumulative_funding_paid,
+        cumulative_fees_paid: position.cumulative_fees_paid,
     };
     Ok(upr)
 }
 
 pub fn get_admin(deps: Deps) -> Result<AdminResponse, ContractError> {
     let state =STATE.load(deps.storage)?;
+    let pending_admin = PENDING_ADMIN.may_load(deps.storage)?;
     let admin = AdminResponse {
-        admin: state.admin.to_string()
+        admin: state.admin.to_string(),
+        pending_admin: pending_admin.map(|a| a.to_string()),
     };
     Ok(admin)
 }
@@ -74,6 +95,10 @@ pub fn is_exchange_paused(deps: Deps) -> Result<IsExchangePausedResponse, Contra
     let state = STATE.load(deps.storage)?;
     let ex_paused = IsExchangePausedResponse {
         exchange_paused: state.exchange_paused,
+        paused_by_governance: GOVERNANCE_PAUSE
+            .may_load(deps.storage)?
+            .unwrap_or_default()
+            .exchange_paused,
     };
     Ok(ex_paused)
 }
@@ -82,6 +107,10 @@ pub fn is_funding_paused(deps: Deps) -> Result<IsFundingPausedResponse, Contract
     let state = STATE.load(deps.storage)?;
     let funding_paused = IsFundingPausedResponse {
         funding_paused: state.funding_paused,
+        paused_by_governance: GOVERNANCE_PAUSE
+            .may_load(deps.storage)?
+            .unwrap_or_default()
+            .funding_paused,
     };
     Ok(funding_paused)
 }
@@ -229,9 +258,12 @@ pub fn get_fee_structure(deps: Deps) -> Result<FeeStructureResponse, ContractErr
 
 pub fn get_market_info(deps: Deps, market_index: u64) -> Result<MarketInfoResponse, ContractError> {
     let market = MARKETS.load(deps.storage, market_index.to_string())?;
+    let (bid_price, ask_price) = calculate_bid_ask_price(&market)?;
     let market_info = MarketInfoResponse {
         market_name: market.market_name,
         initialized: market.initialized,
+        settled: market.settled,
+        settlement_price: market.settlement_price,
         base_asset_amount_long: market.base_asset_amount_long,
         base_asset_amount_short: market.base_asset_amount_short,
         base_asset_amount: market.base_asset_amount,
@@ -259,11 +291,281 @@ pub fn get_market_info(deps: Deps, market_index: u64) -> Result<MarketInfoRespon
         last_oracle_price_twap_ts: market.amm.last_oracle_price_twap_ts,
         last_oracle_price: market.amm.last_oracle_price,
         minimum_base_asset_trade_size: market.amm.minimum_base_asset_trade_size,
-        minimum_quote_asset_trade_size: market.amm.minimum_quote_asset_trade_size
+        minimum_quote_asset_trade_size: market.amm.minimum_quote_asset_trade_size,
+        k_adjustment: market.k_adjustment,
+        volume_since_last_k_update: market.volume_since_last_k_update,
+        last_k_update_ts: market.last_k_update_ts,
+        funding_params: market.funding_params,
+        premium_index_sum: market.premium_index_sum,
+        premium_index_duration: market.premium_index_duration,
+        last_premium: market.last_premium,
+        last_premium_ts: market.last_premium_ts,
+        pnl_pool: market.pnl_pool,
+        fee_tiers: market.fee_tiers,
+        base_spread: market.amm.base_spread,
+        max_spread: market.amm.max_spread,
+        bid_price,
+        ask_price,
     };
     Ok(market_info)
 }
 
+pub fn get_roles(deps: Deps) -> Result<RolesResponse, ContractError> {
+    let mut roles: Vec<RoleMembersResponse> = vec![];
+    for role in Role::all() {
+        let members = ROLES
+            .may_load(deps.storage, role.key())?
+            .unwrap_or_default()
+            .iter()
+            .map(|m| m.to_string())
+            .collect();
+        roles.push(RoleMembersResponse { role, members });
+    }
+    Ok(RolesResponse { roles })
+}
+
+pub fn get_pending_changes(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> Result<Vec<PendingChangeResponse>, ContractError> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after
+        .map(|start| U64Key::from(start).joined_key())
+        .map(Bound::Exclusive);
+    let pending_changes = PENDING_CHANGES
+        .range(deps.storage, start, None, Order::Ascending)
+        .filter_map(|pending| {
+            pending.ok().and_then(|(k, p)| {
+                let change_id = parse_u64_key(&k)?;
+                Some(PendingChangeResponse {
+                    change_id,
+                    change: p.change,
+                    proposer: p.proposer.to_string(),
+                    queued_ts: p.queued_ts,
+                    eta: p.eta,
+                })
+            })
+        })
+        .take(limit)
+        .collect();
+    Ok(pending_changes)
+}
+
+pub fn get_open_orders(
+    deps: Deps,
+    env: Env,
+    user_address: String,
+    market_index: u64,
+) -> Result<Vec<OrderResponse>, ContractError> {
+    let user_address = addr_validate_to_lower(deps.api, &user_address)?;
+    let state = STATE.load(deps.storage)?;
+    let oracle_guard_rails = ORACLEGUARDRAILS.load(deps.storage)?;
+    let market = MARKETS.load(deps.storage, market_index.to_string())?;
+    let mut orders: Vec<OrderResponse> = vec![];
+    for (order_id, order) in get_position_orders(deps.storage, &user_address, market_index)? {
+        let effective_price = get_valid_oracle_price(
+            Some(&state.oracle),
+            &market,
+            &order,
+            &oracle_guard_rails,
+            env.block.time.seconds(),
+        )
+        .and_then(|valid_oracle_price| get_limit_price(&order, valid_oracle_price))
+        .ok()
+        .filter(|price| !price.is_zero());
+        orders.push(OrderResponse {
+            order_id,
+            order,
+            effective_price,
+        });
+    }
+    Ok(orders)
+}
+
+pub fn get_twap_orders(
+    deps: Deps,
+    user_address: String,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> Result<Vec<TwapOrderResponse>, ContractError> {
+    let user_address = addr_validate_to_lower(deps.api, &user_address)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after
+        .map(|start| U64Key::from(start).joined_key())
+        .map(Bound::Exclusive);
+    let twap_orders = TWAP_ORDERS
+        .prefix(&user_address)
+        .range(deps.storage, start, None, Order::Ascending)
+        .filter_map(|twap| {
+            twap.ok().and_then(|(k, t)| {
+                let twap_order_id = parse_u64_key(&k)?;
+                let next_slice_base_asset_amount = t.next_slice_base_asset_amount().ok()?;
+                Some(TwapOrderResponse {
+                    twap_order_id,
+                    market_index: t.market_index,
+                    direction: t.direction,
+                    base_asset_amount: t.base_asset_amount,
+                    base_asset_amount_filled: t.base_asset_amount_filled,
+                    quote_asset_amount_filled: t.quote_asset_amount_filled,
+                    slices: t.slices,
+                    slices_filled: t.slices_filled,
+                    slices_skipped: t.slices_skipped,
+                    interval: t.interval,
+                    limit_price: t.limit_price,
+                    next_slice_ts: t.next_slice_ts,
+                    next_slice_base_asset_amount,
+                })
+            })
+        })
+        .take(limit)
+        .collect();
+    Ok(twap_orders)
+}
+
+pub fn get_trading_reward_epochs(
+    deps: Deps,
+    env: Env,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> Result<Vec<TradingRewardEpochResponse>, ContractError> {
+    let now = env.block.time.seconds();
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after
+        .map(|start| U64Key::from(start).joined_key())
+        .map(Bound::Exclusive);
+    let epochs = TRADING_REWARD_EPOCHS
+        .range(deps.storage, start, None, Order::Ascending)
+        .filter_map(|epoch| {
+            epoch.ok().and_then(|(k, epoch)| {
+                let epoch_id = parse_u64_key(&k)?;
+                Some(TradingRewardEpochResponse {
+                    epoch_id,
+                    reward_token: epoch.reward_token.to_string(),
+                    start_ts: epoch.start_ts,
+                    end_ts: epoch.end_ts,
+                    reward_amount: epoch.reward_amount,
+                    total_score: epoch.total_score,
+                    total_claimed: epoch.total_claimed,
+                    active: epoch.is_active(now),
+                    ended: now >= epoch.end_ts,
+                })
+            })
+        })
+        .take(limit)
+        .collect();
+    Ok(epochs)
+}
+
+pub fn get_user_trading_rewards(
+    deps: Deps,
+    env: Env,
+    user_address: String,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> Result<UserTradingRewardsResponse, ContractError> {
+    let now = env.block.time.seconds();
+    let user_address = addr_validate_to_lower(deps.api, &user_address)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after
+        .map(|start| U64Key::from(start).joined_key())
+        .map(Bound::Exclusive);
+    let reward_token = TRADING_REWARDS
+        .may_load(deps.storage)?
+        .unwrap_or_default()
+        .reward_token;
+
+    let mut accrued: Vec<(Addr, Uint128)> = vec![];
+    let mut claimable: Vec<(Addr, Uint128)> = vec![];
+    let mut epochs = vec![];
+    for score in TRADING_REWARD_SCORES
+        .prefix(&user_address)
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+    {
+        let (k, score) = score?;
+        let epoch_id = parse_u64_key(&k).ok_or(ContractError::MathError)?;
+        let epoch = TRADING_REWARD_EPOCHS.load(deps.storage, epoch_id.into())?;
+        let reward = epoch.reward_for_score(score.score);
+        let ended = now >= epoch.end_ts;
+        if !ended {
+            add_reward(&mut accrued, &epoch.reward_token, reward)?;
+        } else if !score.claimed {
+            add_reward(&mut claimable, &epoch.reward_token, reward)?;
+        }
+        epochs.push(UserTradingRewardEpochResponse {
+            epoch_id,
+            reward_token: epoch.reward_token.to_string(),
+            score: score.score,
+            reward,
+            claimed: score.claimed,
+            ended,
+        });
+    }
+    let to_response = |rewards: Vec<(Addr, Uint128)>| {
+        rewards
+            .into_iter()
+            .map(|(reward_token, amount)| TradingRewardAmountResponse {
+                reward_token: reward_token.to_string(),
+                amount,
+            })
+            .collect()
+    };
+
+    Ok(UserTradingRewardsResponse {
+        reward_token: reward_token.map(|addr| addr.to_string()),
+        accrued: to_response(accrued),
+        claimable: to_response(claimable),
+        epochs,
+    })
+}
+
+pub fn get_referral_code(deps: Deps, code: String) -> Result<ReferralCodeResponse, ContractError> {
+    let referrer = REFERRAL_CODES
+        .may_load(deps.storage, code.clone())?
+        .ok_or(ContractError::ReferralCodeNotFound)?;
+    Ok(ReferralCodeResponse {
+        code,
+        referrer: referrer.to_string(),
+    })
+}
+
+pub fn get_referrer(
+    deps: Deps,
+    referrer_address: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> Result<ReferrerResponse, ContractError> {
+    let referrer_address = addr_validate_to_lower(deps.api, &referrer_address)?;
+    let referrer = REFERRERS
+        .may_load(deps.storage, &referrer_address)?
+        .unwrap_or_default();
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after
+        .map(|start| start.joined_key())
+        .map(Bound::Exclusive);
+    let referees = REFEREES
+        .prefix(&referrer_address)
+        .range(deps.storage, start, None, Order::Ascending)
+        .filter_map(|referee| {
+            referee.ok().and_then(|(k, reward)| {
+                Some(RefereeResponse {
+                    referee: String::from_utf8(k).ok()?,
+                    reward,
+                })
+            })
+        })
+        .take(limit)
+        .collect();
+    Ok(ReferrerResponse {
+        code: referrer.code,
+        referee_count: referrer.referee_count,
+        total_reward: referrer.total_reward,
+        unclaimed_reward: referrer.unclaimed_reward,
+        referees,
+    })
+}
+
 // get list in response
 // pub fn get_active_positions(
 //     deps: Deps,
@@ -359,7 +661,34 @@ pub fn calculate_liquidation_status(
                 }
 
                 let market = MARKETS.load(deps.storage, n.to_string())?;
+                if market.settled {
+                    let (settled_base_asset_value, settled_pnl) =
+                        calculate_base_asset_value_and_pnl_with_oracle_price(
+                            &m,
+                            market.settlement_price.u128() as i128,
+                        )?;
+                    base_asset_value = base_asset_value.checked_add(settled_base_asset_value)?;
+                    unrealized_pnl = unrealized_pnl
+                        .checked_add(settled_pnl)
+                        .ok_or(ContractError::HelpersError)?;
+                    adjusted_unrealized_pnl = adjusted_unrealized_pnl
+                        .checked_add(settled_pnl)
+                        .ok_or(ContractError::HelpersError)?;
+                    continue;
+                }
                 let a = &market.amm;
+                let margin_ratio_partial = calculate_size_adjusted_margin_ratio(
+                    m.base_asset_amount.i128(),
+                    market.margin_ratio_partial,
+                    market.margin_ratio_initial,
+                    market.imf_factor,
+                )?;
+                let margin_ratio_maintenance = calculate_size_adjusted_margin_ratio(
+                    m.base_asset_amount.i128(),
+                    market.margin_ratio_maintenance,
+                    market.margin_ratio_initial,
+                    market.imf_factor,
+                )?;
                 let (amm_position_base_asset_value, amm_position_unrealized_pnl) =
                     calculate_base_asset_value_and_pnl(&m, a)?;
 
@@ -411,13 +740,13 @@ pub fn calculate_liquidation_status(
                             .ok_or_else(|| (ContractError::HelpersError))?;
 
                         market_partial_margin_requirement = (oracle_position_base_asset_value)
-                            .checked_mul(market.margin_ratio_partial.into())?;
+                            .checked_mul(margin_ratio_partial.into())?;
 
                         partial_margin_requirement = partial_margin_requirement
                             .checked_add(market_partial_margin_requirement)?;
 
                         market_maintenance_margin_requirement = oracle_position_base_asset_value
-                            .checked_mul(market.margin_ratio_maintenance.into())?;
+                            .checked_mul(margin_ratio_maintenance.into())?;
 
                         maintenance_margin_requirement = maintenance_margin_requirement
                             .checked_add(market_maintenance_margin_requirement)?;
@@ -427,13 +756,13 @@ pub fn calculate_liquidation_status(
                             .ok_or_else(|| (ContractError::HelpersError))?;
 
                         market_partial_margin_requirement = (amm_position_base_asset_value)
-                            .checked_mul(market.margin_ratio_partial.into())?;
+                            .checked_mul(margin_ratio_partial.into())?;
 
                         partial_margin_requirement = partial_margin_requirement
                             .checked_add(market_partial_margin_requirement)?;
 
                         market_maintenance_margin_requirement = amm_position_base_asset_value
-                            .checked_mul(market.margin_ratio_maintenance.into())?;
+                            .checked_mul(margin_ratio_maintenance.into())?;
 
                         maintenance_margin_requirement = maintenance_margin_requirement
                             .checked_add(market_maintenance_margin_requirement)?;
@@ -444,13 +773,13 @@ pub fn calculate_liquidation_status(
                         .ok_or_else(|| (ContractError::HelpersError))?;
 
                     market_partial_margin_requirement = (amm_position_base_asset_value)
-                        .checked_mul(market.margin_ratio_partial.into())?;
+                        .checked_mul(margin_ratio_partial.into())?;
 
                     partial_margin_requirement =
                         partial_margin_requirement.checked_add(market_partial_margin_requirement)?;
 
                     market_maintenance_margin_requirement = amm_position_base_asset_value
-                        .checked_mul(market.margin_ratio_maintenance.into())?;
+                        .checked_mul(margin_ratio_maintenance.into())?;
 
                     maintenance_margin_requirement = maintenance_margin_requirement
                         .checked_add(market_maintenance_margin_requirement)?;
@@ -530,3 +859,181 @@ pub fn calculate_liquidation_status(
         margin_ratio,
     })
 }
+
+pub fn get_keeper_rewards(deps: Deps) -> Result<KeeperRewardsResponse, ContractError> {
+    let keeper_rewards = KEEPER_REWARDS.may_load(deps.storage)?.unwrap_or_default();
+    let pool = KEEPER_REWARD_POOL.may_load(deps.storage)?.unwrap_or_default();
+    Ok(KeeperRewardsResponse {
+        keeper_rewards,
+        pool_balance: pool.balance,
+        total_paid: pool.total_paid,
+    })
+}
+
+pub fn get_staking_contract(deps: Deps) -> Result<StakingContractResponse, ContractError> {
+    let staking_contract = STAKING_CONTRACT.may_load(deps.storage)?;
+    Ok(StakingContractResponse {
+        staking_contract: staking_contract.map(|addr| addr.to_string()),
+    })
+}
+
+pub fn get_funding_info(
+    deps: Deps,
+    env: Env,
+    market_index: u64,
+) -> Result<FundingInfoResponse, ContractError> {
+    let market = MARKETS.load(deps.storage, market_index.to_string())?;
+    let now = env.block.time.seconds();
+
+    let mark_price_twap = market.amm.last_mark_price_twap;
+    let oracle_price_twap = market.amm.last_oracle_price_twap.i128();
+    let (estimated_raw_funding_rate, estimated_funding_rate) =
+        calculate_funding_rate(&market, mark_price_twap, oracle_price_twap)?;
+
+    let next_funding_rate_ts = market
+        .amm
+        .last_funding_rate_ts
+        .checked_add(calculate_next_funding_update_wait(&market.amm)?)
+        .ok_or(ContractError::MathError)?;
+
+    let periods_per_year = ONE_YEAR / max(market.amm.funding_period, 1);
+    let annualized_funding_rate = if oracle_price_twap == 0 {
+        0
+    } else {
+        estimated_funding_rate
+            .checked_mul(periods_per_year as i128)
+            .ok_or(ContractError::MathError)?
+            .checked_mul(MARK_PRICE_PRECISION.u128() as i128)
+            .ok_or(ContractError::MathError)?
+            .checked_div(FUNDING_PAYMENT_PRECISION.u128() as i128)
+            .ok_or(ContractError::MathError)?
+            .checked_div(oracle_price_twap)
+            .ok_or(ContractError::MathError)?
+    };
+
+    Ok(FundingInfoResponse {
+        market_index,
+        last_funding_rate: market.amm.last_funding_rate,
+        last_funding_rate_ts: market.amm.last_funding_rate_ts,
+        mark_price_twap,
+        oracle_price_twap: market.amm.last_oracle_price_twap,
+        estimated_funding_rate: Number128::new(estimated_funding_rate),
+        estimated_raw_funding_rate: Number128::new(estimated_raw_funding_rate),
+        next_funding_rate_ts,
+        seconds_until_next_update: next_funding_rate_ts.saturating_sub(now),
+        annualized_funding_rate: Number128::new(annualized_funding_rate),
+    })
+}
+
+pub fn get_pending_funding(
+    deps: Deps,
+    user_address: String,
+) -> Result<PendingFundingResponse, ContractError> {
+    let user_address = addr_validate_to_lower(deps.api, &user_address)?;
+    let markets_length = STATE.load(deps.storage)?.markets_length;
+
+    let mut positions = vec![];
+    let mut total_pending_funding_payment: i128 = 0;
+    for market_index in 1..markets_length {
+        let position = match POSITIONS.may_load(deps.storage, (&user_address, market_index.to_string()))? {
+            Some(position) if position.base_asset_amount.i128() != 0 => position,
+            _ => continue,
+        };
+        let market = MARKETS.load(deps.storage, market_index.to_string())?;
+        let amm_cumulative_funding_rate = if position.base_asset_amount.i128() > 0 {
+            market.amm.cumulative_funding_rate_long.i128()
+        } else {
+            market.amm.cumulative_funding_rate_short.i128()
+        };
+        let pending_funding_payment = calculate_funding_payment(amm_cumulative_funding_rate, &position)?
+            .checked_div(AMM_TO_QUOTE_PRECISION_RATIO_I128.u128() as i128)
+            .ok_or(ContractError::MathError)?;
+
+        total_pending_funding_payment = total_pending_funding_payment
+            .checked_add(pending_funding_payment)
+            .ok_or(ContractError::MathError)?;
+        positions.push(PendingFundingPaymentResponse {
+            market_index,
+            base_asset_amount: position.base_asset_amount,
+            pending_funding_payment: Number128::new(pending_funding_payment),
+        });
+    }
+
+    Ok(PendingFundingResponse {
+        positions,
+        total_pending_funding_payment: Number128::new(total_pending_funding_payment),
+    })
+}
+
+pub fn get_user_fee_tier(
+    deps: Deps,
+    env: Env,
+    user_address: String,
+    market_index: u64,
+) -> Result<UserFeeTierResponse, ContractError> {
+    let user = USERS.load(
+        deps.storage,
+        &addr_validate_to_lower(deps.api, &user_address)?,
+    )?;
+    let market = MARKETS.load(deps.storage, market_index.to_string())?;
+    let trailing_volume_30d = calculate_rolling_volume(
+        &user.daily_volumes,
+        user.last_volume_update_ts,
+        env.block.time.seconds(),
+    )?;
+    let fee_tiers = get_fee_tiers(deps.storage, &market)?;
+    let (tier, fee_tier) = calculate_fee_tier(
+        &FEESTRUCTURE.load(deps.storage)?,
+        &fee_tiers,
+        trailing_volume_30d,
+    );
+    Ok(UserFeeTierResponse {
+        trailing_volume_30d,
+        tier,
+        fee_tier,
+    })
+}
+
+pub fn get_circuit_breaker(
+    deps: Deps,
+    env: Env,
+    market_index: u64,
+) -> Result<CircuitBreakerResponse, ContractError> {
+    let market = MARKETS.load(deps.storage, market_index.to_string())?;
+    Ok(CircuitBreakerResponse {
+        market_index,
+        in_cool_down: market.in_cool_down(env.block.time.seconds()),
+        circuit_breaker: market.circuit_breaker,
+        reference_price: market.circuit_breaker_reference_price,
+        reference_ts: market.circuit_breaker_reference_ts,
+        cool_down_until_ts: market.cool_down_until_ts,
+    })
+}
+
+pub fn get_effective_leverage(
+    deps: Deps,
+    market_index: u64,
+    base_asset_amount: Uint128,
+) -> Result<EffectiveLeverageResponse, ContractError> {
+    let market = MARKETS.load(deps.storage, market_index.to_string())?;
+    let margin_ratio_initial = calculate_size_adjusted_margin_ratio(
+        base_asset_amount.u128() as i128,
+        market.margin_ratio_initial,
+        market.margin_ratio_initial,
+        market.imf_factor,
+    )?;
+    let margin_ratio_maintenance = calculate_size_adjusted_margin_ratio(
+        base_asset_amount.u128() as i128,
+        market.margin_ratio_maintenance,
+        market.margin_ratio_initial,
+        market.imf_factor,
+    )?;
+    Ok(EffectiveLeverageResponse {
+        market_index,
+        base_asset_amount,
+        imf_factor: market.imf_factor,
+        margin_ratio_initial,
+        margin_ratio_maintenance,
+        max_leverage: Decimal::from_ratio(MARGIN_PRECISION, margin_ratio_initial),
+    })
+}
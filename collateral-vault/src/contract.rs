@@ -7,8 +7,8 @@ use cosmwasm_std::{
 use cw2::set_contract_version;
 
 use crate::error::ContractError;
-use crate::msg::{BalanceResponse, ConfigResponse, ExecuteMsg, InstantiateMsg, QueryMsg};
-use crate::state::{State, ADMIN, STATE};
+use crate::msg::{BalanceResponse, ConfigResponse, ExecuteMsg, InstantiateMsg, QueryMsg, SudoMsg};
+use crate::state::{State, ADMIN, PENDING_ADMIN, STATE};
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:collateral-funds";
@@ -26,6 +26,7 @@ pub fn instantiate(
         total_deposit: Uint128::zero(),
         clearing_house: msg.clearing_house,
         denom_stable: msg.denom_stable,
+        withdrawals_frozen: false,
     };
 
     STATE.save(deps.storage, &state)?;
@@ -44,10 +45,9 @@ pub fn execute(
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
     match msg {
-        ExecuteMsg::UpdateAdmin { new_admin } => {
-            let addr = Some(deps.api.addr_validate(&new_admin)?);
-            Ok(ADMIN.execute_update_admin(deps, info, addr)?)
-        }
+        ExecuteMsg::ProposeNewAdmin { new_admin } => propose_new_admin(deps, info, new_admin),
+        ExecuteMsg::AcceptAdmin {} => accept_admin(deps, info),
+        ExecuteMsg::CancelAdminProposal {} => cancel_admin_proposal(deps, info),
         ExecuteMsg::UpdateClearingHouse { new_clearing_house } => {
             change_clearing_house(deps, info, new_clearing_house)
         }
@@ -56,6 +56,13 @@ pub fn execute(
     }
 }
 
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn sudo(deps: DepsMut, _env: Env, msg: SudoMsg) -> Result<Response, ContractError> {
+    match msg {
+        SudoMsg::FreezeWithdrawals { frozen } => freeze_withdrawals(deps, frozen),
+    }
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
@@ -64,6 +71,52 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
     }
 }
 
+pub fn propose_new_admin(
+    deps: DepsMut,
+    info: MessageInfo,
+    new_admin: String,
+) -> Result<Response, ContractError> {
+    ADMIN.assert_admin(deps.as_ref(), &info.sender)?;
+    let new_admin = deps.api.addr_validate(&new_admin)?;
+    PENDING_ADMIN.save(deps.storage, &new_admin)?;
+    Ok(Response::new()
+        .add_attribute("method", "propose_new_admin")
+        .add_attribute("pending_admin", new_admin))
+}
+
+pub fn accept_admin(mut deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    let pending_admin = PENDING_ADMIN
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NoPendingAdmin {})?;
+    if info.sender != pending_admin {
+        return Err(ContractError::UnauthorizedAdmin {});
+    }
+    ADMIN.set(deps.branch(), Some(pending_admin.clone()))?;
+    PENDING_ADMIN.remove(deps.storage);
+    Ok(Response::new()
+        .add_attribute("method", "accept_admin")
+        .add_attribute("admin", pending_admin))
+}
+
+pub fn cancel_admin_proposal(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    ADMIN.assert_admin(deps.as_ref(), &info.sender)?;
+    if PENDING_ADMIN.may_load(deps.storage)?.is_none() {
+        return Err(ContractError::NoPendingAdmin {});
+    }
+    PENDING_ADMIN.remove(deps.storage);
+    Ok(Response::new().add_attribute("method", "cancel_admin_proposal"))
+}
+
+pub fn freeze_withdrawals(deps: DepsMut, frozen: bool) -> Result<Response, ContractError> {
+    STATE.update(deps.storage, |mut state| -> Result<_, ContractError> {
+        state.withdrawals_frozen = frozen;
+        Ok(state)
+    })?;
+    Ok(Response::new()
+        .add_attribute("method", "freeze_withdrawals")
+        .add_attribute("frozen", frozen.to_string()))
+}
+
 pub fn change_clearing_house(
     deps: DepsMut,
     info: MessageInfo,
@@ -106,15 +159,18 @@ pub fn withdraw(
     deps: DepsMut,
     info: MessageInfo,
     to: Addr,
-    amount: u128,
+    amount: Uint128,
 ) -> Result<Response, ContractError> {
     let mut state: State = STATE.load(deps.storage)?;
-    let amount = Uint128::from(amount);
 
     if info.sender != state.clearing_house {
         return Err(ContractError::UnauthorizedClearingHouse {});
     }
 
+    if state.withdrawals_frozen {
+        return Err(ContractError::WithdrawalsFrozen {});
+    }
+
     if amount.gt(&state.total_deposit) {
         return Err(ContractError::InsufficientFunds {});
     };
@@ -139,10 +195,13 @@ pub fn withdraw(
 fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
     let state = STATE.load(deps.storage)?;
     let res = ADMIN.query_admin(deps).unwrap();
+    let pending_admin = PENDING_ADMIN.may_load(deps.storage)?;
     Ok(ConfigResponse {
         clearing_house: state.clearing_house,
         admin: res.admin.unwrap(),
+        pending_admin: pending_admin.map(|a| a.to_string()),
         denom: state.denom_stable,
+        withdrawals_frozen: state.withdrawals_frozen,
     })
 }
 
@@ -9,8 +9,10 @@ use cw_storage_plus::Item;
 pub struct State {
     pub clearing_house: Addr,
     pub total_deposit: Uint128,
-    pub denom_stable: String
+    pub denom_stable: String,
+    pub withdrawals_frozen: bool,
 }
 
 pub const STATE: Item<State> = Item::new("state");
-pub const ADMIN: Admin = Admin::new("admin");
\ No newline at end of file
+pub const ADMIN: Admin = Admin::new("admin");
+pub const PENDING_ADMIN: Item<Addr> = Item::new("pending_admin");
\ No newline at end of file
@@ -1,7 +1,8 @@
 #[cfg(test)]
 mod tests {
-    use crate::contract::{instantiate, query, deposit, change_clearing_house, execute};
-    use crate::msg::{InstantiateMsg, ConfigResponse, QueryMsg, BalanceResponse, ExecuteMsg};
+    use crate::contract::{instantiate, query, deposit, change_clearing_house, execute, sudo, withdraw};
+    use crate::msg::{InstantiateMsg, ConfigResponse, QueryMsg, BalanceResponse, ExecuteMsg, SudoMsg};
+    use crate::ContractError;
 
     
     use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
@@ -88,7 +89,7 @@ mod tests {
             deps.as_mut(),
             mock_env(),
             dep_info.clone(),
-            ExecuteMsg::UpdateAdmin {
+            ExecuteMsg::ProposeNewAdmin {
                 new_admin: "newadmin".to_string(),
             },
         )
@@ -96,6 +97,60 @@ mod tests {
         let res = query(deps.as_ref(), mock_env(), QueryMsg::GetConfig {}).unwrap();
         let value: ConfigResponse = from_binary(&res).unwrap();
         assert_eq!(Addr::unchecked("newclearing"), value.clearing_house);
+        assert_eq!("creator", value.admin);
+        assert_eq!(Some("newadmin".to_string()), value.pending_admin);
+
+        // only the proposed address can accept
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("someone", &[]),
+            ExecuteMsg::AcceptAdmin {},
+        )
+        .unwrap_err();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("newadmin", &[]),
+            ExecuteMsg::AcceptAdmin {},
+        )
+        .unwrap();
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetConfig {}).unwrap();
+        let value: ConfigResponse = from_binary(&res).unwrap();
         assert_eq!("newadmin", value.admin);
+        assert_eq!(None, value.pending_admin);
+    }
+
+    #[test]
+    fn sudo_freezes_withdrawals() {
+        let mut deps = mock_dependencies(&coins(2, "token"));
+
+        let msg = InstantiateMsg {
+            clearing_house: Addr::unchecked("testaddr"),
+            denom_stable: "uusd".to_string(),
+        };
+        let info = mock_info("creator", &coins(1000, "earth"));
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let dep_info = mock_info("testaddr", &coins(1000000, "uusd"));
+        deposit(deps.as_mut(), dep_info.clone()).unwrap();
+
+        sudo(deps.as_mut(), mock_env(), SudoMsg::FreezeWithdrawals { frozen: true }).unwrap();
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetConfig {}).unwrap();
+        let value: ConfigResponse = from_binary(&res).unwrap();
+        assert!(value.withdrawals_frozen);
+
+        // even the clearing house cannot withdraw while frozen
+        let res = withdraw(deps.as_mut(), dep_info.clone(), Addr::unchecked("user"), Uint128::from(1000u128));
+        match res {
+            Err(ContractError::WithdrawalsFrozen {}) => {}
+            _ => panic!("Must return withdrawals frozen error"),
+        }
+
+        sudo(deps.as_mut(), mock_env(), SudoMsg::FreezeWithdrawals { frozen: false }).unwrap();
+        withdraw(deps.as_mut(), dep_info, Addr::unchecked("user"), Uint128::from(1000u128)).unwrap();
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetBalance {}).unwrap();
+        let value: BalanceResponse = from_binary(&res).unwrap();
+        assert_eq!(Uint128::from(999000u64), value.balance);
     }
 }
@@ -5,9 +5,9 @@ use cw2::set_contract_version;
 use cw_storage_plus::{Bound, PrimaryKey};
 
 use crate::error::ContractError;
-use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg, TradeHistoryResponse, LiquidationHistoryResponse, FundingRateHistoryResponse, LengthResponse, ConfigResponse, CurveHistoryResponse, DepositHistoryResponse, FundingPaymentHistoryResponse};
+use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg, TradeHistoryResponse, LiquidationHistoryResponse, FundingRateHistoryResponse, LengthResponse, ConfigResponse, CurveHistoryResponse, DepositHistoryResponse, FundingPaymentHistoryResponse, SettlePnlHistoryResponse};
 use crate::package::validate::addr_validate_to_lower;
-use crate::state::{State, STATE, TradeRecord, LiquidationRecord, FundingPaymentRecord, CurveRecord, FundingRateRecord, DepositRecord, FUNDING_RATE_HISTORY, LIQUIDATION_HISTORY, TRADE_HISTORY, LENGTH, DEPOSIT_HISTORY, FUNDING_PAYMENT_HISTORY, CURVEHISTORY, Length};
+use crate::state::{State, STATE, PENDING_ADMIN, TradeRecord, LiquidationRecord, FundingPaymentRecord, CurveRecord, FundingRateRecord, DepositRecord, SettlePnlRecord, FUNDING_RATE_HISTORY, LIQUIDATION_HISTORY, TRADE_HISTORY, LENGTH, DEPOSIT_HISTORY, FUNDING_PAYMENT_HISTORY, CURVEHISTORY, SETTLE_PNL_HISTORY, Length};
 
 // iterator limits
 pub const MAX_LIMIT: u32 = 20;
@@ -46,7 +46,9 @@ pub fn execute(
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
     match msg {
-        ExecuteMsg::UpdateAdmin{new_admin}=>try_new_admin(deps,info,new_admin),
+        ExecuteMsg::ProposeNewAdmin { new_admin } => try_propose_new_admin(deps, info, new_admin),
+        ExecuteMsg::AcceptAdmin {} => try_accept_admin(deps, info),
+        ExecuteMsg::CancelAdminProposal {} => try_cancel_admin_proposal(deps, info),
         ExecuteMsg::UpdateClearingHouse{new_house}=>try_new_clearing_house(deps,info,new_house),
         ExecuteMsg::RecordCurve{c}=>try_record_curve(deps,info,c),
         ExecuteMsg::RecordFundingPayment{f}=>try_record_funding_payment(deps,info,f),
@@ -54,11 +56,26 @@ pub fn execute(
         ExecuteMsg::RecordLiquidation{l}=>try_record_liquidation(deps,info,l),
         ExecuteMsg::RecordTrade{t}=>try_record_trade(deps,info,t),
         ExecuteMsg::RecordDeposit { d } => try_record_deposit(deps, info, d),
+        ExecuteMsg::RecordSettlePnl { s } => try_record_settle_pnl(deps, info, s),
         ExecuteMsg::RecordFundingPaymentsMultiple {vecf} => try_record_funding_payment_multiple(deps, info, vecf),
+        ExecuteMsg::RecordBatch { records } => try_record_batch(deps, _env, info, records),
         // ExecuteMsg::RecordOrder { o } => try_record_order(deps, info, o), 
     }
 }
 
+fn try_record_batch(mut deps: DepsMut, env: Env, info: MessageInfo, records: Vec<ExecuteMsg>) -> Result<Response, ContractError> {
+    let state = STATE.load(deps.storage)?;
+    if info.sender != state.clearing_house {
+        return Err(ContractError::UnauthorizedClearingHouse {});
+    };
+
+    for r in records {
+        execute(deps.branch(), env.clone(), info.clone(), r)?;
+    }
+
+    Ok(Response::new().add_attribute("method", "record_batch"))
+}
+
 fn try_record_funding_payment_multiple(deps: DepsMut, info: MessageInfo, vecf: Vec<FundingPaymentRecord>) -> Result<Response, ContractError> {
     let state = STATE.load(deps.storage)?;
     if info.sender != state.clearing_house {
@@ -107,6 +124,25 @@ fn try_record_deposit(deps: DepsMut, info: MessageInfo, d: DepositRecord) -> Res
     Ok(Response::new().add_attribute("method", "record_deposit"))
 }
 
+fn try_record_settle_pnl(deps: DepsMut, info: MessageInfo, s: SettlePnlRecord) -> Result<Response, ContractError> {
+    let state = STATE.load(deps.storage)?;
+    if info.sender != state.clearing_house {
+        return Err(ContractError::UnauthorizedClearingHouse {});
+    };
+
+    let mut len = LENGTH.load(deps.storage)?;
+    let settle_pnl_history_info_length = len.settle_pnl_history_length.checked_add(1).ok_or(ContractError::MathError)?;
+    len.settle_pnl_history_length = settle_pnl_history_info_length;
+    LENGTH.save(deps.storage, &len)?;
+    SETTLE_PNL_HISTORY.save(
+        deps.storage,
+        (&s.user, settle_pnl_history_info_length.to_string()),
+        &s
+    )?;
+
+    Ok(Response::new().add_attribute("method", "record_settle_pnl"))
+}
+
 fn try_record_trade(deps: DepsMut, info: MessageInfo, t: TradeRecord) -> Result<Response, ContractError> {
     let state = STATE.load(deps.storage)?;
     if info.sender != state.clearing_house {
@@ -227,19 +263,50 @@ fn try_new_clearing_house(deps: DepsMut, info: MessageInfo, new_house: String) -
     Ok(Response::new().add_attribute("method", "new_clearing_house"))
 }
 
-fn try_new_admin(deps: DepsMut, info: MessageInfo, new_admin: String) -> Result<Response, ContractError> {
-    let mut state = STATE.load(deps.storage)?;
+fn try_propose_new_admin(deps: DepsMut, info: MessageInfo, new_admin: String) -> Result<Response, ContractError> {
+    let state = STATE.load(deps.storage)?;
     if info.sender != state.owner {
-        return Err(ContractError::UnauthorizedClearingHouse {});
+        return Err(ContractError::Unauthorized {});
     };
 
-    state.owner = deps.api.addr_validate(&new_admin)?;
+    let new_admin = deps.api.addr_validate(&new_admin)?;
+    PENDING_ADMIN.save(deps.storage, &new_admin)?;
 
-    STATE.update(deps.storage, |_s| -> Result<State, ContractError> {
-        Ok(state)
+    Ok(Response::new()
+        .add_attribute("method", "propose_new_admin")
+        .add_attribute("pending_admin", new_admin))
+}
+
+fn try_accept_admin(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    let pending_admin = PENDING_ADMIN
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NoPendingAdmin)?;
+    if info.sender != pending_admin {
+        return Err(ContractError::Unauthorized {});
+    };
+
+    STATE.update(deps.storage, |mut s| -> Result<State, ContractError> {
+        s.owner = pending_admin.clone();
+        Ok(s)
     })?;
+    PENDING_ADMIN.remove(deps.storage);
 
-    Ok(Response::new().add_attribute("method", "new_admin"))
+    Ok(Response::new()
+        .add_attribute("method", "accept_admin")
+        .add_attribute("admin", pending_admin))
+}
+
+fn try_cancel_admin_proposal(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    let state = STATE.load(deps.storage)?;
+    if info.sender != state.owner {
+        return Err(ContractError::Unauthorized {});
+    };
+    if PENDING_ADMIN.may_load(deps.storage)?.is_none() {
+        return Err(ContractError::NoPendingAdmin);
+    }
+    PENDING_ADMIN.remove(deps.storage);
+
+    Ok(Response::new().add_attribute("method", "cancel_admin_proposal"))
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -290,13 +357,28 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> Result<Binary, ContractErr
         QueryMsg::GetTradeHistory { start_after, limit } => {
             Ok(to_binary(&get_trade_history(deps, start_after, limit)?)?)
         },
+        QueryMsg::GetSettlePnlHistory {
+            user_address,
+            start_after,
+            limit,
+        } => Ok(to_binary(&get_settle_pnl_history(
+            deps,
+            user_address,
+            start_after,
+            limit,
+        )?)?),
         
     }
 }
 
 fn get_config(deps: Deps) -> Result<ConfigResponse, ContractError> {
     let state = STATE.load(deps.storage)?;
-    let config = ConfigResponse { clearing_house: state.clearing_house, owner: state.owner };
+    let pending_admin = PENDING_ADMIN.may_load(deps.storage)?;
+    let config = ConfigResponse {
+        clearing_house: state.clearing_house,
+        owner: state.owner,
+        pending_admin: pending_admin.map(|a| a.to_string()),
+    };
     Ok(config)
 }
 
@@ -309,6 +391,7 @@ pub fn get_length(deps: Deps) -> Result<LengthResponse, ContractError> {
         funding_rate_history_length: len.funding_rate_history_length,
         liquidation_history_length: len.liquidation_history_length,
         trade_history_length: len.trade_history_length,
+        settle_pnl_history_length: len.settle_pnl_history_length,
     };
     Ok(length)
 }
@@ -385,6 +468,36 @@ pub fn get_deposit_history(
     Ok(deposit_history)
 }
 
+pub fn get_settle_pnl_history(
+    deps: Deps,
+    user_address: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> Result<Vec<SettlePnlHistoryResponse>, ContractError> {
+    let user_addr = addr_validate_to_lower(deps.api, user_address.as_str())?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after
+        .map(|start| start.joined_key())
+        .map(Bound::Exclusive);
+    let settle_pnl_history = SETTLE_PNL_HISTORY
+        .prefix(&user_addr)
+        .range(deps.storage, start, None, Order::Descending)
+        .filter_map(|records| {
+            records.ok().map(|record| SettlePnlHistoryResponse {
+                ts: record.1.ts,
+                user: record.1.user.to_string(),
+                market_index: record.1.market_index,
+                pnl: record.1.pnl,
+                mark_price: record.1.mark_price,
+                oracle_price: record.1.oracle_price,
+                pnl_pool_after: record.1.pnl_pool_after,
+            })
+        })
+        .take(limit)
+        .collect();
+    Ok(settle_pnl_history)
+}
+
 pub fn get_funding_payment_history(
     deps: Deps,
     user_address: String,
@@ -440,6 +553,7 @@ pub fn get_funding_rate_history(
                         ts: funding_record.1.ts,
                         market_index: funding_record.1.market_index,
                         funding_rate: funding_record.1.funding_rate,
+                        raw_funding_rate: funding_record.1.raw_funding_rate,
                         cumulative_funding_rate_long: funding_record.1.cumulative_funding_rate_long,
                         cumulative_funding_rate_short: funding_record
                             .1
@@ -518,6 +632,10 @@ pub fn get_trade_history(
                 liquidation: record.1.liquidation,
                 market_index: record.1.market_index,
                 oracle_price: record.1.oracle_price,
+                realized_pnl: record.1.realized_pnl,
+                entry_price: record.1.entry_price,
+                cumulative_funding_paid: record.1.cumulative_funding_paid,
+                cumulative_fees_paid: record.1.cumulative_fees_paid,
             })
         })
         .take(limit)
@@ -20,6 +20,9 @@ pub enum ContractError {
 
     #[error("Math Error")]
     MathError,
+
+    #[error("No admin transfer pending")]
+    NoPendingAdmin,
 }
 
 impl From<OverflowError> for ContractError {
@@ -4,7 +4,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::package::types::{PositionDirection, DepositDirection};
 use crate::package::number::Number128;
-use crate::state::{CurveRecord, FundingPaymentRecord, FundingRateRecord, LiquidationRecord, TradeRecord, DepositRecord};
+use crate::state::{CurveRecord, FundingPaymentRecord, FundingRateRecord, LiquidationRecord, TradeRecord, DepositRecord, SettlePnlRecord};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct InstantiateMsg {
@@ -14,9 +14,11 @@ pub struct InstantiateMsg {
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
-    UpdateAdmin {
+    ProposeNewAdmin {
         new_admin: String,
     },
+    AcceptAdmin {},
+    CancelAdminProposal {},
     UpdateClearingHouse {
         new_house: String,
     },
@@ -41,6 +43,13 @@ pub enum ExecuteMsg {
     RecordTrade {
         t: TradeRecord
     },
+    RecordSettlePnl {
+        s: SettlePnlRecord
+    },
+    // records several history entries sent by the clearing house in one message
+    RecordBatch {
+        records: Vec<ExecuteMsg>
+    },
     // RecordOrder {
     //     o: OrderRecord
     // },
@@ -78,6 +87,11 @@ pub enum QueryMsg {
         start_after: Option<String>,
         limit: Option<u32>,
     },
+    GetSettlePnlHistory {
+        user_address: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -130,6 +144,7 @@ pub struct FundingRateHistoryResponse {
     pub ts: u64,
     pub market_index: u64,
     pub funding_rate: Number128,
+    pub raw_funding_rate: Number128,
     pub cumulative_funding_rate_long: Number128,
     pub cumulative_funding_rate_short: Number128,
     pub oracle_price_twap: Number128,
@@ -169,6 +184,21 @@ pub struct TradeHistoryResponse {
     pub liquidation: bool,
     pub market_index: u64,
     pub oracle_price: Number128,
+    pub realized_pnl: Number128,
+    pub entry_price: Uint128,
+    pub cumulative_funding_paid: Number128,
+    pub cumulative_fees_paid: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SettlePnlHistoryResponse {
+    pub ts: u64,
+    pub user: String,
+    pub market_index: u64,
+    pub pnl: Number128,
+    pub mark_price: Uint128,
+    pub oracle_price: Number128,
+    pub pnl_pool_after: Uint128,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -180,10 +210,12 @@ pub struct LengthResponse {
     pub liquidation_history_length: u64,
     // pub order_history_length: u64,
     pub trade_history_length: u64,
+    pub settle_pnl_history_length: u64,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct ConfigResponse {
     pub clearing_house: Addr,
     pub owner: Addr,
+    pub pending_admin: Option<String>,
 }
\ No newline at end of file
@@ -37,7 +37,8 @@ pub struct CurveRecord {
     pub total_fee: Uint128,
     pub total_fee_minus_distributions: Uint128,
     pub adjustment_cost: Number128,
-    pub oracle_price: Number128
+    pub oracle_price: Number128,
+    pub record_type: Type,
 }
 
 pub const CURVEHISTORY: Map<String,  CurveRecord> = Map::new("curve_history");
@@ -74,6 +75,8 @@ pub struct FundingRateRecord {
     pub ts: u64,
     pub market_index: u64,
     pub funding_rate: Number128,
+    // premium plus interest before the max funding rate clamp
+    pub raw_funding_rate: Number128,
     pub cumulative_funding_rate_long: Number128,
     pub cumulative_funding_rate_short: Number128,
     pub oracle_price_twap: Number128,
@@ -150,10 +153,29 @@ pub struct TradeRecord {
     pub liquidation: bool,
     pub market_index: u64,
     pub oracle_price: Number128,
+    pub circuit_breaker_cool_down: bool,
+    // pnl realized by this trade and the position's totals after it
+    pub realized_pnl: Number128,
+    pub entry_price: Uint128,
+    pub cumulative_funding_paid: Number128,
+    pub cumulative_fees_paid: Uint128,
 }
 
 pub const TRADE_HISTORY: Map<(&Addr, String),  TradeRecord> = Map::new("trade_history");
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SettlePnlRecord {
+    pub ts: u64,
+    pub user: Addr,
+    pub market_index: u64,
+    pub pnl: Number128,
+    pub mark_price: Uint128,
+    pub oracle_price: Number128,
+    pub pnl_pool_after: Uint128,
+}
+
+pub const SETTLE_PNL_HISTORY: Map<(&Addr, String),  SettlePnlRecord> = Map::new("settle_pnl_history");
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Length {
     pub curve_history_length: u64,
@@ -162,6 +184,9 @@ pub struct Length {
     pub funding_rate_history_length: u64,
     pub liquidation_history_length: u64,
     pub trade_history_length: u64,
+    // added after launch, so lengths stored before it deserialize as zero
+    #[serde(default)]
+    pub settle_pnl_history_length: u64,
     // pub order_history_length: u64,
 }
 
@@ -172,4 +197,5 @@ pub struct State {
 }
 
 pub const STATE: Item<State> = Item::new("state");
+pub const PENDING_ADMIN: Item<Addr> = Item::new("pending_admin");
 pub const LENGTH : Item<Length> = Item::new("length");
\ No newline at end of file
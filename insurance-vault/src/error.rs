@@ -23,6 +23,12 @@ pub enum ContractError {
 
     #[error("Insufficient funds")]
     InsufficientFunds {},
+
+    #[error("No admin transfer pending")]
+    NoPendingAdmin {},
+
+    #[error("Withdrawals are frozen")]
+    WithdrawalsFrozen {},
 }
 
 impl From<OverflowError> for ContractError {
@@ -11,10 +11,19 @@ pub struct InstantiateMsg {
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
-    UpdateAdmin { new_admin : String },
+    ProposeNewAdmin { new_admin: String },
+    AcceptAdmin {},
+    CancelAdminProposal {},
     UpdateClearingHouse { new_clearing_house: Addr },
     Deposit {},
-    Withdraw { to_address: Addr, amount: u128 },
+    Withdraw { to_address: Addr, amount: Uint128 },
+}
+
+// sent by the chain's governance module, independent of the admin
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SudoMsg {
+    FreezeWithdrawals { frozen: bool },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -30,7 +39,9 @@ pub enum QueryMsg {
 pub struct ConfigResponse {
     pub clearing_house: Addr,
     pub admin : String,
-    pub denom: String
+    pub pending_admin: Option<String>,
+    pub denom: String,
+    pub withdrawals_frozen: bool,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
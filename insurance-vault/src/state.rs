@@ -9,8 +9,10 @@ pub struct State {
     pub admin: Addr,
     pub clearing_house: Addr,
     pub total_deposit: Uint128,
-    pub denom_stable: String
+    pub denom_stable: String,
+    pub withdrawals_frozen: bool,
 }
 
 pub const STATE: Item<State> = Item::new("state");
+pub const PENDING_ADMIN: Item<Addr> = Item::new("pending_admin");
 // pub const ADMIN: Admin = Admin::new("admin");
\ No newline at end of file
@@ -7,7 +7,7 @@ use cw2::set_contract_version;
 
 use crate::error::ContractError;
 use crate::msg::{ ExecuteMsg, InstantiateMsg, QueryMsg, ConfigResponse, PriceResponse, InfoResponse};
-use crate::state::{Config, CONFIG, ASSETS, Price, FEEDERS};
+use crate::state::{Config, CONFIG, ASSETS, Price, FEEDERS, PENDING_ADMIN};
 // use terra_cosmwasm::{ TerraQuerier, ExchangeRatesResponse };
 
 // version info for migration info
@@ -44,6 +44,9 @@ pub fn execute(
         ExecuteMsg::RegisterAsset { asset, price_feeder } => try_register_asset(deps, info,env, asset, price_feeder),
         ExecuteMsg::RevokeAsset { asset } => try_revoke_asset(deps, info, asset),
         ExecuteMsg::FeedPrice { asset, price } => try_feed_price(deps, info, env, asset, price),
+        ExecuteMsg::ProposeNewAdmin { new_admin } => try_propose_new_admin(deps, info, new_admin),
+        ExecuteMsg::AcceptAdmin {} => try_accept_admin(deps, info),
+        ExecuteMsg::CancelAdminProposal {} => try_cancel_admin_proposal(deps, info),
     }
 }
 
@@ -96,6 +99,52 @@ pub fn try_feed_price(deps: DepsMut, info: MessageInfo, env: Env, asset: String,
     Ok(Response::new().add_attribute("method", "feed_price"))
 }
 
+pub fn try_propose_new_admin(deps: DepsMut, info: MessageInfo, new_admin: String) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if info.sender != config.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let new_admin = deps.api.addr_validate(&new_admin)?;
+    PENDING_ADMIN.save(deps.storage, &new_admin)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "propose_new_admin")
+        .add_attribute("pending_admin", new_admin))
+}
+
+pub fn try_accept_admin(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    let pending_admin = PENDING_ADMIN
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NoPendingAdmin {})?;
+    if info.sender != pending_admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    CONFIG.update(deps.storage, |mut config| -> Result<Config, ContractError> {
+        config.admin = pending_admin.clone();
+        Ok(config)
+    })?;
+    PENDING_ADMIN.remove(deps.storage);
+
+    Ok(Response::new()
+        .add_attribute("method", "accept_admin")
+        .add_attribute("admin", pending_admin))
+}
+
+pub fn try_cancel_admin_proposal(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if info.sender != config.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+    if PENDING_ADMIN.may_load(deps.storage)?.is_none() {
+        return Err(ContractError::NoPendingAdmin {});
+    }
+    PENDING_ADMIN.remove(deps.storage);
+
+    Ok(Response::new().add_attribute("method", "cancel_admin_proposal"))
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
@@ -109,8 +158,10 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
 
 fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
     let state = CONFIG.load(deps.storage)?;
+    let pending_admin = PENDING_ADMIN.may_load(deps.storage)?;
     Ok(ConfigResponse{
         owner: state.admin,
+        pending_admin: pending_admin.map(|a| a.to_string()),
         base_denom: state.base_denom,
     })
 }
@@ -19,7 +19,12 @@ pub enum ExecuteMsg {
     FeedPrice {
         asset: String,
         price: u128
-    }
+    },
+    ProposeNewAdmin {
+        new_admin: String,
+    },
+    AcceptAdmin {},
+    CancelAdminProposal {},
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -37,6 +42,7 @@ pub enum QueryMsg {
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct ConfigResponse {
     pub owner: Addr,
+    pub pending_admin: Option<String>,
     pub base_denom: String,
     // pub mirror_oracle: Addr,
     // pub anchor_oracle: Addr, 
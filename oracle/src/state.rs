@@ -26,5 +26,6 @@ pub struct Price {
 }
 
 pub const CONFIG: Item<Config> = Item::new("config");
+pub const PENDING_ADMIN: Item<Addr> = Item::new("pending_admin");
 pub const ASSETS : Map<String, Price> = Map::new("assets");
 pub const FEEDERS : Map<String, Addr> = Map::new("feeders");
\ No newline at end of file
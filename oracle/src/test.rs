@@ -2,6 +2,7 @@
 mod tests {
     use crate::contract::{instantiate, query, execute};
     use crate::msg::{InstantiateMsg, QueryMsg, ConfigResponse, ExecuteMsg};
+    use crate::ContractError;
 
     use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
     use cosmwasm_std::{coins, from_binary};
@@ -23,6 +24,46 @@ mod tests {
         assert_eq!("creator", value.owner);
     }
 
+    #[test]
+    fn two_step_admin_transfer() {
+        let mut deps = mock_dependencies(&coins(2, "token"));
+
+        let msg = InstantiateMsg { };
+        let info = mock_info("creator", &coins(1000, "earth"));
+        instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+        let msg = ExecuteMsg::ProposeNewAdmin { new_admin: "newadmin".to_string() };
+        execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+        // admin does not change until the proposed address accepts
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+        let value: ConfigResponse = from_binary(&res).unwrap();
+        assert_eq!("creator", value.owner);
+        assert_eq!(Some("newadmin".to_string()), value.pending_admin);
+
+        let res = execute(deps.as_mut(), mock_env(), mock_info("anyone", &[]), ExecuteMsg::AcceptAdmin {});
+        match res {
+            Err(ContractError::Unauthorized {}) => {}
+            _ => panic!("Must return unauthorized error"),
+        }
+
+        execute(deps.as_mut(), mock_env(), mock_info("newadmin", &[]), ExecuteMsg::AcceptAdmin {}).unwrap();
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+        let value: ConfigResponse = from_binary(&res).unwrap();
+        assert_eq!("newadmin", value.owner);
+        assert_eq!(None, value.pending_admin);
+
+        // a cancelled proposal can no longer be accepted
+        let msg = ExecuteMsg::ProposeNewAdmin { new_admin: "other".to_string() };
+        execute(deps.as_mut(), mock_env(), mock_info("newadmin", &[]), msg).unwrap();
+        execute(deps.as_mut(), mock_env(), mock_info("newadmin", &[]), ExecuteMsg::CancelAdminProposal {}).unwrap();
+        let res = execute(deps.as_mut(), mock_env(), mock_info("other", &[]), ExecuteMsg::AcceptAdmin {});
+        match res {
+            Err(ContractError::NoPendingAdmin {}) => {}
+            _ => panic!("Must return no pending admin error"),
+        }
+    }
+
     // #[test]
     // fn feed_and_read() {
     //     let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
@@ -0,0 +1,362 @@
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
+use cosmwasm_std::{
+    coins, from_binary, to_binary, Addr, BankMsg, Binary, Decimal, Deps, DepsMut, Env,
+    MessageInfo, Response, StdResult, Uint128, WasmMsg,
+};
+use cw2::set_contract_version;
+use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
+
+use crate::error::ContractError;
+use crate::msg::{
+    ConfigResponse, ExecuteMsg, InstantiateMsg, QueryMsg, ReceiveMsg, StakedBalanceResponse,
+    StakerResponse,
+};
+use crate::state::{Staker, State, Unbonding, PENDING_ADMIN, STAKERS, STATE, UNBONDINGS};
+
+// version info for migration info
+const CONTRACT_NAME: &str = "crates.io:staking";
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn instantiate(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    let state = State {
+        admin: info.sender.clone(),
+        clearing_house: msg.clearing_house,
+        token: msg.token,
+        reward_denom: msg.reward_denom,
+        unbonding_period: msg.unbonding_period,
+        total_staked: Uint128::zero(),
+        reward_index: Decimal::zero(),
+        undistributed_rewards: Uint128::zero(),
+        total_rewards_distributed: Uint128::zero(),
+    };
+
+    STATE.save(deps.storage, &state)?;
+    Ok(Response::new()
+        .add_attribute("method", "instantiate")
+        .add_attribute("admin", info.sender))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    match msg {
+        ExecuteMsg::ProposeNewAdmin { new_admin } => propose_new_admin(deps, info, new_admin),
+        ExecuteMsg::AcceptAdmin {} => accept_admin(deps, info),
+        ExecuteMsg::CancelAdminProposal {} => cancel_admin_proposal(deps, info),
+        ExecuteMsg::UpdateClearingHouse { new_clearing_house } => {
+            change_clearing_house(deps, info, new_clearing_house)
+        }
+        ExecuteMsg::UpdateUnbondingPeriod { unbonding_period } => {
+            update_unbonding_period(deps, info, unbonding_period)
+        }
+        ExecuteMsg::Receive(cw20_msg) => receive(deps, info, cw20_msg),
+        ExecuteMsg::Unstake { amount } => unstake(deps, env, info, amount),
+        ExecuteMsg::Withdraw {} => withdraw(deps, env, info),
+        ExecuteMsg::ClaimRewards {} => claim_rewards(deps, info),
+        ExecuteMsg::DistributeRewards { amount } => distribute_rewards(deps, info, amount),
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::GetConfig {} => to_binary(&query_config(deps)?),
+        QueryMsg::GetStakedBalance { address } => to_binary(&query_staked_balance(deps, address)?),
+        QueryMsg::GetStaker { address } => to_binary(&query_staker(deps, address)?),
+    }
+}
+
+pub fn propose_new_admin(
+    deps: DepsMut,
+    info: MessageInfo,
+    new_admin: String,
+) -> Result<Response, ContractError> {
+    let state = STATE.load(deps.storage)?;
+    if state.admin != info.sender {
+        return Err(ContractError::UnauthorizedAdmin {});
+    }
+    let new_admin = deps.api.addr_validate(&new_admin)?;
+    PENDING_ADMIN.save(deps.storage, &new_admin)?;
+    Ok(Response::new()
+        .add_attribute("method", "propose_new_admin")
+        .add_attribute("pending_admin", new_admin))
+}
+
+pub fn accept_admin(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    let pending_admin = PENDING_ADMIN
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NoPendingAdmin {})?;
+    if info.sender != pending_admin {
+        return Err(ContractError::UnauthorizedAdmin {});
+    }
+    STATE.update(deps.storage, |mut state| -> Result<_, ContractError> {
+        state.admin = pending_admin.clone();
+        Ok(state)
+    })?;
+    PENDING_ADMIN.remove(deps.storage);
+    Ok(Response::new()
+        .add_attribute("method", "accept_admin")
+        .add_attribute("admin", pending_admin))
+}
+
+pub fn cancel_admin_proposal(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    let state = STATE.load(deps.storage)?;
+    if state.admin != info.sender {
+        return Err(ContractError::UnauthorizedAdmin {});
+    }
+    if PENDING_ADMIN.may_load(deps.storage)?.is_none() {
+        return Err(ContractError::NoPendingAdmin {});
+    }
+    PENDING_ADMIN.remove(deps.storage);
+    Ok(Response::new().add_attribute("method", "cancel_admin_proposal"))
+}
+
+pub fn change_clearing_house(
+    deps: DepsMut,
+    info: MessageInfo,
+    clearing_house: Addr,
+) -> Result<Response, ContractError> {
+    STATE.update(deps.storage, |mut state| -> Result<_, ContractError> {
+        if state.admin != info.sender {
+            return Err(ContractError::UnauthorizedAdmin {});
+        }
+        state.clearing_house = clearing_house.clone();
+        Ok(state)
+    })?;
+    Ok(Response::new()
+        .add_attribute("method", "change_clearing_house")
+        .add_attribute("new_clearing_house", clearing_house))
+}
+
+pub fn update_unbonding_period(
+    deps: DepsMut,
+    info: MessageInfo,
+    unbonding_period: u64,
+) -> Result<Response, ContractError> {
+    // only applies to unstakes made after the update
+    STATE.update(deps.storage, |mut state| -> Result<_, ContractError> {
+        if state.admin != info.sender {
+            return Err(ContractError::UnauthorizedAdmin {});
+        }
+        state.unbonding_period = unbonding_period;
+        Ok(state)
+    })?;
+    Ok(Response::new()
+        .add_attribute("method", "update_unbonding_period")
+        .add_attribute("unbonding_period", unbonding_period.to_string()))
+}
+
+// moves rewards earned since the staker's last update into pending_rewards
+fn accrue_rewards(state: &State, staker: &mut Staker) {
+    if state.reward_index > staker.reward_index {
+        let earned = staker.staked * (state.reward_index - staker.reward_index);
+        staker.pending_rewards += earned;
+    }
+    staker.reward_index = state.reward_index;
+}
+
+pub fn receive(
+    deps: DepsMut,
+    info: MessageInfo,
+    cw20_msg: Cw20ReceiveMsg,
+) -> Result<Response, ContractError> {
+    let mut state = STATE.load(deps.storage)?;
+    if info.sender != state.token {
+        return Err(ContractError::InvalidToken {});
+    }
+    if cw20_msg.amount.is_zero() {
+        return Err(ContractError::ZeroAmount {});
+    }
+
+    match from_binary(&cw20_msg.msg)? {
+        ReceiveMsg::Stake {} => {
+            let staker_addr = deps.api.addr_validate(&cw20_msg.sender)?;
+            let mut staker = STAKERS
+                .may_load(deps.storage, &staker_addr)?
+                .unwrap_or_default();
+            accrue_rewards(&state, &mut staker);
+            staker.staked = staker.staked.checked_add(cw20_msg.amount)?;
+            state.total_staked = state.total_staked.checked_add(cw20_msg.amount)?;
+
+            STAKERS.save(deps.storage, &staker_addr, &staker)?;
+            STATE.save(deps.storage, &state)?;
+            Ok(Response::new()
+                .add_attribute("method", "stake")
+                .add_attribute("staker", staker_addr)
+                .add_attribute("amount", cw20_msg.amount))
+        }
+    }
+}
+
+pub fn unstake(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    if amount.is_zero() {
+        return Err(ContractError::ZeroAmount {});
+    }
+    let mut state = STATE.load(deps.storage)?;
+    let mut staker = STAKERS
+        .may_load(deps.storage, &info.sender)?
+        .ok_or(ContractError::InsufficientStake {})?;
+    if staker.staked < amount {
+        return Err(ContractError::InsufficientStake {});
+    }
+
+    accrue_rewards(&state, &mut staker);
+    staker.staked = staker.staked.checked_sub(amount)?;
+    state.total_staked = state.total_staked.checked_sub(amount)?;
+
+    // the tokens stop counting towards the discount right away but stay locked until released
+    let release_at = env.block.time.seconds() + state.unbonding_period;
+    let mut unbondings = UNBONDINGS
+        .may_load(deps.storage, &info.sender)?
+        .unwrap_or_default();
+    unbondings.push(Unbonding { amount, release_at });
+
+    UNBONDINGS.save(deps.storage, &info.sender, &unbondings)?;
+    STAKERS.save(deps.storage, &info.sender, &staker)?;
+    STATE.save(deps.storage, &state)?;
+    Ok(Response::new()
+        .add_attribute("method", "unstake")
+        .add_attribute("amount", amount)
+        .add_attribute("release_at", release_at.to_string()))
+}
+
+pub fn withdraw(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
+    let state = STATE.load(deps.storage)?;
+    let now = env.block.time.seconds();
+    let unbondings = UNBONDINGS
+        .may_load(deps.storage, &info.sender)?
+        .unwrap_or_default();
+
+    let (released, locked): (Vec<Unbonding>, Vec<Unbonding>) =
+        unbondings.into_iter().partition(|u| u.release_at <= now);
+    let amount = released
+        .iter()
+        .try_fold(Uint128::zero(), |total, u| total.checked_add(u.amount))?;
+    if amount.is_zero() {
+        return Err(ContractError::NothingToWithdraw {});
+    }
+
+    if locked.is_empty() {
+        UNBONDINGS.remove(deps.storage, &info.sender);
+    } else {
+        UNBONDINGS.save(deps.storage, &info.sender, &locked)?;
+    }
+
+    let transfer_msg = WasmMsg::Execute {
+        contract_addr: state.token.to_string(),
+        msg: to_binary(&Cw20ExecuteMsg::Transfer {
+            recipient: info.sender.to_string(),
+            amount,
+        })?,
+        funds: vec![],
+    };
+    Ok(Response::new()
+        .add_message(transfer_msg)
+        .add_attribute("method", "withdraw")
+        .add_attribute("amount", amount))
+}
+
+pub fn claim_rewards(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    let state = STATE.load(deps.storage)?;
+    let mut staker = STAKERS
+        .may_load(deps.storage, &info.sender)?
+        .ok_or(ContractError::NoRewards {})?;
+    accrue_rewards(&state, &mut staker);
+
+    let amount = staker.pending_rewards;
+    if amount.is_zero() {
+        return Err(ContractError::NoRewards {});
+    }
+    staker.pending_rewards = Uint128::zero();
+    STAKERS.save(deps.storage, &info.sender, &staker)?;
+
+    let send_msg = BankMsg::Send {
+        to_address: info.sender.to_string(),
+        amount: coins(amount.u128(), state.reward_denom),
+    };
+    Ok(Response::new()
+        .add_message(send_msg)
+        .add_attribute("method", "claim_rewards")
+        .add_attribute("amount", amount))
+}
+
+pub fn distribute_rewards(
+    deps: DepsMut,
+    info: MessageInfo,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    let mut state = STATE.load(deps.storage)?;
+    if info.sender != state.clearing_house {
+        return Err(ContractError::UnauthorizedClearingHouse {});
+    }
+
+    let rewards = amount.checked_add(state.undistributed_rewards)?;
+    if state.total_staked.is_zero() {
+        state.undistributed_rewards = rewards;
+    } else {
+        state.reward_index = state.reward_index + Decimal::from_ratio(rewards, state.total_staked);
+        state.undistributed_rewards = Uint128::zero();
+    }
+    state.total_rewards_distributed = state.total_rewards_distributed.checked_add(amount)?;
+
+    STATE.save(deps.storage, &state)?;
+    Ok(Response::new()
+        .add_attribute("method", "distribute_rewards")
+        .add_attribute("amount", amount))
+}
+
+fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
+    let state = STATE.load(deps.storage)?;
+    let pending_admin = PENDING_ADMIN.may_load(deps.storage)?;
+    Ok(ConfigResponse {
+        admin: state.admin.to_string(),
+        pending_admin: pending_admin.map(|a| a.to_string()),
+        clearing_house: state.clearing_house,
+        token: state.token,
+        reward_denom: state.reward_denom,
+        unbonding_period: state.unbonding_period,
+        total_staked: state.total_staked,
+        reward_index: state.reward_index,
+        undistributed_rewards: state.undistributed_rewards,
+        total_rewards_distributed: state.total_rewards_distributed,
+    })
+}
+
+fn query_staked_balance(deps: Deps, address: String) -> StdResult<StakedBalanceResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    let staker = STAKERS.may_load(deps.storage, &address)?.unwrap_or_default();
+    Ok(StakedBalanceResponse {
+        balance: staker.staked,
+    })
+}
+
+fn query_staker(deps: Deps, address: String) -> StdResult<StakerResponse> {
+    let state = STATE.load(deps.storage)?;
+    let address = deps.api.addr_validate(&address)?;
+    let mut staker = STAKERS.may_load(deps.storage, &address)?.unwrap_or_default();
+    accrue_rewards(&state, &mut staker);
+    let unbondings = UNBONDINGS.may_load(deps.storage, &address)?.unwrap_or_default();
+    Ok(StakerResponse {
+        staked: staker.staked,
+        pending_rewards: staker.pending_rewards,
+        unbondings,
+    })
+}
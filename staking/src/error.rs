@@ -0,0 +1,38 @@
+use cosmwasm_std::{OverflowError, StdError};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("Admin only")]
+    UnauthorizedAdmin {},
+
+    #[error("Clearing house only")]
+    UnauthorizedClearingHouse {},
+
+    #[error("Only the staking token can be staked")]
+    InvalidToken {},
+
+    #[error("Amount must be greater than zero")]
+    ZeroAmount {},
+
+    #[error("Insufficient staked balance")]
+    InsufficientStake {},
+
+    #[error("Nothing to withdraw")]
+    NothingToWithdraw {},
+
+    #[error("No rewards to claim")]
+    NoRewards {},
+
+    #[error("No admin transfer pending")]
+    NoPendingAdmin {},
+}
+
+impl From<OverflowError> for ContractError {
+    fn from(o: OverflowError) -> Self {
+        StdError::from(o).into()
+    }
+}
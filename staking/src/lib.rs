@@ -0,0 +1,7 @@
+pub mod contract;
+mod error;
+pub mod msg;
+pub mod state;
+pub mod test;
+
+pub use crate::error::ContractError;
@@ -0,0 +1,73 @@
+use cosmwasm_std::{Addr, Decimal, Uint128};
+use cw20::Cw20ReceiveMsg;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::state::Unbonding;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct InstantiateMsg {
+    pub clearing_house: Addr,
+    pub token: Addr,
+    pub reward_denom: String,
+    pub unbonding_period: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecuteMsg {
+    ProposeNewAdmin { new_admin: String },
+    AcceptAdmin {},
+    CancelAdminProposal {},
+    UpdateClearingHouse { new_clearing_house: Addr },
+    UpdateUnbondingPeriod { unbonding_period: u64 },
+    // cw20 send hook, the attached msg must be a ReceiveMsg
+    Receive(Cw20ReceiveMsg),
+    Unstake { amount: Uint128 },
+    // returns unbonded tokens whose unbonding period has passed
+    Withdraw {},
+    ClaimRewards {},
+    // sent by the clearing house right after it moves the fee share to this contract
+    DistributeRewards { amount: Uint128 },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ReceiveMsg {
+    Stake {},
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryMsg {
+    GetConfig {},
+    // used by the clearing house to pick the fee discount tier
+    GetStakedBalance { address: String },
+    GetStaker { address: String },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ConfigResponse {
+    pub admin: String,
+    pub pending_admin: Option<String>,
+    pub clearing_house: Addr,
+    pub token: Addr,
+    pub reward_denom: String,
+    pub unbonding_period: u64,
+    pub total_staked: Uint128,
+    pub reward_index: Decimal,
+    pub undistributed_rewards: Uint128,
+    pub total_rewards_distributed: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct StakedBalanceResponse {
+    pub balance: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct StakerResponse {
+    pub staked: Uint128,
+    pub pending_rewards: Uint128,
+    pub unbondings: Vec<Unbonding>,
+}
@@ -0,0 +1,40 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{Addr, Decimal, Uint128};
+use cw_storage_plus::{Item, Map};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct State {
+    pub admin: Addr,
+    pub clearing_house: Addr,
+    // cw20 token that is staked for fee discounts
+    pub token: Addr,
+    // native denom rewards are paid in
+    pub reward_denom: String,
+    pub unbonding_period: u64,
+    pub total_staked: Uint128,
+    // rewards earned per staked token since instantiation
+    pub reward_index: Decimal,
+    // rewards sent while nothing was staked, added to the next distribution
+    pub undistributed_rewards: Uint128,
+    pub total_rewards_distributed: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct Staker {
+    pub staked: Uint128,
+    pub reward_index: Decimal,
+    pub pending_rewards: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Unbonding {
+    pub amount: Uint128,
+    pub release_at: u64,
+}
+
+pub const STATE: Item<State> = Item::new("state");
+pub const PENDING_ADMIN: Item<Addr> = Item::new("pending_admin");
+pub const STAKERS: Map<&Addr, Staker> = Map::new("stakers");
+pub const UNBONDINGS: Map<&Addr, Vec<Unbonding>> = Map::new("unbondings");
@@ -0,0 +1,179 @@
+#[cfg(test)]
+mod tests {
+    use crate::contract::{execute, instantiate, query};
+    use crate::msg::{
+        ConfigResponse, ExecuteMsg, InstantiateMsg, QueryMsg, ReceiveMsg, StakedBalanceResponse,
+        StakerResponse,
+    };
+    use crate::ContractError;
+
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+    use cosmwasm_std::{coins, from_binary, to_binary, Addr, Uint128};
+    use cw20::Cw20ReceiveMsg;
+
+    fn setup(deps: cosmwasm_std::DepsMut, unbonding_period: u64) {
+        let msg = InstantiateMsg {
+            clearing_house: Addr::unchecked("clearinghouse"),
+            token: Addr::unchecked("token"),
+            reward_denom: "uusd".to_string(),
+            unbonding_period,
+        };
+        instantiate(deps, mock_env(), mock_info("creator", &[]), msg).unwrap();
+    }
+
+    fn stake_msg(staker: &str, amount: u128) -> ExecuteMsg {
+        ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: staker.to_string(),
+            amount: Uint128::from(amount),
+            msg: to_binary(&ReceiveMsg::Stake {}).unwrap(),
+        })
+    }
+
+    fn query_staker(deps: cosmwasm_std::Deps, staker: &str) -> StakerResponse {
+        let res = query(
+            deps,
+            mock_env(),
+            QueryMsg::GetStaker {
+                address: staker.to_string(),
+            },
+        )
+        .unwrap();
+        from_binary(&res).unwrap()
+    }
+
+    #[test]
+    fn proper_initialization() {
+        let mut deps = mock_dependencies(&[]);
+        setup(deps.as_mut(), 100);
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetConfig {}).unwrap();
+        let value: ConfigResponse = from_binary(&res).unwrap();
+        assert_eq!("creator", value.admin);
+        assert_eq!(Addr::unchecked("token"), value.token);
+        assert_eq!(100, value.unbonding_period);
+        assert_eq!(Uint128::zero(), value.total_staked);
+    }
+
+    #[test]
+    fn stake_only_accepts_staking_token() {
+        let mut deps = mock_dependencies(&[]);
+        setup(deps.as_mut(), 100);
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("othertoken", &[]),
+            stake_msg("user", 1000),
+        );
+        match res {
+            Err(ContractError::InvalidToken {}) => {}
+            _ => panic!("Must return invalid token error"),
+        }
+
+        execute(deps.as_mut(), mock_env(), mock_info("token", &[]), stake_msg("user", 1000)).unwrap();
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetStakedBalance {
+                address: "user".to_string(),
+            },
+        )
+        .unwrap();
+        let value: StakedBalanceResponse = from_binary(&res).unwrap();
+        assert_eq!(Uint128::from(1000u64), value.balance);
+    }
+
+    #[test]
+    fn unstake_waits_for_unbonding_period() {
+        let mut deps = mock_dependencies(&[]);
+        setup(deps.as_mut(), 100);
+        execute(deps.as_mut(), mock_env(), mock_info("token", &[]), stake_msg("user", 1000)).unwrap();
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("user", &[]),
+            ExecuteMsg::Unstake {
+                amount: Uint128::from(400u64),
+            },
+        )
+        .unwrap();
+        let value = query_staker(deps.as_ref(), "user");
+        assert_eq!(Uint128::from(600u64), value.staked);
+        assert_eq!(1, value.unbondings.len());
+
+        // tokens stay locked until the unbonding period has passed
+        let res = execute(deps.as_mut(), mock_env(), mock_info("user", &[]), ExecuteMsg::Withdraw {});
+        match res {
+            Err(ContractError::NothingToWithdraw {}) => {}
+            _ => panic!("Must return nothing to withdraw error"),
+        }
+
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(100);
+        let res = execute(deps.as_mut(), env, mock_info("user", &[]), ExecuteMsg::Withdraw {}).unwrap();
+        assert_eq!(1, res.messages.len());
+        assert!(query_staker(deps.as_ref(), "user").unbondings.is_empty());
+    }
+
+    #[test]
+    fn rewards_are_split_pro_rata() {
+        let mut deps = mock_dependencies(&[]);
+        setup(deps.as_mut(), 100);
+
+        // rewards sent before anyone staked are carried over
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("clearinghouse", &[]),
+            ExecuteMsg::DistributeRewards {
+                amount: Uint128::from(1000u64),
+            },
+        )
+        .unwrap();
+
+        execute(deps.as_mut(), mock_env(), mock_info("token", &[]), stake_msg("alice", 1000)).unwrap();
+        execute(deps.as_mut(), mock_env(), mock_info("token", &[]), stake_msg("bob", 3000)).unwrap();
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("someone", &[]),
+            ExecuteMsg::DistributeRewards {
+                amount: Uint128::from(3000u64),
+            },
+        );
+        match res {
+            Err(ContractError::UnauthorizedClearingHouse {}) => {}
+            _ => panic!("Must return unauthorized error"),
+        }
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("clearinghouse", &[]),
+            ExecuteMsg::DistributeRewards {
+                amount: Uint128::from(3000u64),
+            },
+        )
+        .unwrap();
+
+        assert_eq!(Uint128::from(1000u64), query_staker(deps.as_ref(), "alice").pending_rewards);
+        assert_eq!(Uint128::from(3000u64), query_staker(deps.as_ref(), "bob").pending_rewards);
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("alice", &coins(0, "uusd")),
+            ExecuteMsg::ClaimRewards {},
+        )
+        .unwrap();
+        assert_eq!(1, res.messages.len());
+        assert_eq!(Uint128::zero(), query_staker(deps.as_ref(), "alice").pending_rewards);
+
+        let res = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), ExecuteMsg::ClaimRewards {});
+        match res {
+            Err(ContractError::NoRewards {}) => {}
+            _ => panic!("Must return no rewards error"),
+        }
+    }
+}